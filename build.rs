@@ -4,6 +4,12 @@
 use std::fs;
 use std::path::Path;
 
+#[allow(dead_code)]
+#[path = "src/asset_pattern.rs"]
+mod asset_pattern;
+
+use asset_pattern::{ARCH_NAMES, AssetPattern, OS_NAMES, validate_aliases};
+
 fn main() {
     let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
     let binaries_dir = Path::new(&manifest_dir).join("binaries");
@@ -19,6 +25,9 @@ fn main() {
                 let abs_path = path.canonicalize().unwrap();
                 let abs_str = abs_path.to_str().unwrap().replace('\\', "/");
                 println!("cargo:rerun-if-changed={}", abs_str);
+                if let Err(e) = validate_config(&fs::read_to_string(&path).unwrap()) {
+                    panic!("Invalid binary config {}: {e}", path.display());
+                }
                 entries.push(format!("include_str!(\"{}\")", abs_str));
             }
         }
@@ -34,3 +43,47 @@ fn main() {
     );
     fs::write(dest_path, content).unwrap();
 }
+
+/// Validates the asset pattern and alias tables of a binary config, so that a malformed config
+/// fails the build instead of failing installs at runtime
+fn validate_config(content: &str) -> Result<(), String> {
+    let table: toml::Table = content.parse().map_err(|e| format!("{e}"))?;
+
+    if let Some(pattern) = table.get("asset_pattern") {
+        let pattern = pattern
+            .as_str()
+            .ok_or_else(|| "`asset_pattern` must be a string".to_string())?;
+        if !pattern.is_empty() {
+            AssetPattern::parse(pattern)?;
+        }
+    }
+
+    for (field, known) in [("os_aliases", OS_NAMES), ("arch_aliases", ARCH_NAMES)] {
+        let Some(aliases) = table.get(field) else {
+            continue;
+        };
+        let aliases = aliases
+            .as_table()
+            .ok_or_else(|| format!("`{field}` must be a table"))?;
+        let mut entries = vec![];
+        for (key, value) in aliases {
+            let names = value
+                .as_array()
+                .and_then(|names| {
+                    names
+                        .iter()
+                        .map(|name| name.as_str().map(str::to_string))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| format!("`{field}.{key}` must be a list of strings"))?;
+            entries.push((key.as_str(), names));
+        }
+        validate_aliases(
+            field,
+            known,
+            entries.iter().map(|(key, names)| (*key, names.as_slice())),
+        )?;
+    }
+
+    Ok(())
+}
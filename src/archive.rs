@@ -0,0 +1,419 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reading release archives. The format is detected from the file content, so that it does not
+//! depend on how an upstream project names its release assets.
+
+use anyhow::{Context, Error, anyhow, bail};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use tar::Archive;
+use zip::ZipArchive;
+
+#[cfg(not(windows))]
+use std::fs::set_permissions;
+#[cfg(not(windows))]
+use std::os::unix::fs::PermissionsExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    TarXz,
+    TarZst,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Detects the archive format from the magic bytes at the start of the file
+    pub fn detect(path: &Path) -> Result<Self, Error> {
+        let mut file = File::open(path)
+            .with_context(|| format!("Cannot open archive file {}", path.display()))?;
+        let mut magic = [0u8; 6];
+        let read = read_prefix(&mut file, &mut magic)
+            .with_context(|| format!("Cannot read archive file {}", path.display()))?;
+        Self::from_magic(&magic[..read])
+            .ok_or_else(|| anyhow!("Unsupported archive format for {}", path.display()))
+    }
+
+    fn from_magic(magic: &[u8]) -> Option<Self> {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::TarGz)
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::TarXz)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::TarZst)
+        } else if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+fn read_prefix(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+/// A release archive in any of the supported formats
+pub struct ReleaseArchive {
+    path: PathBuf,
+    format: ArchiveFormat,
+}
+
+impl ReleaseArchive {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            format: ArchiveFormat::detect(path)?,
+        })
+    }
+
+    pub fn format(&self) -> ArchiveFormat {
+        self.format
+    }
+
+    /// Extracts the first file entry whose file name is `file_name`, wherever it is in the
+    /// archive, to `output_path`. Returns false if there is no such entry.
+    pub fn extract_file(&self, file_name: &str, output_path: &Path) -> Result<bool, Error> {
+        match self.format {
+            ArchiveFormat::Zip => {
+                let mut archive = self.zip()?;
+                for i in 0..archive.len() {
+                    let mut entry = archive.by_index(i).with_context(|| {
+                        format!(
+                            "Cannot read entry at index {i} from archive {}",
+                            self.path.display()
+                        )
+                    })?;
+                    let name = Path::new(entry.name()).file_name().and_then(|n| n.to_str());
+                    if entry.is_file() && name == Some(file_name) {
+                        let mode = entry.unix_mode();
+                        write_entry(&mut entry, output_path, mode)?;
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            _ => {
+                let mut archive = self.tar()?;
+                for entry in archive
+                    .entries()
+                    .with_context(|| format!("Cannot read archive {}", self.path.display()))?
+                {
+                    let mut entry = entry.with_context(|| {
+                        format!("Cannot read entry from archive {}", self.path.display())
+                    })?;
+                    let entry_path = entry
+                        .path()
+                        .with_context(|| {
+                            format!(
+                                "Cannot read entry path from archive {}",
+                                self.path.display()
+                            )
+                        })?
+                        .into_owned();
+                    if entry.header().entry_type().is_file()
+                        && entry_path.file_name().and_then(|n| n.to_str()) == Some(file_name)
+                    {
+                        let mode = entry.header().mode().ok();
+                        write_entry(&mut entry, output_path, mode)?;
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Extracts all entries into `destination`. Entries with absolute paths or paths that
+    /// escape the destination are rejected.
+    pub fn unpack(&self, destination: &Path) -> Result<(), Error> {
+        match self.format {
+            ArchiveFormat::Zip => {
+                let mut archive = self.zip()?;
+                for i in 0..archive.len() {
+                    let mut entry = archive.by_index(i).with_context(|| {
+                        format!(
+                            "Cannot read entry at index {i} from archive {}",
+                            self.path.display()
+                        )
+                    })?;
+                    let output_path = destination.join(safe_entry_path(Path::new(entry.name()))?);
+                    if entry.is_dir() {
+                        std::fs::create_dir_all(&output_path).with_context(|| {
+                            format!("Cannot create directory {}", output_path.display())
+                        })?;
+                    } else if entry.is_symlink() {
+                        bail!("Unsupported symlink {} in archive", entry.name());
+                    } else {
+                        let mode = entry.unix_mode();
+                        write_entry(&mut entry, &output_path, mode)?;
+                    }
+                }
+            }
+            _ => {
+                std::fs::create_dir_all(destination).with_context(|| {
+                    format!("Cannot create directory {}", destination.display())
+                })?;
+                let mut archive = self.tar()?;
+                for entry in archive
+                    .entries()
+                    .with_context(|| format!("Cannot read archive {}", self.path.display()))?
+                {
+                    let mut entry = entry.with_context(|| {
+                        format!("Cannot read entry from archive {}", self.path.display())
+                    })?;
+                    let entry_path = entry
+                        .path()
+                        .with_context(|| {
+                            format!(
+                                "Cannot read entry path from archive {}",
+                                self.path.display()
+                            )
+                        })?
+                        .into_owned();
+                    safe_entry_path(&entry_path)?;
+                    if let Some(link) = entry.link_name().with_context(|| {
+                        format!("Cannot read link from archive {}", self.path.display())
+                    })? {
+                        safe_entry_path(&link)?;
+                    }
+                    // Unlike `unpack`, `unpack_in` resolves hard link targets in the
+                    // destination rather than in the working directory
+                    let unpacked = entry.unpack_in(destination).with_context(|| {
+                        format!(
+                            "Cannot unpack {} from archive {}",
+                            entry_path.display(),
+                            self.path.display()
+                        )
+                    })?;
+                    if !unpacked {
+                        bail!("Unsafe path {} in archive", entry_path.display());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn zip(&self) -> Result<ZipArchive<BufReader<File>>, Error> {
+        let file = File::open(&self.path)
+            .with_context(|| format!("Cannot open archive file {}", self.path.display()))?;
+        ZipArchive::new(BufReader::new(file))
+            .with_context(|| format!("Cannot read zip archive {}", self.path.display()))
+    }
+
+    fn tar(&self) -> Result<Archive<Box<dyn Read>>, Error> {
+        let file = BufReader::new(
+            File::open(&self.path)
+                .with_context(|| format!("Cannot open archive file {}", self.path.display()))?,
+        );
+        let reader: Box<dyn Read> = match self.format {
+            ArchiveFormat::TarGz => Box::new(GzDecoder::new(file)),
+            ArchiveFormat::TarXz => Box::new(xz2::read::XzDecoder::new(file)),
+            ArchiveFormat::TarZst => Box::new(
+                zstd::stream::read::Decoder::with_buffer(file)
+                    .with_context(|| format!("Cannot read archive {}", self.path.display()))?,
+            ),
+            ArchiveFormat::Zip => unreachable!("zip archives are not tar archives"),
+        };
+        Ok(Archive::new(reader))
+    }
+}
+
+/// Returns the relative path of an archive entry, rejecting absolute paths and paths that could
+/// escape the extraction directory
+fn safe_entry_path(path: &Path) -> Result<PathBuf, Error> {
+    let mut safe = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => safe.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                bail!("Unsafe path {} in archive", path.display())
+            }
+        }
+    }
+    Ok(safe)
+}
+
+fn write_entry(reader: &mut impl Read, output_path: &Path, mode: Option<u32>) -> Result<(), Error> {
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Cannot create directory {}", parent.display()))?;
+    }
+    let mut output_file = File::create(output_path)
+        .with_context(|| format!("Cannot create file {}", output_path.display()))?;
+    std::io::copy(reader, &mut output_file)
+        .with_context(|| format!("Cannot write file {}", output_path.display()))?;
+
+    // Retrieve and apply the original file permissions on Unix-like systems
+    #[cfg(not(windows))]
+    if let Some(mode) = mode {
+        set_permissions(output_path, PermissionsExt::from_mode(mode)).with_context(|| {
+            format!(
+                "Cannot apply original file permissions to {}",
+                output_path.display()
+            )
+        })?;
+    }
+    #[cfg(windows)]
+    let _ = mode;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn tar_bytes(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *content).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn write_archive(dir: &Path, name: &str, format: ArchiveFormat, tar: &[u8]) -> PathBuf {
+        let path = dir.join(name);
+        let file = File::create(&path).unwrap();
+        match format {
+            ArchiveFormat::TarGz => {
+                let mut encoder = flate2::write::GzEncoder::new(file, Default::default());
+                encoder.write_all(tar).unwrap();
+                encoder.finish().unwrap();
+            }
+            ArchiveFormat::TarXz => {
+                let mut encoder = xz2::write::XzEncoder::new(file, 6);
+                encoder.write_all(tar).unwrap();
+                encoder.finish().unwrap();
+            }
+            ArchiveFormat::TarZst => {
+                let mut encoder = zstd::stream::write::Encoder::new(file, 0).unwrap();
+                encoder.write_all(tar).unwrap();
+                encoder.finish().unwrap();
+            }
+            ArchiveFormat::Zip => {
+                let mut zip = zip::ZipWriter::new(file);
+                zip.start_file("release/sui", zip::write::SimpleFileOptions::default())
+                    .unwrap();
+                zip.write_all(b"sui binary").unwrap();
+                zip.finish().unwrap();
+            }
+        }
+        path
+    }
+
+    #[test]
+    fn extracts_binary_from_all_formats() {
+        let dir = TempDir::new().unwrap();
+        let tar = tar_bytes(&[("release/sui", b"sui binary")]);
+        for format in [
+            ArchiveFormat::TarGz,
+            ArchiveFormat::TarXz,
+            ArchiveFormat::TarZst,
+            ArchiveFormat::Zip,
+        ] {
+            // The file name does not tell the format
+            let path = write_archive(dir.path(), "sui.archive", format, &tar);
+            let archive = ReleaseArchive::open(&path).unwrap();
+            assert_eq!(archive.format(), format);
+
+            let output = dir.path().join(format!("{format:?}"));
+            assert!(archive.extract_file("sui", &output).unwrap());
+            assert_eq!(std::fs::read(&output).unwrap(), b"sui binary");
+            assert!(!archive.extract_file("walrus", &output).unwrap());
+        }
+    }
+
+    #[test]
+    fn detect_rejects_unknown_formats() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("sui.tgz");
+        std::fs::write(&path, b"not an archive").unwrap();
+        assert!(ReleaseArchive::open(&path).is_err());
+    }
+
+    #[test]
+    fn unpack_links_hard_links_inside_the_destination() {
+        let dir = TempDir::new().unwrap();
+        let destination = dir.path().join("out");
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "bin/sui", &b"sui"[..])
+            .unwrap();
+        let mut link = tar::Header::new_gnu();
+        link.set_entry_type(tar::EntryType::Link);
+        link.set_size(0);
+        builder.append_link(&mut link, "sui", "bin/sui").unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        // The link target only exists in the destination, not in the working directory
+        let path = write_archive(dir.path(), "links.tgz", ArchiveFormat::TarGz, &tar);
+        ReleaseArchive::open(&path)
+            .unwrap()
+            .unpack(&destination)
+            .unwrap();
+        assert_eq!(std::fs::read(destination.join("sui")).unwrap(), b"sui");
+    }
+
+    #[test]
+    fn unpack_rejects_entries_escaping_the_destination() {
+        let dir = TempDir::new().unwrap();
+        let destination = dir.path().join("out");
+
+        let tar = tar_bytes(&[("bin/suiup", b"suiup")]);
+        let path = write_archive(dir.path(), "ok.tgz", ArchiveFormat::TarGz, &tar);
+        ReleaseArchive::open(&path)
+            .unwrap()
+            .unpack(&destination)
+            .unwrap();
+        assert!(destination.join("bin/suiup").exists());
+
+        // The tar builder refuses unsafe paths, so write the header name directly
+        let mut header = tar::Header::new_gnu();
+        header.as_gnu_mut().unwrap().name[..13].copy_from_slice(b"../../escaped");
+        header.set_size(1);
+        header.set_cksum();
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.append(&header, &b"x"[..]).unwrap();
+        let tar = builder.into_inner().unwrap();
+        let path = write_archive(dir.path(), "bad.tgz", ArchiveFormat::TarGz, &tar);
+        let err = ReleaseArchive::open(&path)
+            .unwrap()
+            .unpack(&destination)
+            .unwrap_err();
+        assert!(err.to_string().contains("Unsafe path"));
+        assert!(!dir.path().join("escaped").exists());
+    }
+
+    #[test]
+    fn safe_entry_path_rejects_absolute_paths() {
+        assert!(safe_entry_path(Path::new("/etc/passwd")).is_err());
+        assert!(safe_entry_path(Path::new("a/../../b")).is_err());
+        assert_eq!(
+            safe_entry_path(Path::new("./a/b")).unwrap(),
+            PathBuf::from("a/b")
+        );
+    }
+}
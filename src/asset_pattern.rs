@@ -0,0 +1,269 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Asset name templates used in the binary TOML configs to select release assets, e.g.
+//! `{name}-{network}-{version}-{os}-{arch}.tgz`.
+//!
+//! This module only depends on `std`, as it is also compiled into the build script to validate
+//! the embedded configs.
+
+/// Placeholders that can be used in an asset pattern
+pub const PLACEHOLDERS: &[&str] = &["name", "network", "version", "os", "arch"];
+
+/// OS names as detected by suiup, which are the keys of the `os_aliases` tables
+pub const OS_NAMES: &[&str] = &["ubuntu", "macos", "windows"];
+
+/// Architecture names as detected by suiup, which are the keys of the `arch_aliases` tables
+pub const ARCH_NAMES: &[&str] = &["x86_64", "aarch64", "arm64"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(&'static str),
+    /// `*`, matches any text, including none
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetPattern {
+    segments: Vec<Segment>,
+}
+
+/// The values to match the placeholders of a pattern against. A placeholder with no candidate
+/// values matches any non-empty text.
+#[derive(Debug, Clone, Default)]
+pub struct AssetValues {
+    pub name: Vec<String>,
+    pub network: Vec<String>,
+    pub version: Vec<String>,
+    pub os: Vec<String>,
+    pub arch: Vec<String>,
+}
+
+impl AssetValues {
+    fn get(&self, placeholder: &str) -> &[String] {
+        match placeholder {
+            "name" => &self.name,
+            "network" => &self.network,
+            "version" => &self.version,
+            "os" => &self.os,
+            "arch" => &self.arch,
+            _ => &[],
+        }
+    }
+}
+
+impl AssetPattern {
+    /// Parses and validates an asset pattern
+    pub fn parse(template: &str) -> Result<Self, String> {
+        if template.trim().is_empty() {
+            return Err("asset pattern is empty".to_string());
+        }
+        if template.contains('/') || template.contains('\\') {
+            return Err(format!(
+                "asset pattern `{template}` must be a file name, not a path"
+            ));
+        }
+
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => {
+                                return Err(format!(
+                                    "asset pattern `{template}` has an unclosed `{{`"
+                                ));
+                            }
+                            Some(c) => placeholder.push(c),
+                        }
+                    }
+                    let placeholder = PLACEHOLDERS
+                        .iter()
+                        .find(|p| **p == placeholder)
+                        .ok_or_else(|| {
+                            format!(
+                                "asset pattern `{template}` has an unknown placeholder `{{{placeholder}}}`. Supported: {}",
+                                PLACEHOLDERS
+                                    .iter()
+                                    .map(|p| format!("{{{p}}}"))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            )
+                        })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                }
+                '}' => return Err(format!("asset pattern `{template}` has an unmatched `}}`")),
+                '*' => {
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Wildcard);
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        for required in ["os", "arch"] {
+            if !segments.contains(&Segment::Placeholder(required)) {
+                return Err(format!(
+                    "asset pattern `{template}` must contain the `{{{required}}}` placeholder"
+                ));
+            }
+        }
+        // Without a separator, it is ambiguous where one value ends and the next one starts
+        if segments.windows(2).any(|w| {
+            matches!(w[0], Segment::Placeholder(_)) && matches!(w[1], Segment::Placeholder(_))
+        }) {
+            return Err(format!(
+                "asset pattern `{template}` has placeholders that are not separated by text"
+            ));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Checks whether an asset name matches this pattern for the given values
+    pub fn matches(&self, asset_name: &str, values: &AssetValues) -> bool {
+        matches_segments(&self.segments, asset_name, values)
+    }
+}
+
+fn matches_segments(segments: &[Segment], input: &str, values: &AssetValues) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        return input.is_empty();
+    };
+    match segment {
+        Segment::Literal(literal) => input
+            .strip_prefix(literal.as_str())
+            .is_some_and(|input| matches_segments(rest, input, values)),
+        Segment::Placeholder(placeholder) => {
+            let candidates = values.get(placeholder);
+            if candidates.is_empty() {
+                (1..=input.len())
+                    .filter(|i| input.is_char_boundary(*i))
+                    .any(|i| matches_segments(rest, &input[i..], values))
+            } else {
+                candidates.iter().any(|candidate| {
+                    !candidate.is_empty()
+                        && input
+                            .strip_prefix(candidate.as_str())
+                            .is_some_and(|input| matches_segments(rest, input, values))
+                })
+            }
+        }
+        Segment::Wildcard => (0..=input.len())
+            .filter(|i| input.is_char_boundary(*i))
+            .any(|i| matches_segments(rest, &input[i..], values)),
+    }
+}
+
+/// Validates an `os_aliases` or `arch_aliases` table, given as its entries
+pub fn validate_aliases<'a>(
+    field: &str,
+    known: &[&str],
+    entries: impl IntoIterator<Item = (&'a str, &'a [String])>,
+) -> Result<(), String> {
+    for (key, aliases) in entries {
+        if !known.contains(&key) {
+            return Err(format!(
+                "`{field}` has an unknown key `{key}`. Supported: {}",
+                known.join(", ")
+            ));
+        }
+        if aliases.is_empty() || aliases.iter().any(|a| a.trim().is_empty()) {
+            return Err(format!(
+                "`{field}.{key}` must be a non-empty list of non-empty names"
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(network: &str, version: &str, os: &[&str], arch: &[&str]) -> AssetValues {
+        AssetValues {
+            name: vec!["sui".to_string()],
+            network: vec![network.to_string()],
+            version: if version.is_empty() {
+                vec![]
+            } else {
+                vec![version.to_string()]
+            },
+            os: os.iter().map(|s| s.to_string()).collect(),
+            arch: arch.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn parse_rejects_malformed_patterns() {
+        assert!(AssetPattern::parse("{name}-{os}-{arch}.tgz").is_ok());
+        assert!(AssetPattern::parse("").is_err());
+        assert!(AssetPattern::parse("{name}-{os-{arch}").is_err());
+        assert!(AssetPattern::parse("{name}-{os}}-{arch}").is_err());
+        assert!(AssetPattern::parse("{name}-{platform}-{arch}").is_err());
+        assert!(AssetPattern::parse("{name}-{os}.tgz").is_err());
+        assert!(AssetPattern::parse("{name}-{os}{arch}").is_err());
+        assert!(AssetPattern::parse("bin/{name}-{os}-{arch}").is_err());
+    }
+
+    #[test]
+    fn matches_with_values_and_aliases() {
+        let pattern = AssetPattern::parse("{name}-{network}-{version}-{os}-{arch}.tgz").unwrap();
+        let asset = "sui-testnet-v1.44.2-ubuntu-x86_64.tgz";
+
+        assert!(pattern.matches(
+            asset,
+            &values("testnet", "v1.44.2", &["ubuntu"], &["x86_64"])
+        ));
+        assert!(pattern.matches(
+            asset,
+            &values("testnet", "", &["linux", "ubuntu"], &["x86_64"])
+        ));
+        assert!(!pattern.matches(asset, &values("devnet", "", &["ubuntu"], &["x86_64"])));
+        assert!(!pattern.matches(
+            asset,
+            &values("testnet", "v1.44.3", &["ubuntu"], &["x86_64"])
+        ));
+        assert!(!pattern.matches(asset, &values("testnet", "", &["ubuntu"], &["aarch64"])));
+        assert!(!pattern.matches(
+            "sui-testnet-v1.44.2-ubuntu-x86_64.zip",
+            &values("testnet", "", &["ubuntu"], &["x86_64"])
+        ));
+    }
+
+    #[test]
+    fn wildcard_matches_any_suffix() {
+        let pattern = AssetPattern::parse("mvr-{os}-{arch}*").unwrap();
+        let values = AssetValues {
+            os: vec!["windows".to_string()],
+            arch: vec!["x86_64".to_string()],
+            ..Default::default()
+        };
+        assert!(pattern.matches("mvr-windows-x86_64.exe", &values));
+        assert!(pattern.matches("mvr-windows-x86_64", &values));
+        assert!(!pattern.matches("mvr-ubuntu-x86_64", &values));
+    }
+
+    #[test]
+    fn validate_aliases_rejects_unknown_keys_and_empty_lists() {
+        let linux = vec!["ubuntu".to_string(), "linux".to_string()];
+        assert!(validate_aliases("os_aliases", OS_NAMES, [("ubuntu", linux.as_slice())]).is_ok());
+        assert!(validate_aliases("os_aliases", OS_NAMES, [("linux", linux.as_slice())]).is_err());
+        assert!(validate_aliases("os_aliases", OS_NAMES, [("ubuntu", [].as_slice())]).is_err());
+    }
+}
@@ -0,0 +1,603 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inspection of installed binaries: the executable format and architectures from the ELF,
+//! Mach-O or PE header, the glibc version an ELF binary requires, and whether a binary starts
+//! and reports its version.
+
+use anyhow::{Context, Error, bail};
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryFormat {
+    Elf,
+    MachO,
+    Pe,
+    /// A script started through a `#!` interpreter, which runs on any architecture
+    Script,
+}
+
+impl BinaryFormat {
+    /// The executable format of the host OS
+    pub fn native() -> Option<Self> {
+        if cfg!(target_os = "linux") {
+            Some(BinaryFormat::Elf)
+        } else if cfg!(target_os = "macos") {
+            Some(BinaryFormat::MachO)
+        } else if cfg!(windows) {
+            Some(BinaryFormat::Pe)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for BinaryFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            BinaryFormat::Elf => write!(f, "ELF (Linux)"),
+            BinaryFormat::MachO => write!(f, "Mach-O (macOS)"),
+            BinaryFormat::Pe => write!(f, "PE (Windows)"),
+            BinaryFormat::Script => write!(f, "script"),
+        }
+    }
+}
+
+/// A glibc version like 2.36
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GlibcVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl GlibcVersion {
+    /// Parses `2.36`, as well as the `GLIBC_2.36` symbol version names
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.strip_prefix("GLIBC_").unwrap_or(version);
+        let mut parts = version.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        Some(Self { major, minor })
+    }
+}
+
+impl Display for GlibcVersion {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryInfo {
+    pub format: BinaryFormat,
+    /// The architectures the binary contains code for, more than one for macOS universal
+    /// binaries. Empty for scripts.
+    pub archs: Vec<String>,
+    /// The newest glibc symbol version an ELF binary links against
+    pub glibc: Option<GlibcVersion>,
+}
+
+impl BinaryInfo {
+    /// Reads the header of an executable
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let mut file =
+            File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+        let mut header = [0u8; 64];
+        let read = read_prefix(&mut file, &mut header)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        let header = &header[..read];
+
+        if header.starts_with(b"#!") {
+            Ok(Self {
+                format: BinaryFormat::Script,
+                archs: vec![],
+                glibc: None,
+            })
+        } else if header.starts_with(b"\x7fELF") {
+            read_elf(&mut file)
+                .with_context(|| format!("Cannot parse the ELF header of {}", path.display()))
+        } else if let Some(archs) = mach_o_archs(&mut file, header)? {
+            Ok(Self {
+                format: BinaryFormat::MachO,
+                archs,
+                glibc: None,
+            })
+        } else if header.starts_with(b"MZ") {
+            read_pe(&mut file)
+                .with_context(|| format!("Cannot parse the PE header of {}", path.display()))
+        } else {
+            bail!("{} is not an executable", path.display())
+        }
+    }
+
+    pub fn supports_arch(&self, arch: &str) -> bool {
+        self.format == BinaryFormat::Script || self.archs.iter().any(|a| a == arch)
+    }
+}
+
+/// The glibc version of the host, `None` when it is not a glibc based Linux system or the
+/// version cannot be determined.
+pub fn host_glibc_version() -> Option<GlibcVersion> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let output = Command::new("getconf")
+        .arg("GNU_LIBC_VERSION")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Prints `glibc 2.36`
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .last()
+        .and_then(GlibcVersion::parse)
+}
+
+/// The result of running a binary with `--version`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionOutput {
+    Exited {
+        success: bool,
+        stdout: String,
+        stderr: String,
+    },
+    TimedOut,
+}
+
+/// Runs `binary --version`, killing it if it does not exit within `timeout`.
+pub fn run_version(binary: &Path, timeout: Duration) -> Result<VersionOutput, Error> {
+    let mut child = Command::new(binary)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Cannot run {}", binary.display()))?;
+    // The pipes are drained while the binary runs, so it cannot block on a full pipe
+    let stdout = drain(child.stdout.take().expect("stdout is piped"));
+    let stderr = drain(child.stderr.take().expect("stderr is piped"));
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(VersionOutput::TimedOut);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+
+    // A process started by the binary can keep the pipes open after it exits, so the output
+    // is only waited for until the timeout
+    let deadline = (start + timeout).max(Instant::now() + Duration::from_millis(100));
+    Ok(VersionOutput::Exited {
+        success: status.success(),
+        stdout: stdout.output(deadline),
+        stderr: stderr.output(deadline),
+    })
+}
+
+/// The output of a pipe that is read on a separate thread
+struct DrainedPipe {
+    output: Arc<Mutex<Vec<u8>>>,
+    done: mpsc::Receiver<()>,
+}
+
+impl DrainedPipe {
+    /// The output read until the pipe was closed, or until the deadline
+    fn output(&self, deadline: Instant) -> String {
+        let _ = self
+            .done
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()));
+        String::from_utf8_lossy(&self.output.lock().expect("output lock")).to_string()
+    }
+}
+
+fn drain(mut pipe: impl Read + Send + 'static) -> DrainedPipe {
+    let output = Arc::new(Mutex::new(vec![]));
+    let (done_tx, done) = mpsc::channel();
+    let thread_output = Arc::clone(&output);
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        while let Ok(n) = pipe.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            thread_output
+                .lock()
+                .expect("output lock")
+                .extend_from_slice(&buf[..n]);
+        }
+        let _ = done_tx.send(());
+    });
+    DrainedPipe { output, done }
+}
+
+fn read_prefix(file: &mut File, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read(&mut buf[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+/// Largest header table or section that is read from a binary. Version sections are a few
+/// kilobytes, so anything larger comes from a corrupt file.
+const MAX_READ: u64 = 16 * 1024 * 1024;
+
+/// Reads `len` bytes at `offset`. The range comes from the headers of the file, so it is
+/// checked against the file length before anything is allocated.
+fn read_at(file: &mut File, offset: u64, len: u64) -> Result<Vec<u8>, Error> {
+    let file_len = file.metadata()?.len();
+    if len > MAX_READ || offset.checked_add(len).is_none_or(|end| end > file_len) {
+        bail!("Invalid range of {len} bytes at offset {offset} in a file of {file_len} bytes");
+    }
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = vec![0u8; len as usize];
+    file.read_exact(&mut buf)
+        .with_context(|| format!("Cannot read {len} bytes at offset {offset}"))?;
+    Ok(buf)
+}
+
+/// Reads integers with the byte order of an ELF file
+#[derive(Clone, Copy)]
+struct Endian {
+    little: bool,
+}
+
+impl Endian {
+    fn u16(self, buf: &[u8], at: usize) -> u16 {
+        let bytes = [buf[at], buf[at + 1]];
+        if self.little {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    }
+
+    fn u32(self, buf: &[u8], at: usize) -> u32 {
+        let bytes = buf[at..at + 4].try_into().expect("4 bytes");
+        if self.little {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    }
+
+    fn u64(self, buf: &[u8], at: usize) -> u64 {
+        let bytes = buf[at..at + 8].try_into().expect("8 bytes");
+        if self.little {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        }
+    }
+}
+
+/// A section of an ELF file, with the fields needed to find the version requirements
+struct ElfSection {
+    kind: u32,
+    offset: u64,
+    size: u64,
+    link: u32,
+}
+
+const SHT_GNU_VERNEED: u32 = 0x6fff_fffe;
+
+fn read_elf(file: &mut File) -> Result<BinaryInfo, Error> {
+    let header = read_at(file, 0, 64)?;
+    let is_64 = match header[4] {
+        1 => false,
+        2 => true,
+        class => bail!("Unknown ELF class {class}"),
+    };
+    let endian = Endian {
+        little: header[5] == 1,
+    };
+    let arch = match endian.u16(&header, 18) {
+        3 => "x86".to_string(),
+        40 => "arm".to_string(),
+        62 => "x86_64".to_string(),
+        183 => "aarch64".to_string(),
+        243 => "riscv64".to_string(),
+        machine => format!("unknown ELF machine {machine}"),
+    };
+
+    let (shoff, shentsize, shnum) = if is_64 {
+        (
+            endian.u64(&header, 0x28),
+            endian.u16(&header, 0x3a),
+            endian.u16(&header, 0x3c),
+        )
+    } else {
+        (
+            u64::from(endian.u32(&header, 0x20)),
+            endian.u16(&header, 0x2e),
+            endian.u16(&header, 0x30),
+        )
+    };
+    if shnum > 0 && shentsize < if is_64 { 64 } else { 40 } {
+        bail!("Invalid section header size {shentsize}");
+    }
+    let table = read_at(file, shoff, u64::from(shentsize) * u64::from(shnum))?;
+    let sections: Vec<ElfSection> = table
+        .chunks_exact(usize::from(shentsize).max(1))
+        .map(|entry| {
+            if is_64 {
+                ElfSection {
+                    kind: endian.u32(entry, 4),
+                    offset: endian.u64(entry, 24),
+                    size: endian.u64(entry, 32),
+                    link: endian.u32(entry, 40),
+                }
+            } else {
+                ElfSection {
+                    kind: endian.u32(entry, 4),
+                    offset: u64::from(endian.u32(entry, 16)),
+                    size: u64::from(endian.u32(entry, 20)),
+                    link: endian.u32(entry, 24),
+                }
+            }
+        })
+        .collect();
+
+    let mut glibc = None;
+    for verneed in sections.iter().filter(|s| s.kind == SHT_GNU_VERNEED) {
+        let Some(strtab) = sections.get(verneed.link as usize) else {
+            continue;
+        };
+        let needed = read_at(file, verneed.offset, verneed.size)?;
+        let strings = read_at(file, strtab.offset, strtab.size)?;
+        for name in version_requirements(&needed, &strings, endian) {
+            glibc = glibc.max(GlibcVersion::parse(&name));
+        }
+    }
+
+    Ok(BinaryInfo {
+        format: BinaryFormat::Elf,
+        archs: vec![arch],
+        glibc,
+    })
+}
+
+/// The symbol version names, like `GLIBC_2.34`, in a `.gnu.version_r` section
+fn version_requirements(needed: &[u8], strings: &[u8], endian: Endian) -> Vec<String> {
+    let string_at = |offset: u32| -> Option<String> {
+        let bytes = strings.get(offset as usize..)?;
+        let end = bytes.iter().position(|b| *b == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+    };
+
+    let mut names = vec![];
+    let mut offset = 0usize;
+    // Elf_Verneed is 16 bytes and Elf_Vernaux 16 bytes, for both 32 and 64 bit files
+    while offset + 16 <= needed.len() {
+        let count = endian.u16(needed, offset + 2);
+        let mut aux = offset + endian.u32(needed, offset + 8) as usize;
+        for _ in 0..count {
+            if aux + 16 > needed.len() {
+                break;
+            }
+            if let Some(name) = string_at(endian.u32(needed, aux + 8)) {
+                names.push(name);
+            }
+            match endian.u32(needed, aux + 12) {
+                0 => break,
+                next => aux += next as usize,
+            }
+        }
+        match endian.u32(needed, offset + 12) {
+            0 => break,
+            next => offset += next as usize,
+        }
+    }
+    names
+}
+
+fn mach_o_arch(cpu_type: u32) -> String {
+    match cpu_type {
+        7 => "x86".to_string(),
+        12 => "arm".to_string(),
+        0x0100_0007 => "x86_64".to_string(),
+        0x0100_000c => "aarch64".to_string(),
+        cpu_type => format!("unknown Mach-O CPU type {cpu_type:#x}"),
+    }
+}
+
+/// The architectures of a thin or universal Mach-O binary, `None` for other files
+fn mach_o_archs(file: &mut File, header: &[u8]) -> Result<Option<Vec<String>>, Error> {
+    if header.len() < 8 {
+        return Ok(None);
+    }
+    let magic_le = u32::from_le_bytes(header[..4].try_into().expect("4 bytes"));
+    if magic_le == 0xfeed_face || magic_le == 0xfeed_facf {
+        let cpu_type = u32::from_le_bytes(header[4..8].try_into().expect("4 bytes"));
+        return Ok(Some(vec![mach_o_arch(cpu_type)]));
+    }
+
+    let magic_be = u32::from_be_bytes(header[..4].try_into().expect("4 bytes"));
+    if magic_be != 0xcafe_babe {
+        return Ok(None);
+    }
+    let count = u32::from_be_bytes(header[4..8].try_into().expect("4 bytes"));
+    // Java class files share the magic number, their version is far above any arch count
+    if count == 0 || count > 32 {
+        return Ok(None);
+    }
+    let table = read_at(file, 8, u64::from(count) * 20)?;
+    Ok(Some(
+        table
+            .chunks_exact(20)
+            .map(|entry| mach_o_arch(u32::from_be_bytes(entry[..4].try_into().expect("4 bytes"))))
+            .collect(),
+    ))
+}
+
+fn read_pe(file: &mut File) -> Result<BinaryInfo, Error> {
+    let dos_header = read_at(file, 0, 64)?;
+    let pe_offset = u32::from_le_bytes(dos_header[0x3c..0x40].try_into().expect("4 bytes"));
+    let pe_header = read_at(file, u64::from(pe_offset), 6)?;
+    if &pe_header[..4] != b"PE\0\0" {
+        bail!("Missing PE signature");
+    }
+    let arch = match u16::from_le_bytes([pe_header[4], pe_header[5]]) {
+        0x014c => "x86".to_string(),
+        0x8664 => "x86_64".to_string(),
+        0xaa64 => "aarch64".to_string(),
+        machine => format!("unknown PE machine {machine:#x}"),
+    };
+    Ok(BinaryInfo {
+        format: BinaryFormat::Pe,
+        archs: vec![arch],
+        glibc: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_temp(bytes: &[u8]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(bytes).unwrap();
+        file
+    }
+
+    #[test]
+    fn glibc_versions_are_ordered() {
+        let v2_17 = GlibcVersion::parse("GLIBC_2.17").unwrap();
+        let v2_34 = GlibcVersion::parse("2.34").unwrap();
+        assert!(v2_17 < v2_34);
+        assert_eq!(v2_34.to_string(), "2.34");
+        assert_eq!(GlibcVersion::parse("GLIBC_PRIVATE"), None);
+    }
+
+    #[test]
+    fn reads_mach_o_and_pe_headers() {
+        let mut thin = vec![0xcf, 0xfa, 0xed, 0xfe];
+        thin.extend(0x0100_000cu32.to_le_bytes());
+        thin.resize(64, 0);
+        let info = BinaryInfo::read(write_temp(&thin).path()).unwrap();
+        assert_eq!(info.format, BinaryFormat::MachO);
+        assert_eq!(info.archs, vec!["aarch64"]);
+
+        let mut universal = 0xcafe_babeu32.to_be_bytes().to_vec();
+        universal.extend(2u32.to_be_bytes());
+        for cpu_type in [0x0100_0007u32, 0x0100_000c] {
+            universal.extend(cpu_type.to_be_bytes());
+            universal.extend([0u8; 16]);
+        }
+        let info = BinaryInfo::read(write_temp(&universal).path()).unwrap();
+        assert_eq!(info.archs, vec!["x86_64", "aarch64"]);
+        assert!(info.supports_arch("aarch64"));
+
+        let mut pe = b"MZ".to_vec();
+        pe.resize(0x3c, 0);
+        pe.extend(64u32.to_le_bytes());
+        pe.extend(b"PE\0\0");
+        pe.extend(0x8664u16.to_le_bytes());
+        let info = BinaryInfo::read(write_temp(&pe).path()).unwrap();
+        assert_eq!(info.format, BinaryFormat::Pe);
+        assert_eq!(info.archs, vec!["x86_64"]);
+
+        let info = BinaryInfo::read(write_temp(b"#!/bin/sh\necho sui\n").path()).unwrap();
+        assert_eq!(info.format, BinaryFormat::Script);
+        assert!(info.supports_arch("riscv64"));
+
+        assert!(BinaryInfo::read(write_temp(b"hello").path()).is_err());
+    }
+
+    #[test]
+    fn rejects_sections_outside_of_the_file() {
+        // A 64-bit little endian x86_64 ELF header with one version section that claims to be
+        // larger than the file
+        let mut elf = b"\x7fELF\x02\x01".to_vec();
+        elf.resize(64, 0);
+        elf[18..20].copy_from_slice(&62u16.to_le_bytes());
+        elf[0x28..0x30].copy_from_slice(&64u64.to_le_bytes());
+        elf[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        elf[0x3c..0x3e].copy_from_slice(&1u16.to_le_bytes());
+        let mut section = vec![0u8; 64];
+        section[4..8].copy_from_slice(&SHT_GNU_VERNEED.to_le_bytes());
+        section[24..32].copy_from_slice(&0u64.to_le_bytes());
+        section[32..40].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+        elf.extend(section);
+
+        let error = BinaryInfo::read(write_temp(&elf).path()).unwrap_err();
+        assert!(format!("{error:#}").contains("Invalid range"));
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    #[test]
+    fn reads_own_elf_header() {
+        let info = BinaryInfo::read(&std::env::current_exe().unwrap()).unwrap();
+        assert_eq!(info.format, BinaryFormat::Elf);
+        assert!(info.supports_arch(std::env::consts::ARCH));
+        let glibc = info.glibc.expect("linked against glibc");
+        if let Some(host) = host_glibc_version() {
+            assert!(glibc <= host);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_version_reports_output_and_timeout() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = |name: &str, body: &str| {
+            use std::os::unix::fs::PermissionsExt;
+            let path = dir.path().join(name);
+            std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+
+        let ok = script("ok", "echo \"sui 1.44.2\"");
+        assert_eq!(
+            run_version(&ok, Duration::from_secs(10)).unwrap(),
+            VersionOutput::Exited {
+                success: true,
+                stdout: "sui 1.44.2\n".to_string(),
+                stderr: String::new(),
+            }
+        );
+
+        let verbose = script("verbose", "head -c 200000 /dev/zero | tr '\\0' a");
+        let VersionOutput::Exited { stdout, .. } =
+            run_version(&verbose, Duration::from_secs(10)).unwrap()
+        else {
+            panic!("expected the binary to exit");
+        };
+        assert_eq!(stdout.len(), 200000);
+
+        let start = Instant::now();
+        let detaches = script("detaches", "sleep 10 &\necho \"sui 1.44.2\"");
+        let VersionOutput::Exited { stdout, .. } =
+            run_version(&detaches, Duration::from_secs(1)).unwrap()
+        else {
+            panic!("expected the binary to exit");
+        };
+        assert_eq!(stdout, "sui 1.44.2\n");
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let hangs = script("hangs", "exec sleep 10");
+        assert_eq!(
+            run_version(&hangs, Duration::from_millis(200)).unwrap(),
+            VersionOutput::TimedOut
+        );
+    }
+}
@@ -0,0 +1,281 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Size limit for the release archive cache.
+//!
+//! Every time an archive is downloaded or taken from the cache, its last access time is recorded
+//! in an index. When `SUIUP_MAX_CACHE_SIZE` is set, the least recently used archives are removed
+//! after each download until the cache fits in that size.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Error, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::fs_utils::{read_json_file, write_json_file};
+use crate::handlers::cleanup::format_file_size;
+use crate::lock::{download_lock_file, lock_cache, try_lock_download};
+use crate::paths::{cache_index_file, release_archive_dir};
+
+/// Environment variable with the maximum size of the release archive cache, e.g. `2GB`
+pub const MAX_CACHE_SIZE_ENV: &str = "SUIUP_MAX_CACHE_SIZE";
+
+/// Archives used this recently may be about to be extracted by another suiup process, so they
+/// are not evicted
+const IN_USE_SECS: u64 = 5 * 60;
+
+/// The last access time of each archive, in seconds since the Unix epoch, by file name
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct CacheIndex {
+    archives: BTreeMap<String, u64>,
+}
+
+impl CacheIndex {
+    /// Reads the index, starting over when it is missing or unreadable
+    fn read() -> Self {
+        let path = cache_index_file();
+        if !path.exists() {
+            return Self::default();
+        }
+        read_json_file(&path).unwrap_or_else(|e| {
+            eprintln!("Warning: {e}, the cache index is rebuilt");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        write_json_file(&cache_index_file(), self)
+    }
+}
+
+/// An archive in the release archive cache
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedArchive {
+    pub path: PathBuf,
+    pub size: u64,
+    pub last_access: u64,
+}
+
+/// Records that an archive in the cache was used and, when a maximum cache size is configured,
+/// removes the least recently used archives until the cache fits. The archive itself is kept.
+/// Failures only print a warning, as they do not affect the command that uses the archive.
+pub fn archive_used(archive: &Path) {
+    if let Err(e) = record_access(archive).and_then(|()| {
+        if let Some(max_size) = max_cache_size()? {
+            evict_to_size(max_size, Some(archive), false)?;
+        }
+        Ok(())
+    }) {
+        eprintln!("Warning: cannot update the release archive cache: {e:#}");
+    }
+}
+
+fn record_access(archive: &Path) -> Result<(), Error> {
+    let Some(file_name) = archive.file_name() else {
+        return Ok(());
+    };
+    let _lock = lock_cache()?;
+    let mut index = CacheIndex::read();
+    index
+        .archives
+        .insert(file_name.to_string_lossy().to_string(), now());
+    index.save()
+}
+
+/// The maximum cache size from the environment, if set
+pub fn max_cache_size() -> Result<Option<u64>, Error> {
+    match std::env::var(MAX_CACHE_SIZE_ENV) {
+        Ok(value) if !value.trim().is_empty() => parse_size(&value)
+            .map(Some)
+            .with_context(|| format!("Invalid {MAX_CACHE_SIZE_ENV} value `{value}`")),
+        _ => Ok(None),
+    }
+}
+
+/// Parses a size like `2GB`, `500 MB`, `1.5G` or a number of bytes. Units are powers of 1024,
+/// as in the sizes printed by `cleanup`.
+pub fn parse_size(size: &str) -> Result<u64, Error> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid size `{size}`, expected e.g. 500MB or 2GB"))?;
+    let exponent = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 1,
+        "M" | "MB" | "MIB" => 2,
+        "G" | "GB" | "GIB" => 3,
+        "T" | "TB" | "TIB" => 4,
+        _ => bail!("Invalid size unit `{unit}` in `{size}`, expected B, KB, MB, GB or TB"),
+    };
+    Ok((number * 1024f64.powi(exponent)) as u64)
+}
+
+/// The archives in the cache, least recently used first. Archives that are not in the index,
+/// e.g. ones downloaded by an older suiup, use their modification time.
+pub fn cached_archives() -> Result<Vec<CachedArchive>, Error> {
+    let dir = release_archive_dir();
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let index = CacheIndex::read();
+    let mut archives = vec![];
+    for entry in std::fs::read_dir(&dir)
+        .with_context(|| format!("Cannot read directory {}", dir.display()))?
+    {
+        let path = entry
+            .with_context(|| format!("Cannot read an entry in directory {}", dir.display()))?
+            .path();
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !path.is_file()
+            || file_name.starts_with('.')
+            || file_name.ends_with(".lock")
+            || file_name.ends_with(".part")
+        {
+            continue;
+        }
+        let metadata = std::fs::metadata(&path)
+            .with_context(|| format!("Cannot read metadata for {}", path.display()))?;
+        let last_access = index.archives.get(&file_name).copied().unwrap_or_else(|| {
+            metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs())
+        });
+        archives.push(CachedArchive {
+            path,
+            size: metadata.len(),
+            last_access,
+        });
+    }
+    archives.sort_by(|a, b| {
+        a.last_access
+            .cmp(&b.last_access)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(archives)
+}
+
+/// Removes the least recently used archives until the cache is at most `max_size`, never
+/// removing `keep`, archives used in the last few minutes or archives that another process is
+/// downloading. Returns the archives that were (or, for a dry run, would be) removed.
+pub fn evict_to_size(
+    max_size: u64,
+    keep: Option<&Path>,
+    dry_run: bool,
+) -> Result<Vec<CachedArchive>, Error> {
+    let _lock = lock_cache()?;
+    let archives = cached_archives()?;
+    let in_use_since = now().saturating_sub(IN_USE_SECS);
+    let victims = select_victims(&archives, max_size, |archive| {
+        keep == Some(archive.path.as_path()) || archive.last_access >= in_use_since
+    });
+    if dry_run || victims.is_empty() {
+        return Ok(victims);
+    }
+
+    let mut index = CacheIndex::read();
+    let mut evicted = vec![];
+    for archive in victims {
+        let Some(_download_lock) = try_lock_download(&archive.path)? else {
+            continue;
+        };
+        println!(
+            "Removing {} from the cache ({})",
+            archive.path.display(),
+            format_file_size(archive.size)
+        );
+        std::fs::remove_file(&archive.path)
+            .with_context(|| format!("Cannot remove file {}", archive.path.display()))?;
+        let _ = std::fs::remove_file(download_lock_file(&archive.path));
+        if let Some(file_name) = archive.path.file_name() {
+            index.archives.remove(&*file_name.to_string_lossy());
+        }
+        evicted.push(archive);
+    }
+    index.save()?;
+    Ok(evicted)
+}
+
+/// The least recently used archives to remove so that the rest fits in `max_size`, skipping
+/// the ones to `keep`
+fn select_victims(
+    archives: &[CachedArchive],
+    max_size: u64,
+    keep: impl Fn(&CachedArchive) -> bool,
+) -> Vec<CachedArchive> {
+    let mut total: u64 = archives.iter().map(|a| a.size).sum();
+    let mut victims = vec![];
+    for archive in archives {
+        if total <= max_size {
+            break;
+        }
+        if keep(archive) {
+            continue;
+        }
+        total -= archive.size;
+        victims.push(archive.clone());
+    }
+    victims
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive(name: &str, size: u64, last_access: u64) -> CachedArchive {
+        CachedArchive {
+            path: PathBuf::from(name),
+            size,
+            last_access,
+        }
+    }
+
+    #[test]
+    fn parse_size_accepts_units() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("2GB").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(parse_size("500 mb").unwrap(), 500 * 1024 * 1024);
+        assert_eq!(parse_size("1.5K").unwrap(), 1536);
+        assert!(parse_size("GB").is_err());
+        assert!(parse_size("2 parsecs").is_err());
+    }
+
+    #[test]
+    fn victims_are_least_recently_used_first() {
+        let archives = vec![
+            archive("old.tgz", 300, 1),
+            archive("current.tgz", 300, 2),
+            archive("recent.tgz", 300, 3),
+        ];
+        let names = |victims: Vec<CachedArchive>| -> Vec<PathBuf> {
+            victims.into_iter().map(|a| a.path).collect()
+        };
+
+        assert!(select_victims(&archives, 900, |_| false).is_empty());
+        assert_eq!(
+            names(select_victims(&archives, 600, |_| false)),
+            vec![PathBuf::from("old.tgz")]
+        );
+        assert_eq!(
+            names(select_victims(&archives, 500, |a| a.path == Path::new("old.tgz"))),
+            vec![PathBuf::from("current.tgz"), PathBuf::from("recent.tgz")]
+        );
+        assert_eq!(select_victims(&archives, 0, |_| false).len(), 3);
+    }
+}
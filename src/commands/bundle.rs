@@ -0,0 +1,78 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+use crate::handlers::bundle::{handle_bundle_create, handle_bundle_install};
+
+/// Create and install offline bundles of binaries for machines without network access.
+#[derive(Args, Debug)]
+pub struct Command {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Download binaries and pack them with their release metadata into a single file
+    Create {
+        /// Path of the bundle file to create
+        #[arg(long, value_name = "path")]
+        out: PathBuf,
+
+        /// Binaries to bundle with optional version
+        /// (e.g. 'sui@testnet', 'sui-node@mainnet-1.44.2', 'walrus')
+        #[arg(required = true)]
+        binaries: Vec<String>,
+
+        /// OS of the machine the bundle is for (linux, macos, windows). Defaults to this machine's OS
+        #[arg(long)]
+        os: Option<String>,
+
+        /// Architecture of the machine the bundle is for (x86_64, aarch64). Defaults to this
+        /// machine's architecture
+        #[arg(long)]
+        arch: Option<String>,
+
+        /// Resolve versions to pre-releases too
+        #[arg(long)]
+        pre: bool,
+    },
+    /// Install all binaries from a bundle, without accessing the network
+    Install {
+        /// Path of the bundle file
+        bundle: PathBuf,
+
+        /// Accept defaults without prompting
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+impl Command {
+    pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
+        match &self.command {
+            Commands::Create {
+                out,
+                binaries,
+                os,
+                arch,
+                pre,
+            } => {
+                handle_bundle_create(
+                    out,
+                    binaries,
+                    os.as_deref(),
+                    arch.as_deref(),
+                    *pre,
+                    github_token.map(str::to_owned),
+                )
+                .await
+            }
+            Commands::Install { bundle, yes } => handle_bundle_install(bundle, *yes),
+        }
+    }
+}
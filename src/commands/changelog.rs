@@ -0,0 +1,34 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::changelog::handle_changelog;
+
+/// Show the release notes of the releases between two versions of a binary.
+#[derive(Args, Debug)]
+pub struct Command {
+    /// Binary and network (e.g. 'sui@testnet', 'walrus@mainnet', 'mvr')
+    binary: String,
+
+    /// Version the changelog starts after, the installed version by default
+    #[arg(long, value_name = "version")]
+    from: Option<String>,
+
+    /// Last version in the changelog, 'latest' by default
+    #[arg(long, value_name = "version")]
+    to: Option<String>,
+}
+
+impl Command {
+    pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
+        handle_changelog(
+            &self.binary,
+            self.from.as_deref(),
+            self.to.as_deref(),
+            github_token.map(str::to_owned),
+        )
+        .await
+    }
+}
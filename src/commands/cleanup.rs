@@ -1,6 +1,7 @@
 use anyhow::Result;
 use clap::Args;
 
+use crate::cache::parse_size;
 use crate::handle_commands::handle_cmd;
 
 use super::ComponentCommands;
@@ -19,6 +20,18 @@ pub struct Command {
     /// Show what would be removed without actually removing anything
     #[clap(long, short = 'n')]
     dry_run: bool,
+
+    /// Remove the least recently used archives until the cache fits in this size (e.g. 2GB)
+    #[clap(long, value_name = "size", value_parser = parse_size, conflicts_with_all = ["all", "days", "metadata", "build_cache"])]
+    max_size: Option<u64>,
+
+    /// Remove stale cached release lists and ETags instead of release archives
+    #[clap(long)]
+    metadata: bool,
+
+    /// Remove the target directories and logs of source builds instead of release archives
+    #[clap(long, conflicts_with_all = ["all", "days", "metadata"])]
+    build_cache: bool,
 }
 
 impl Command {
@@ -28,6 +41,9 @@ impl Command {
                 all: self.all,
                 days: self.days,
                 dry_run: self.dry_run,
+                max_size: self.max_size,
+                metadata: self.metadata,
+                build_cache: self.build_cache,
             },
             github_token,
         )
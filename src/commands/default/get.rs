@@ -1,15 +1,10 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::BTreeMap;
-
-use anyhow::{Context, Result, anyhow};
+use anyhow::Result;
 use clap::Args;
 
-use crate::{
-    paths::default_file_path,
-    types::{Binaries, Version},
-};
+use crate::types::{Binaries, DefaultBinaries};
 
 use crate::commands::print_table;
 
@@ -19,17 +14,7 @@ pub struct Command;
 
 impl Command {
     pub fn exec(&self) -> Result<()> {
-        let default_path = default_file_path()?;
-        let default = std::fs::read_to_string(&default_path)
-            .with_context(|| format!("Cannot read default file {}", default_path.display()))?;
-        let default: BTreeMap<String, (String, Version, bool)> = serde_json::from_str(&default)
-            .map_err(|e| {
-                anyhow!(
-                    "Cannot deserialize default file {}: {e}",
-                    default_path.display()
-                )
-            })?;
-        let binaries = Binaries::from(default);
+        let binaries = Binaries::from(&DefaultBinaries::read_from_file()?);
 
         println!("\x1b[1mDefault binaries:\x1b[0m");
         print_table(&binaries.binaries);
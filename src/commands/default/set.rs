@@ -6,10 +6,11 @@ use clap::Args;
 use tracing::{debug, info};
 
 use crate::{
-    commands::{CommandMetadata, parse_component_with_version},
+    commands::{CommandMetadata, parse_member_with_version},
     handlers::{installed_binaries_grouped_by_network, update_default_version_file},
     paths::{binaries_dir, get_default_bin_dir},
     registry::InstallationType,
+    transaction::{Transaction, fail_point},
 };
 
 #[cfg(not(windows))]
@@ -57,13 +58,17 @@ impl Command {
             )
         }
 
-        let CommandMetadata {
+        // Binaries shipped in the archive of another binary follow the releases of that binary
+        let (
             name,
-            network,
-            version,
-        } = parse_component_with_version(name)?;
-
-        let config = name.config();
+            CommandMetadata {
+                name: release_name,
+                network,
+                version,
+            },
+        ) = parse_member_with_version(name)?;
+
+        let config = release_name.config();
         let network =
             if !config.network_based || config.installation_type == InstallationType::Standalone {
                 if let Some(nightly) = nightly {
@@ -86,7 +91,7 @@ impl Command {
         // Check if the binary exists in any network
         let binary_exists = installed_binaries
             .values()
-            .any(|bins| bins.iter().any(|x| x.binary_name == name.to_string()));
+            .any(|bins| bins.iter().any(|x| x.binary_name == name));
         if !binary_exists {
             bail!(
                 "Binary {name} not found in installed binaries. Use `suiup show` to see installed binaries."
@@ -102,7 +107,7 @@ impl Command {
         } else {
             binaries
                 .iter()
-                .filter(|b| b.binary_name == name.to_string())
+                .filter(|b| b.binary_name == name)
                 .max_by(|a, b| a.version.cmp(&b.version))
                 .map(|b| b.version.clone())
                 .ok_or_else(|| anyhow!("No version found for {name} in {network}"))?
@@ -114,7 +119,7 @@ impl Command {
         binaries
         .iter()
         .find(|b| {
-            b.binary_name == name.to_string() && b.version == version && b.network_release == network
+            b.binary_name == name && b.version == version && b.network_release == network
         })
         .ok_or_else(|| {
             anyhow!("Binary {binary_version} from {network} release not found. Use `suiup show` to see installed binaries.")
@@ -125,7 +130,7 @@ impl Command {
         let name = if *debug {
             format!("{}-debug", name)
         } else {
-            format!("{}", name)
+            name
         };
 
         dst.push(&name);
@@ -164,51 +169,29 @@ impl Command {
                 .expect("Expected binary filename as string")
         ));
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            if dst.exists() {
-                std::fs::remove_file(&dst).with_context(|| {
-                    format!("Cannot remove existing default binary {}", dst.display())
-                })?;
-            }
-
-            std::fs::copy(&src, &dst).with_context(|| {
-                format!(
-                    "Cannot copy binary from {} to {}",
-                    src.display(),
-                    dst.display()
-                )
-            })?;
+        let mut tx = Transaction::new()?;
+        tx.copy_file(&src, &dst)?;
 
-            #[cfg(unix)]
-            {
-                let mut perms = std::fs::metadata(&dst)
-                    .with_context(|| format!("Cannot read metadata for {}", dst.display()))?
-                    .permissions();
-                perms.set_mode(0o755);
-                std::fs::set_permissions(&dst, perms).with_context(|| {
-                    format!("Cannot set executable permissions on {}", dst.display())
-                })?;
-            }
-        }
-
-        #[cfg(target_os = "windows")]
+        #[cfg(unix)]
         {
-            std::fs::copy(&src, &dst).with_context(|| {
-                format!(
-                    "Cannot copy binary from {} to {}",
-                    src.display(),
-                    dst.display()
-                )
+            let mut perms = std::fs::metadata(&dst)
+                .with_context(|| format!("Cannot read metadata for {}", dst.display()))?
+                .permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&dst, perms).with_context(|| {
+                format!("Cannot set executable permissions on {}", dst.display())
             })?;
         }
 
         update_default_version_file(
+            &mut tx,
             &vec![name.to_string()],
             network.to_string(),
             &version,
             *debug,
         )?;
+        fail_point("default")?;
+        tx.commit();
 
         if *debug {
             println!(
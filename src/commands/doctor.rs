@@ -8,11 +8,20 @@ use crate::component::ComponentManager;
 
 /// Run diagnostic checks on the environment.
 #[derive(Args, Debug)]
-pub struct Command {}
+pub struct Command {
+    /// Fix the problems that can be fixed automatically, asking for confirmation for each fix
+    #[arg(long)]
+    fix: bool,
+    /// Apply the fixes without prompting
+    #[arg(short, long, requires = "fix")]
+    yes: bool,
+}
 
 impl Command {
     pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
         let component_manager = ComponentManager::new(github_token.map(str::to_owned));
-        component_manager.run_doctor_checks().await
+        component_manager
+            .run_doctor_checks(self.fix, self.yes)
+            .await
     }
 }
@@ -0,0 +1,50 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::fetch::handle_fetch;
+
+/// Download binaries into a directory, without installing them or changing the defaults.
+#[derive(Args, Debug)]
+pub struct Command {
+    /// Binaries to fetch with optional version
+    /// (e.g. 'sui@testnet', 'sui-node@mainnet-1.44.2', 'mvr')
+    #[arg(required = true)]
+    binaries: Vec<String>,
+
+    /// Directory to place the binaries in
+    #[arg(long, value_name = "path", default_value = ".")]
+    dir: PathBuf,
+
+    /// Download the binaries for this OS instead of the host OS (linux, macos, windows).
+    /// Can also be set together with the architecture via `SUIUP_TARGET`, e.g. 'linux-x86_64'
+    #[arg(long)]
+    os: Option<String>,
+
+    /// Download the binaries for this architecture instead of the host architecture
+    /// (x86_64, aarch64)
+    #[arg(long)]
+    arch: Option<String>,
+
+    /// Resolve versions to pre-releases too
+    #[arg(long)]
+    pre: bool,
+}
+
+impl Command {
+    pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
+        handle_fetch(
+            &self.binaries,
+            &self.dir,
+            self.os.as_deref(),
+            self.arch.as_deref(),
+            self.pre,
+            github_token.map(str::to_owned),
+        )
+        .await
+    }
+}
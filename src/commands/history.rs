@@ -0,0 +1,21 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::history::handle_history;
+
+/// List the operations that changed the installed or default binaries, newest first.
+#[derive(Args, Debug)]
+pub struct Command {
+    /// Only show this many of the most recent operations
+    #[arg(long, short = 'n')]
+    limit: Option<usize>,
+}
+
+impl Command {
+    pub fn exec(&self) -> Result<()> {
+        handle_history(self.limit)
+    }
+}
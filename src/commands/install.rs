@@ -1,10 +1,13 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::path::PathBuf;
+
 use anyhow::Result;
 use clap::Args;
 
 use crate::handle_commands::handle_cmd;
+use crate::types::BuildOptions;
 
 use super::ComponentCommands;
 
@@ -29,6 +32,35 @@ pub struct Command {
     /// Accept defaults without prompting
     #[arg(short, long)]
     yes: bool,
+
+    /// Install from a local release archive instead of downloading it from GitHub.
+    /// Network and version are derived from the archive name, unless they are part of the
+    /// binary spec (e.g. 'sui@testnet-1.44.2')
+    #[arg(long, value_name = "path", conflicts_with_all = ["nightly", "from_url"])]
+    from_file: Option<PathBuf>,
+
+    /// Install from a release archive at the given URL instead of the GitHub release.
+    /// Network and version are derived like for `--from-file`
+    #[arg(long, value_name = "url", conflicts_with = "nightly")]
+    from_url: Option<String>,
+
+    /// Additional binaries shipped in the same release archive to install,
+    /// e.g. `--with sui-tool,sui-faucet` (only available for sui)
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["nightly", "from_file", "from_url"])]
+    with: Vec<String>,
+
+    #[command(flatten)]
+    build: BuildOptions,
+
+    /// Build the release from its `<network>-v<version>` tag when there is no release archive
+    /// for this OS and architecture. Note that this requires Rust & cargo to be installed.
+    #[arg(long, conflicts_with_all = ["nightly", "from_file", "from_url", "with"])]
+    build_from_source_fallback: bool,
+
+    /// Install the latest pre-release when no version is given, and allow installing
+    /// pre-releases by their version
+    #[arg(long, conflicts_with_all = ["nightly", "from_file", "from_url"])]
+    pre: bool,
 }
 
 impl Command {
@@ -39,6 +71,12 @@ impl Command {
                 nightly: self.nightly.clone(),
                 debug: self.debug,
                 yes: self.yes,
+                from_file: self.from_file.clone(),
+                from_url: self.from_url.clone(),
+                with: self.with.clone(),
+                build: Box::new(self.build.clone()),
+                build_from_source_fallback: self.build_from_source_fallback,
+                pre: self.pre,
             },
             github_token,
         )
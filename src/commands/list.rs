@@ -5,15 +5,40 @@ use anyhow::Result;
 use clap::Args;
 
 use crate::handle_commands::handle_cmd;
+use crate::handlers::list::handle_list_remote;
+use crate::registry::BinaryName;
 
 use super::ComponentCommands;
 
-/// List available binaries to install.
+/// List available binaries to install, or the available versions of a binary.
 #[derive(Args, Debug)]
-pub struct Command;
+pub struct Command {
+    /// List the versions of this binary in its release list (e.g. 'sui', 'mvr')
+    #[arg(long, value_name = "binary")]
+    remote: Option<BinaryName>,
+
+    /// Only list the versions released for this network
+    #[arg(long, requires = "remote")]
+    network: Option<String>,
+
+    /// Maximum number of versions to list
+    #[arg(long, default_value_t = 20, requires = "remote")]
+    limit: usize,
+}
 
 impl Command {
     pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
-        handle_cmd(ComponentCommands::List, github_token).await
+        match &self.remote {
+            Some(name) => {
+                handle_list_remote(
+                    name,
+                    self.network.as_deref(),
+                    self.limit,
+                    github_token.map(str::to_owned),
+                )
+                .await
+            }
+            None => handle_cmd(ComponentCommands::List, github_token).await,
+        }
     }
 }
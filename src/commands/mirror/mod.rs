@@ -0,0 +1,31 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+mod serve;
+mod sync;
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+/// Keep releases in a local folder and share them with other suiup clients.
+#[derive(Debug, Args)]
+pub struct Command {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    Serve(serve::Command),
+    Sync(sync::Command),
+}
+
+impl Command {
+    /// Handles the mirror commands
+    pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
+        match &self.command {
+            Commands::Serve(cmd) => cmd.exec().await,
+            Commands::Sync(cmd) => cmd.exec(github_token).await,
+        }
+    }
+}
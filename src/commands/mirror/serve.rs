@@ -0,0 +1,38 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::mirror::handle_mirror_serve;
+use crate::paths::default_mirror_dir;
+
+/// Serve the mirror over HTTP. It answers the release endpoints of the GitHub API, so clients
+/// use it by setting SUIUP_GITHUB_API_URL to its address.
+#[derive(Args, Debug)]
+pub struct Command {
+    /// Folder of the mirror [default: the suiup data folder]
+    #[arg(long, value_name = "path")]
+    dir: Option<PathBuf>,
+
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+
+    /// Address to listen on
+    #[arg(long, default_value = "0.0.0.0")]
+    address: String,
+}
+
+impl Command {
+    pub async fn exec(&self) -> Result<()> {
+        handle_mirror_serve(
+            &self.dir.clone().unwrap_or_else(default_mirror_dir),
+            &self.address,
+            self.port,
+        )
+        .await
+    }
+}
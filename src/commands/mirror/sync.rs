@@ -0,0 +1,63 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::commands::BinaryName;
+use crate::handlers::download::host_os_arch;
+use crate::handlers::mirror::handle_mirror_sync;
+use crate::paths::default_mirror_dir;
+
+/// Download release metadata and archives into the mirror. Releases that are already mirrored
+/// are kept.
+#[derive(Args, Debug)]
+pub struct Command {
+    /// Folder of the mirror [default: the suiup data folder]
+    #[arg(long, value_name = "path")]
+    dir: Option<PathBuf>,
+
+    /// Binaries to mirror (e.g. 'sui,walrus')
+    #[arg(long, value_delimiter = ',', required = true)]
+    binaries: Vec<BinaryName>,
+
+    /// Networks to mirror the releases of. Binaries that are not released per network ignore it
+    #[arg(long, value_delimiter = ',', default_value = "testnet,devnet,mainnet")]
+    networks: Vec<String>,
+
+    /// Platforms to mirror the archives of as <os>-<arch> (e.g. 'linux-x86_64,macos-arm64')
+    /// [default: the host platform]
+    #[arg(long, value_delimiter = ',')]
+    platforms: Vec<String>,
+
+    /// Number of the most recent releases to mirror for each network
+    #[arg(long, default_value_t = 1)]
+    releases: usize,
+
+    /// Mirror pre-releases too
+    #[arg(long)]
+    pre: bool,
+}
+
+impl Command {
+    pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
+        let platforms = if self.platforms.is_empty() {
+            let (os, arch) = host_os_arch()?;
+            vec![format!("{os}-{arch}")]
+        } else {
+            self.platforms.clone()
+        };
+        handle_mirror_sync(
+            &self.dir.clone().unwrap_or_else(default_mirror_dir),
+            &self.binaries,
+            &self.networks,
+            &platforms,
+            self.releases,
+            self.pre,
+            github_token.map(str::to_owned),
+        )
+        .await
+    }
+}
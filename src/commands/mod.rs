@@ -1,21 +1,36 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod bundle;
+mod changelog;
 mod cleanup;
 mod default;
 mod doctor;
+mod fetch;
+mod history;
 mod install;
 mod list;
+mod mirror;
+mod registry;
 mod remove;
+mod repair;
 mod self_;
 mod show;
 mod status;
 mod switch;
+mod undo;
 mod update;
 mod which;
 
+use crate::cache::parse_size;
 pub use crate::registry::BinaryName;
-use crate::{handlers::self_::check_for_updates, types::BinaryVersion};
+use crate::registry::BinaryRegistry;
+use crate::{
+    handlers::self_::check_for_updates,
+    types::{BinaryVersion, BuildOptions},
+};
+
+use std::path::PathBuf;
 
 use anyhow::{Result, anyhow, bail};
 use clap::{Parser, Subcommand};
@@ -39,11 +54,18 @@ pub struct Command {
 
 #[derive(Subcommand)]
 pub enum Commands {
+    Bundle(bundle::Command),
+    Changelog(changelog::Command),
     Default(default::Command),
     Doctor(doctor::Command),
+    Fetch(fetch::Command),
+    History(history::Command),
     Install(install::Command),
     Remove(remove::Command),
+    Repair(repair::Command),
     List(list::Command),
+    Mirror(mirror::Command),
+    Registry(registry::Command),
 
     #[command(name = "self")]
     Self_(self_::Command),
@@ -51,6 +73,7 @@ pub enum Commands {
     Show(show::Command),
     Status(status::Command),
     Switch(switch::Command),
+    Undo(undo::Command),
     Update(update::Command),
     Which(which::Command),
     Cleanup(cleanup::Command),
@@ -75,15 +98,23 @@ impl Command {
         let github_token_ref = github_token.as_deref();
 
         match &self.command {
+            Commands::Bundle(cmd) => cmd.exec(github_token_ref).await,
+            Commands::Changelog(cmd) => cmd.exec(github_token_ref).await,
             Commands::Default(cmd) => cmd.exec(),
             Commands::Doctor(cmd) => cmd.exec(github_token_ref).await,
+            Commands::Fetch(cmd) => cmd.exec(github_token_ref).await,
+            Commands::History(cmd) => cmd.exec(),
             Commands::Install(cmd) => cmd.exec(github_token_ref).await,
             Commands::Remove(cmd) => cmd.exec(github_token_ref).await,
+            Commands::Repair(cmd) => cmd.exec(),
             Commands::List(cmd) => cmd.exec(github_token_ref).await,
+            Commands::Mirror(cmd) => cmd.exec(github_token_ref).await,
+            Commands::Registry(cmd) => cmd.exec().await,
             Commands::Self_(cmd) => cmd.exec().await,
             Commands::Show(cmd) => cmd.exec(),
             Commands::Status(cmd) => cmd.exec(github_token_ref).await,
             Commands::Switch(cmd) => cmd.exec(),
+            Commands::Undo(cmd) => cmd.exec(github_token_ref).await,
             Commands::Update(cmd) => cmd.exec(github_token_ref).await,
             Commands::Which(cmd) => cmd.exec(),
             Commands::Cleanup(cmd) => cmd.exec(github_token_ref).await,
@@ -94,7 +125,14 @@ impl Command {
 #[derive(Subcommand)]
 pub enum ComponentCommands {
     #[command(about = "Run diagnostic checks on the environment")]
-    Doctor,
+    Doctor {
+        /// Fix the problems that can be fixed automatically
+        #[arg(long)]
+        fix: bool,
+        /// Apply the fixes without prompting
+        #[arg(short, long, requires = "fix")]
+        yes: bool,
+    },
     #[command(about = "List available binaries to install")]
     List,
     #[command(about = "Add a binary")]
@@ -120,6 +158,41 @@ pub enum ComponentCommands {
         nightly: Option<String>,
         #[arg(short, long, help = "Accept defaults without prompting")]
         yes: bool,
+        #[arg(
+            long,
+            value_name = "path",
+            conflicts_with_all = ["nightly", "from_url"],
+            help = "Install from a local release archive instead of downloading it from GitHub"
+        )]
+        from_file: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "url",
+            conflicts_with = "nightly",
+            help = "Install from a release archive at the given URL"
+        )]
+        from_url: Option<String>,
+        #[arg(
+            long,
+            value_delimiter = ',',
+            conflicts_with_all = ["nightly", "from_file", "from_url"],
+            help = "Additional binaries from the same release archive to install (e.g. 'sui-tool,sui-faucet')"
+        )]
+        with: Vec<String>,
+        #[command(flatten)]
+        build: Box<BuildOptions>,
+        #[arg(
+            long,
+            conflicts_with_all = ["nightly", "from_file", "from_url", "with"],
+            help = "Build the release from its tag when there is no archive for this OS and architecture. Note that this requires Rust & cargo to be installed."
+        )]
+        build_from_source_fallback: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["nightly", "from_file", "from_url"],
+            help = "Resolve versions to pre-releases too"
+        )]
+        pre: bool,
     },
     #[command(
         about = "Remove one. By default, the binary from each release will be removed. Use --version to specify which exact version to remove"
@@ -137,6 +210,15 @@ pub enum ComponentCommands {
         /// Show what would be removed without actually removing anything
         #[arg(long, short = 'n')]
         dry_run: bool,
+        /// Remove the least recently used archives until the cache fits in this size (e.g. 2GB)
+        #[arg(long, value_name = "size", value_parser = parse_size, conflicts_with_all = ["all", "days", "metadata", "build_cache"])]
+        max_size: Option<u64>,
+        /// Remove stale cached release lists and ETags instead of release archives
+        #[arg(long)]
+        metadata: bool,
+        /// Remove the target directories and logs of source builds instead of release archives
+        #[arg(long, conflicts_with_all = ["all", "days", "metadata"])]
+        build_cache: bool,
     },
 }
 
@@ -155,7 +237,7 @@ fn parse_binary_name(name: &str) -> Result<BinaryName> {
     })
 }
 
-fn split_component_spec(s: &str) -> (&str, Option<&str>) {
+pub(crate) fn split_component_spec(s: &str) -> (&str, Option<&str>) {
     for delimiter in ["@", "==", "="] {
         if let Some((name, spec)) = s.split_once(delimiter) {
             return (name, Some(spec));
@@ -187,6 +269,22 @@ pub fn parse_component_with_version(s: &str) -> Result<CommandMetadata, anyhow::
     })
 }
 
+/// Parses a binary spec like [`parse_component_with_version`], also accepting the binaries shipped
+/// in the release archive of another binary (see `archive_members` in the binary configs).
+/// Returns the name of the binary and the metadata of the binary it is released with.
+pub fn parse_member_with_version(s: &str) -> Result<(String, CommandMetadata), anyhow::Error> {
+    let (name, version_spec) = split_component_spec(s);
+    let Some(parent) = BinaryRegistry::global().member_of(name) else {
+        let metadata = parse_component_with_version(s)?;
+        return Ok((metadata.name.to_string(), metadata));
+    };
+    let spec = match version_spec {
+        Some(spec) => format!("{}@{spec}", parent.name),
+        None => parent.name.clone(),
+    };
+    Ok((name.to_string(), parse_component_with_version(&spec)?))
+}
+
 pub fn parse_version_spec(spec: Option<&str>) -> Result<(String, Option<String>)> {
     match spec {
         None => Ok(("testnet".to_string(), None)),
@@ -221,27 +319,34 @@ pub fn print_table(binaries: &[BinaryVersion]) {
     let mut binaries_vec = binaries.to_owned();
     // sort by Binary column
     binaries_vec.sort_by_key(|b| b.binary_name.clone());
+    // The cargo settings are only shown when a binary was built with non-default ones
+    let show_build = binaries_vec.iter().any(|b| !b.build.is_default());
+    let mut header = vec!["Binary", "Release/Branch", "Version", "Debug"];
+    if show_build {
+        header.push("Build");
+    }
     let mut table = Table::new();
-    table
-        .load_preset(TABLE_FORMAT)
-        .set_header(vec!["Binary", "Release/Branch", "Version", "Debug"])
-        .add_rows(
-            binaries_vec
-                .into_iter()
-                .map(|binary| {
-                    vec![
-                        binary.binary_name,
-                        binary.network_release,
-                        binary.version,
-                        if binary.debug {
-                            "Yes".to_string()
-                        } else {
-                            "No".to_string()
-                        },
-                    ]
-                })
-                .collect::<Vec<Vec<String>>>(),
-        );
+    table.load_preset(TABLE_FORMAT).set_header(header).add_rows(
+        binaries_vec
+            .into_iter()
+            .map(|binary| {
+                let mut row = vec![
+                    binary.binary_name,
+                    binary.network_release,
+                    binary.version,
+                    if binary.debug {
+                        "Yes".to_string()
+                    } else {
+                        "No".to_string()
+                    },
+                ];
+                if show_build {
+                    row.push(binary.build.to_string());
+                }
+                row
+            })
+            .collect::<Vec<Vec<String>>>(),
+    );
     println!("{table}");
 }
 
@@ -0,0 +1,25 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::registry::handle_registry_add;
+
+/// Subscribe to a remote registry index of binary definitions. Only the entries signed with the
+/// given public key are loaded.
+#[derive(Args, Debug)]
+pub struct Command {
+    /// URL of the registry index
+    url: String,
+
+    /// Base64 encoded Ed25519 public key the registry signs its entries with
+    #[arg(long)]
+    public_key: String,
+}
+
+impl Command {
+    pub async fn exec(&self) -> Result<()> {
+        handle_registry_add(&self.url, &self.public_key).await
+    }
+}
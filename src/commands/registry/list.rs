@@ -0,0 +1,18 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::registry::handle_registry_list;
+
+/// List the binary definitions and where each one comes from. Definitions are built in or loaded
+/// from TOML files in the `binaries` folder of the suiup config directory.
+#[derive(Args, Debug)]
+pub struct Command;
+
+impl Command {
+    pub fn exec(&self) -> Result<()> {
+        handle_registry_list()
+    }
+}
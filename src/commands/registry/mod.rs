@@ -0,0 +1,37 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+mod add;
+mod list;
+mod remove;
+mod update;
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+/// Manage the definitions of the binaries suiup can install.
+#[derive(Debug, Args)]
+pub struct Command {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    Add(add::Command),
+    List(list::Command),
+    Remove(remove::Command),
+    Update(update::Command),
+}
+
+impl Command {
+    /// Handles the registry commands
+    pub async fn exec(&self) -> Result<()> {
+        match &self.command {
+            Commands::Add(cmd) => cmd.exec().await,
+            Commands::List(cmd) => cmd.exec(),
+            Commands::Remove(cmd) => cmd.exec(),
+            Commands::Update(cmd) => cmd.exec().await,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::registry::handle_registry_remove;
+
+/// Unsubscribe from a remote registry. Binaries installed from its definitions are kept.
+#[derive(Args, Debug)]
+pub struct Command {
+    /// URL of the registry index
+    url: String,
+}
+
+impl Command {
+    pub fn exec(&self) -> Result<()> {
+        handle_registry_remove(&self.url)
+    }
+}
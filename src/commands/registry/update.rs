@@ -0,0 +1,17 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::registry::handle_registry_update;
+
+/// Refresh the cached indexes of the subscribed remote registries.
+#[derive(Args, Debug)]
+pub struct Command;
+
+impl Command {
+    pub async fn exec(&self) -> Result<()> {
+        handle_registry_update().await
+    }
+}
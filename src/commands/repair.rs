@@ -0,0 +1,29 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::repair::handle_repair;
+
+/// Reconcile the installed binaries with the files in the binaries folder.
+///
+/// Adds binaries found on disk that are not recorded, drops recorded binaries whose files are
+/// gone and restores missing or outdated copies in the default bin folder. The changes are shown
+/// before they are applied.
+#[derive(Args, Debug)]
+pub struct Command {
+    /// Apply the changes without prompting
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Show the changes without applying them
+    #[arg(long, short = 'n', conflicts_with = "yes")]
+    dry_run: bool,
+}
+
+impl Command {
+    pub fn exec(&self) -> Result<()> {
+        handle_repair(self.yes, self.dry_run)
+    }
+}
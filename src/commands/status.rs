@@ -9,10 +9,14 @@ use crate::handlers::status::handle_status;
 /// Check for available updates for installed binaries.
 /// Use `suiup list` to see all available binaries to install.
 #[derive(Args, Debug)]
-pub struct Command;
+pub struct Command {
+    /// Show the release notes of the releases each update brings
+    #[arg(long)]
+    changelog: bool,
+}
 
 impl Command {
     pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
-        handle_status(github_token.map(str::to_owned)).await
+        handle_status(self.changelog, github_token.map(str::to_owned)).await
     }
 }
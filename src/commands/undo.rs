@@ -0,0 +1,28 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::handlers::history::handle_undo;
+
+/// Revert the most recent operations listed by `suiup history`.
+///
+/// Restores the default binaries and reinstalls or removes binaries as needed. The undo is
+/// recorded in the history as well, so running `suiup undo` again reverts it.
+#[derive(Args, Debug)]
+pub struct Command {
+    /// Number of operations to revert
+    #[arg(default_value = "1")]
+    count: usize,
+
+    /// Apply the changes without prompting
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl Command {
+    pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
+        handle_undo(self.count, self.yes, github_token.map(str::to_owned)).await
+    }
+}
@@ -16,10 +16,20 @@ pub struct Command {
     /// Accept defaults without prompting
     #[arg(short, long)]
     yes: bool,
+
+    /// Update to pre-releases too
+    #[arg(long)]
+    pre: bool,
 }
 
 impl Command {
     pub async fn exec(&self, github_token: Option<&str>) -> Result<()> {
-        handle_update(self.name.clone(), self.yes, github_token.map(str::to_owned)).await
+        handle_update(
+            self.name.clone(),
+            self.yes,
+            self.pre,
+            github_token.map(str::to_owned),
+        )
+        .await
     }
 }
@@ -1,49 +1,162 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::binary_info::{
+    BinaryFormat, BinaryInfo, GlibcVersion, VersionOutput, host_glibc_version, run_version,
+};
+use crate::handlers::release::github_api_url;
+use crate::handlers::repair::{confirm, default_source_path, executable_name};
+use crate::handlers::version::is_nightly;
 use crate::paths::{
-    default_file_path, get_default_bin_dir, get_suiup_data_dir, installed_binaries_file,
+    default_file_path, get_default_bin_dir, get_suiup_data_dir, initialize, installed_binaries_file,
 };
-use crate::types::InstalledBinaries;
-use anyhow::Result;
+use crate::toolchain::command_version;
+use crate::transaction::Transaction;
+use crate::types::{BinaryVersion, DefaultBinaries, DefaultBinary, InstalledBinaries};
+use anyhow::{Context, Result, anyhow};
 use colored::Colorize;
 use std::env;
-use std::process::Command;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-pub async fn run_doctor_checks() -> Result<()> {
-    println!("\n{}", "Suiup Environment Doctor".bold());
-    println!("------------------------");
+/// How long a default binary may take to print its version
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
 
-    let mut warnings = 0;
-    let mut errors = 0;
+/// The outcome of a single doctor check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub check: String,
+    pub status: Status,
+    /// Details for a passing check, or a description of the problem
+    pub message: String,
+    /// How `doctor --fix` can solve the problem, if it can
+    pub fix: Option<Fix>,
+}
 
-    let mut check = |message: &str, result: Result<String, String>| match result {
-        Ok(info) if info.is_empty() => println!("[{}] {}", "✓".green(), message),
-        Ok(info) => println!("[{}] {} {}", "✓".green(), message, info.dimmed()),
-        Err(e) => {
-            if e.starts_with("WARN:") {
-                warnings += 1;
-                println!(
-                    "[{}] {}",
-                    "!".yellow(),
-                    e.strip_prefix("WARN:").unwrap_or(&e).trim()
-                );
-            } else {
-                errors += 1;
-                println!(
-                    "[{}] {}",
-                    "✗".red(),
-                    e.strip_prefix("ERROR:").unwrap_or(&e).trim()
-                );
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// A remediation that `doctor --fix` can apply
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fix {
+    /// Recreate the suiup directories and state files
+    InitializePaths,
+    /// Make a binary executable
+    SetExecutable(PathBuf),
+    /// Remove a default binary whose installed binary is gone
+    RemoveDefault(String),
+    /// Prepend the default bin directory to PATH in a shell profile
+    PrependToPath { profile: PathBuf, line: String },
+}
+
+impl Finding {
+    fn ok(check: &str, message: impl Into<String>) -> Self {
+        Self {
+            check: check.to_string(),
+            status: Status::Ok,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(check: &str, message: impl Into<String>) -> Self {
+        Self {
+            check: check.to_string(),
+            status: Status::Warning,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn error(check: &str, message: impl Into<String>) -> Self {
+        Self {
+            check: check.to_string(),
+            status: Status::Error,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn with_fix(mut self, fix: Option<Fix>) -> Self {
+        self.fix = fix;
+        self
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.status {
+            Status::Ok if self.message.is_empty() => write!(f, "[{}] {}", "✓".green(), self.check),
+            Status::Ok => write!(
+                f,
+                "[{}] {} {}",
+                "✓".green(),
+                self.check,
+                self.message.dimmed()
+            ),
+            Status::Warning => write!(f, "[{}] {}", "!".yellow(), self.message),
+            Status::Error => write!(f, "[{}] {}", "✗".red(), self.message),
+        }
+    }
+}
+
+impl Display for Fix {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Fix::InitializePaths => write!(f, "Recreate the missing suiup directories and files"),
+            Fix::SetExecutable(path) => write!(f, "Make {} executable", path.display()),
+            Fix::RemoveDefault(name) => write!(f, "Remove the dangling default binary {name}"),
+            Fix::PrependToPath { profile, line } => {
+                write!(f, "Add `{line}` to {}", profile.display())
             }
         }
+    }
+}
+
+impl Fix {
+    fn apply(&self) -> Result<()> {
+        match self {
+            Fix::InitializePaths => initialize(),
+            Fix::SetExecutable(path) => set_executable(path),
+            Fix::RemoveDefault(name) => remove_default(name),
+            Fix::PrependToPath { profile, line } => append_to_profile(profile, line),
+        }
+    }
+}
+
+pub async fn run_doctor_checks(fix: bool, yes: bool) -> Result<()> {
+    println!("\n{}", "Suiup Environment Doctor".bold());
+    println!("------------------------");
+
+    let mut findings = vec![];
+    let mut report = |new: Vec<Finding>| {
+        for finding in &new {
+            println!("{finding}");
+        }
+        findings.extend(new);
     };
 
-    check("suiup data directory exists", check_suiup_data_dir());
-    check_path_variables(&mut check);
-    check_config_files(&mut check);
-    check_dependencies(&mut check);
-    check_network_connectivity(&mut check).await;
+    report(vec![check_suiup_data_dir()]);
+    report(check_path_variables());
+    report(check_config_files());
+    report(check_default_binaries());
+    report(check_installed_binaries());
+    report(check_dependencies());
+    report(vec![check_network_connectivity().await]);
+
+    let errors = findings
+        .iter()
+        .filter(|f| f.status == Status::Error)
+        .count();
+    let warnings = findings
+        .iter()
+        .filter(|f| f.status == Status::Warning)
+        .count();
 
     println!("\n{}", "Checkup complete.".bold());
     if errors > 0 {
@@ -57,41 +170,104 @@ pub async fn run_doctor_checks() -> Result<()> {
         println!("{}", "Your environment looks good!".green());
     }
 
+    let mut fixes: Vec<Fix> = vec![];
+    for fix in findings.into_iter().filter_map(|f| f.fix) {
+        if !fixes.contains(&fix) {
+            fixes.push(fix);
+        }
+    }
+    if fixes.is_empty() {
+        return Ok(());
+    }
+    if !fix {
+        println!(
+            "Run `suiup doctor --fix` to fix {} of these problem(s).",
+            fixes.len()
+        );
+        return Ok(());
+    }
+    apply_fixes(&fixes, yes)
+}
+
+/// Applies each fix after asking for confirmation, unless `yes` is set. A failing fix does not
+/// stop the others, but makes the command fail once all were tried.
+fn apply_fixes(fixes: &[Fix], yes: bool) -> Result<()> {
+    println!("\n{}", "Fixing problems".bold());
+    let mut failed = 0;
+    for fix in fixes {
+        if !yes && !confirm(&format!("{fix}? [y/N] "))? {
+            println!("[{}] Skipped: {fix}", "-".dimmed());
+            continue;
+        }
+        match fix.apply() {
+            Ok(()) => println!("[{}] {fix}", "✓".green()),
+            Err(e) => {
+                failed += 1;
+                println!("[{}] {fix}: {e:#}", "✗".red());
+            }
+        }
+    }
+
+    if fixes
+        .iter()
+        .any(|fix| matches!(fix, Fix::PrependToPath { .. }))
+    {
+        println!("Restart your shell for the PATH change to take effect.");
+    }
+    if failed > 0 {
+        return Err(anyhow!("{failed} fix(es) failed"));
+    }
     Ok(())
 }
 
-fn check_suiup_data_dir() -> Result<String, String> {
+fn check_suiup_data_dir() -> Finding {
+    let check = "suiup data directory exists";
     let path = get_suiup_data_dir();
     if path.exists() && path.is_dir() {
-        Ok(format!("at {}", path.display()))
+        Finding::ok(check, format!("at {}", path.display()))
     } else {
-        Err(format!(
-            "ERROR: suiup data directory not found at {}",
-            path.display()
-        ))
+        Finding::error(
+            check,
+            format!("suiup data directory not found at {}", path.display()),
+        )
+        .with_fix(Some(Fix::InitializePaths))
     }
 }
 
-fn check_path_variables(check: &mut impl FnMut(&str, Result<String, String>)) {
+fn check_path_variables() -> Vec<Finding> {
+    let mut findings = vec![];
     let default_bin_dir = get_default_bin_dir();
-    check(
-        "Default binary directory",
-        Ok(format!("is {}", default_bin_dir.display())),
-    );
+    if default_bin_dir.is_dir() {
+        findings.push(Finding::ok(
+            "Default binary directory",
+            format!("is {}", default_bin_dir.display()),
+        ));
+    } else {
+        findings.push(
+            Finding::error(
+                "Default binary directory",
+                format!(
+                    "Default binary directory {} does not exist",
+                    default_bin_dir.display()
+                ),
+            )
+            .with_fix(Some(Fix::InitializePaths)),
+        );
+    }
 
     match env::var("PATH") {
         Ok(path_var) => {
             let paths: Vec<_> = env::split_paths(&path_var).collect();
             if !paths.contains(&default_bin_dir) {
-                check(
-                    "Default binary directory in PATH",
-                    Err(
-                        "WARN: Not found in PATH. Binaries managed by suiup may not be accessible."
-                            .to_string(),
-                    ),
+                findings.push(
+                    Finding::warning(
+                        "Default binary directory in PATH",
+                        "Not found in PATH. Binaries managed by suiup may not be accessible.",
+                    )
+                    .with_fix(prepend_to_path_fix(&default_bin_dir)),
                 );
             } else {
-                check("Default binary directory in PATH", Ok("".to_string()));
+                findings.push(Finding::ok("Default binary directory in PATH", ""));
 
                 // Check PATH order
                 let cargo_bin_dir = dirs::home_dir().map(|p| p.join(".cargo/bin"));
@@ -102,149 +278,463 @@ fn check_path_variables(check: &mut impl FnMut(&str, Result<String, String>)) {
                     let cargo_pos = paths.iter().position(|p| p == &cargo_bin);
                     if let (Some(s_pos), Some(c_pos)) = (suiup_pos, cargo_pos) {
                         if s_pos > c_pos {
-                            check(
-                                "PATH order",
-                                Err(format!(
-                                    "WARN: Default binary directory ({}) is after cargo's binary directory ({}). This may cause conflicts if you have also installed sui via `cargo install`.",
-                                    default_bin_dir.display(),
-                                    cargo_bin.display()
-                                )),
+                            findings.push(
+                                Finding::warning(
+                                    "PATH order",
+                                    format!(
+                                        "Default binary directory ({}) is after cargo's binary directory ({}). This may cause conflicts if you have also installed sui via `cargo install`.",
+                                        default_bin_dir.display(),
+                                        cargo_bin.display()
+                                    ),
+                                )
+                                .with_fix(prepend_to_path_fix(&default_bin_dir)),
                             );
                         } else {
-                            check("PATH order", Ok("is correct".to_string()));
+                            findings.push(Finding::ok("PATH order", "is correct"));
                         }
                     }
                 }
             }
         }
         Err(_) => {
-            check(
+            findings.push(Finding::error(
                 "PATH variable",
-                Err("ERROR: Could not read PATH environment variable.".to_string()),
-            );
+                "Could not read PATH environment variable.",
+            ));
         }
     }
+    findings
 }
 
-fn check_config_files(check: &mut impl FnMut(&str, Result<String, String>)) {
+fn check_config_files() -> Vec<Finding> {
+    let mut findings = vec![];
     let installed_path = installed_binaries_file();
-    match installed_path {
+    let check = "Installed binaries config";
+    findings.push(match installed_path {
         Ok(path) => {
             if !path.exists() {
-                check(
-                    "Installed binaries config",
-                    Err(format!("WARN: File not found at {}", path.display())),
-                );
+                Finding::warning(check, format!("File not found at {}", path.display()))
+                    .with_fix(Some(Fix::InitializePaths))
             } else {
                 match InstalledBinaries::read_from_file() {
-                    Ok(_) => check("Installed binaries config", Ok("is valid".to_string())),
-                    Err(e) => check(
-                        "Installed binaries config",
-                        Err(format!("ERROR: Failed to parse: {}", e)),
-                    ),
+                    Ok(_) => Finding::ok(check, "is valid"),
+                    Err(e) => Finding::error(check, format!("Failed to parse: {}", e)),
                 }
             }
         }
-        Err(e) => check(
-            "Installed binaries config",
-            Err(format!("ERROR: Could not get path: {}", e)),
-        ),
-    }
+        Err(e) => Finding::error(check, format!("Could not get path: {}", e)),
+    });
 
     let default_path = default_file_path();
-    match default_path {
+    let check = "Default version config";
+    findings.push(match default_path {
         Ok(path) => {
             if !path.exists() {
-                check(
-                    "Default version config",
-                    Err(format!("WARN: File not found at {}", path.display())),
-                );
+                Finding::warning(check, format!("File not found at {}", path.display()))
+                    .with_fix(Some(Fix::InitializePaths))
             } else {
-                match std::fs::read_to_string(&path) {
-                    Ok(content) => {
-                        let result: Result<serde_json::Value, _> = serde_json::from_str(&content);
-                        if let Err(e) = result {
-                            check(
-                                "Default version config",
-                                Err(format!(
-                                    "ERROR: Failed to parse {} as valid JSON: {}",
-                                    path.display(),
-                                    e
-                                )),
-                            );
-                        } else {
-                            check("Default version config", Ok("is valid".to_string()));
-                        }
-                    }
-                    Err(e) => check(
-                        "Default version config",
-                        Err(format!("ERROR: Failed to read {}: {}", path.display(), e)),
-                    ),
+                match DefaultBinaries::read_from_file() {
+                    Ok(_) => Finding::ok(check, "is valid"),
+                    Err(e) => Finding::error(check, format!("Failed to parse: {}", e)),
                 }
             }
         }
-        Err(e) => check(
-            "Default version config",
-            Err(format!("ERROR: Could not get path: {}", e)),
-        ),
-    }
+        Err(e) => Finding::error(check, format!("Could not get path: {}", e)),
+    });
+    findings
 }
 
-fn check_dependencies(check: &mut impl FnMut(&str, Result<String, String>)) {
-    // Check for rustc
-    match Command::new("rustc").arg("--version").output() {
-        Ok(output) if output.status.success() => {
-            let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            check("rustc", Ok(version));
+/// Checks that every default binary points to an installed binary, that the default and
+/// installed binaries are executable, and that each default binary starts and reports the
+/// installed version.
+fn check_default_binaries() -> Vec<Finding> {
+    let mut findings = vec![];
+    let Ok(defaults) = DefaultBinaries::read_from_file() else {
+        // Reported by the config file checks
+        return findings;
+    };
+    let installed = InstalledBinaries::read_from_file().ok();
+
+    let default_bin_dir = get_default_bin_dir();
+    for (name, default) in defaults.binaries() {
+        let check = format!("Default {name}");
+        let src = default_source_path(name, default);
+        if !src.is_file() {
+            findings.push(
+                Finding::error(
+                    &check,
+                    format!(
+                        "Default {name} refers to {}, which is not installed",
+                        src.display()
+                    ),
+                )
+                .with_fix(Some(Fix::RemoveDefault(name.clone()))),
+            );
+            continue;
+        }
+        if let Some(installed) = &installed
+            && let Some(finding) =
+                check_installed_entry(&check, installed.binaries(), name, default)
+        {
+            findings.push(finding);
+        }
+
+        let dst = default_bin_dir.join(executable_name(name));
+        if !dst.is_file() {
+            findings.push(Finding::warning(
+                &check,
+                format!(
+                    "Default {name} is missing from {}. Run `suiup repair` to copy it again",
+                    default_bin_dir.display()
+                ),
+            ));
+        } else if !is_executable(&dst) {
+            findings.push(
+                Finding::error(&check, format!("{} is not executable", dst.display()))
+                    .with_fix(Some(Fix::SetExecutable(dst))),
+            );
+        } else {
+            findings.push(check_version_output(
+                &check,
+                &dst,
+                &default.version,
+                default,
+            ));
         }
-        _ => check(
-            "rustc",
-            Err("WARN: Not found. Required for --nightly builds.".to_string()),
-        ),
     }
 
-    // Check for cargo
-    match Command::new("cargo").arg("--version").output() {
-        Ok(output) if output.status.success() => {
-            let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            check("cargo", Ok(version));
+    if let Some(installed) = installed {
+        for binary in installed.binaries() {
+            if let Some(path) = binary.path.as_ref().map(PathBuf::from)
+                && path.is_file()
+                && !is_executable(&path)
+            {
+                findings.push(
+                    Finding::error(
+                        &format!("Installed {binary}"),
+                        format!("{} is not executable", path.display()),
+                    )
+                    .with_fix(Some(Fix::SetExecutable(path))),
+                );
+            }
         }
-        _ => check(
-            "cargo",
-            Err("WARN: Not found. Required for --nightly builds.".to_string()),
+    }
+    findings
+}
+
+/// Compares a default binary with its entries in the installed binaries file, matched by name,
+/// network and debug flag. Warns when there is no entry, or none with the default version.
+fn check_installed_entry(
+    check: &str,
+    installed: &[BinaryVersion],
+    name: &str,
+    default: &DefaultBinary,
+) -> Option<Finding> {
+    let base_name = match name.strip_suffix("-debug") {
+        Some(base_name) if default.debug => base_name,
+        _ => name,
+    };
+    let versions: Vec<&str> = installed
+        .iter()
+        .filter(|b| {
+            b.binary_name == base_name
+                && b.network_release == default.network_release
+                && b.debug == default.debug
+        })
+        .map(|b| b.version.as_str())
+        .collect();
+    if versions.is_empty() {
+        Some(Finding::warning(
+            check,
+            format!(
+                "Default {name} {} [{}] is not in the installed binaries. Run `suiup repair` to record it",
+                default.version, default.network_release
+            ),
+        ))
+    } else if !versions.contains(&default.version.as_str()) {
+        Some(Finding::warning(
+            check,
+            format!(
+                "Default {name} is {} [{}], but the installed binaries only have {}",
+                default.version,
+                default.network_release,
+                versions.join(", ")
+            ),
+        ))
+    } else {
+        None
+    }
+}
+
+/// Runs a default binary with `--version` and compares the output with the expected version
+fn check_version_output(
+    check: &str,
+    binary: &Path,
+    expected: &str,
+    default: &DefaultBinary,
+) -> Finding {
+    let name = binary.display();
+    match run_version(binary, VERSION_TIMEOUT) {
+        Err(e) => Finding::error(check, format!("{name} does not start: {e:#}")),
+        Ok(VersionOutput::TimedOut) => Finding::warning(
+            check,
+            format!(
+                "{name} did not print its version within {}s",
+                VERSION_TIMEOUT.as_secs()
+            ),
+        ),
+        Ok(VersionOutput::Exited {
+            success: false,
+            stderr,
+            ..
+        }) => Finding::error(
+            check,
+            format!(
+                "{name} --version failed: {}",
+                stderr.lines().next().unwrap_or("no output")
+            ),
         ),
+        Ok(VersionOutput::Exited { stdout, stderr, .. }) => {
+            let output = if stdout.trim().is_empty() {
+                stderr.trim()
+            } else {
+                stdout.trim()
+            };
+            let version = expected.strip_prefix('v').unwrap_or(expected);
+            if is_nightly(expected) || output.contains(version) {
+                Finding::ok(
+                    check,
+                    format!("is {}@{}", default.version, default.network_release),
+                )
+            } else {
+                Finding::warning(
+                    check,
+                    format!(
+                        "{name} reports `{}`, but {expected} is installed",
+                        output.lines().next().unwrap_or_default()
+                    ),
+                )
+            }
+        }
+    }
+}
+
+/// Checks that every installed binary is built for the OS and architecture of this machine and
+/// that the host glibc is new enough for it.
+fn check_installed_binaries() -> Vec<Finding> {
+    let Ok(installed) = InstalledBinaries::read_from_file() else {
+        return vec![];
+    };
+    let host_glibc = host_glibc_version();
+    installed
+        .binaries()
+        .iter()
+        .filter_map(|binary| {
+            let path = binary.path.as_ref().map(PathBuf::from)?;
+            path.is_file()
+                .then(|| check_binary_compatibility(binary, &path, host_glibc))
+        })
+        .collect()
+}
+
+fn check_binary_compatibility(
+    binary: &BinaryVersion,
+    path: &Path,
+    host_glibc: Option<GlibcVersion>,
+) -> Finding {
+    let check = format!("Installed {binary} [{}]", binary.network_release);
+    let info = match BinaryInfo::read(path) {
+        Ok(info) => info,
+        Err(e) => return Finding::error(&check, format!("{e:#}")),
+    };
+    if info.format == BinaryFormat::Script {
+        return Finding::ok(&check, "is a script");
     }
 
-    // Check for git
-    match Command::new("git").arg("--version").output() {
-        Ok(output) if output.status.success() => {
-            let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            check("git", Ok(version));
+    let host_arch = env::consts::ARCH;
+    if let Some(native) = BinaryFormat::native()
+        && info.format != native
+    {
+        return Finding::error(
+            &check,
+            format!(
+                "{} is a {} binary, which cannot run on this machine",
+                path.display(),
+                info.format
+            ),
+        );
+    }
+    if !info.supports_arch(host_arch) {
+        let archs = info.archs.join(", ");
+        // Rosetta runs x86_64 binaries on Apple silicon
+        if cfg!(target_os = "macos") && host_arch == "aarch64" && info.supports_arch("x86_64") {
+            return Finding::warning(
+                &check,
+                format!(
+                    "{} is built for {archs} and runs through Rosetta on this machine",
+                    path.display()
+                ),
+            );
         }
-        _ => check(
-            "git",
-            Err("WARN: Not found. Required for --nightly builds.".to_string()),
-        ),
+        return Finding::error(
+            &check,
+            format!(
+                "{} is built for {archs}, but this machine is {host_arch}",
+                path.display()
+            ),
+        );
+    }
+    if let (Some(required), Some(host)) = (info.glibc, host_glibc)
+        && required > host
+    {
+        return Finding::error(
+            &check,
+            format!(
+                "{} requires glibc {required}, but this machine has glibc {host}",
+                path.display()
+            ),
+        );
+    }
+
+    let mut details = format!("is {} {}", info.format, info.archs.join(", "));
+    if let Some(required) = info.glibc {
+        details.push_str(&format!(", requires glibc {required}"));
     }
+    Finding::ok(&check, details)
+}
+
+fn check_dependencies() -> Vec<Finding> {
+    let mut findings: Vec<Finding> = ["rustc", "cargo", "git"]
+        .into_iter()
+        .map(|tool| match command_version(tool) {
+            Some(version) => Finding::ok(tool, version),
+            None => Finding::warning(
+                tool,
+                format!("{tool} not found. Required for --nightly builds."),
+            ),
+        })
+        .collect();
+    findings.push(match command_version("rustup") {
+        Some(version) => Finding::ok("rustup", version),
+        None => Finding::warning(
+            "rustup",
+            "rustup not found. Needed to install the Rust toolchains that --nightly builds require.",
+        ),
+    });
+    findings
 }
 
-async fn check_network_connectivity(check: &mut impl FnMut(&str, Result<String, String>)) {
+async fn check_network_connectivity() -> Finding {
     let client = reqwest::Client::new();
+    let check = "GitHub API connectivity";
 
     match client
-        .get("https://api.github.com")
+        .get(github_api_url())
         .header("User-Agent", "suiup")
         .send()
         .await
     {
-        Ok(resp) if resp.status().is_success() => {
-            check("GitHub API connectivity", Ok("".to_string()))
-        }
-        _ => check(
-            "GitHub API connectivity",
-            Err("ERROR: Cannot connect to GitHub API. Downloads will fail.".to_string()),
+        Ok(resp) if resp.status().is_success() => Finding::ok(check, ""),
+        _ => Finding::error(check, "Cannot connect to GitHub API. Downloads will fail."),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("Cannot set executable permissions on {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+fn remove_default(name: &str) -> Result<()> {
+    let mut tx = Transaction::new()?;
+    let mut defaults = DefaultBinaries::read_from_file()?;
+    defaults.remove(name);
+    let dst = get_default_bin_dir().join(executable_name(name));
+    if dst.is_file() {
+        tx.remove_file(&dst)?;
+    }
+    tx.write_json(&default_file_path()?, &defaults)?;
+    tx.commit();
+    Ok(())
+}
+
+/// The shell profile and the line that prepends `bin_dir` to PATH for the user's shell, based on
+/// the SHELL environment variable. Not available on Windows, where PATH is not set in a profile.
+fn prepend_to_path_fix(bin_dir: &Path) -> Option<Fix> {
+    if cfg!(windows) {
+        return None;
+    }
+    let home = dirs::home_dir()?;
+    let shell = env::var("SHELL").unwrap_or_default();
+    let (profile, line) = shell_profile_line(&home, &shell, bin_dir);
+    Some(Fix::PrependToPath { profile, line })
+}
+
+fn shell_profile_line(home: &Path, shell: &str, bin_dir: &Path) -> (PathBuf, String) {
+    let shell = Path::new(shell)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let bin_dir = bin_dir.display();
+    match shell.as_str() {
+        "fish" => (
+            home.join(".config/fish/config.fish"),
+            format!("fish_add_path --prepend --move {bin_dir}"),
         ),
+        "zsh" => (
+            home.join(".zshrc"),
+            format!("export PATH=\"{bin_dir}:$PATH\""),
+        ),
+        "bash" => (
+            home.join(".bashrc"),
+            format!("export PATH=\"{bin_dir}:$PATH\""),
+        ),
+        _ => (
+            home.join(".profile"),
+            format!("export PATH=\"{bin_dir}:$PATH\""),
+        ),
+    }
+}
+
+fn append_to_profile(profile: &Path, line: &str) -> Result<()> {
+    let content = match std::fs::read_to_string(profile) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Cannot read {}", profile.display()));
+        }
+    };
+    if content.lines().any(|l| l.trim() == line) {
+        return Ok(());
+    }
+    if let Some(parent) = profile.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Cannot create directory {}", parent.display()))?;
+    }
+
+    let mut content = content;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
     }
+    content.push_str(&format!("\n# Added by suiup doctor --fix\n{line}\n"));
+    std::fs::write(profile, content).with_context(|| format!("Cannot write {}", profile.display()))
 }
 
 #[cfg(test)]
@@ -252,6 +742,7 @@ mod tests {
     use super::*;
     use crate::remove_env_var;
     use crate::set_env_var;
+    use crate::types::BuildOptions;
     use lazy_static::lazy_static;
     use std::fs;
     use std::sync::Mutex;
@@ -281,9 +772,9 @@ mod tests {
             set_env_var!("XDG_DATA_HOME", data_dir.to_str().unwrap());
         }
 
-        let result = check_suiup_data_dir();
-        assert!(result.is_ok());
-        assert!(result.unwrap().contains("at"));
+        let finding = check_suiup_data_dir();
+        assert_eq!(finding.status, Status::Ok);
+        assert!(finding.message.contains("at"));
 
         // Restore original env var
         #[cfg(windows)]
@@ -326,13 +817,10 @@ mod tests {
         let path = crate::paths::get_suiup_data_dir();
         println!("Testing path: {}", path.display());
         println!("Path exists: {}", path.exists());
-        let result = check_suiup_data_dir();
-        assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .contains("suiup data directory not found")
-        );
+        let finding = check_suiup_data_dir();
+        assert_eq!(finding.status, Status::Error);
+        assert!(finding.message.contains("suiup data directory not found"));
+        assert_eq!(finding.fix, Some(Fix::InitializePaths));
 
         // Restore original env var
         #[cfg(windows)]
@@ -352,4 +840,124 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_shell_profile_line() {
+        let home = Path::new("/home/user");
+        let bin_dir = Path::new("/home/user/.local/bin");
+        assert_eq!(
+            shell_profile_line(home, "/usr/bin/zsh", bin_dir),
+            (
+                home.join(".zshrc"),
+                "export PATH=\"/home/user/.local/bin:$PATH\"".to_string()
+            )
+        );
+        assert_eq!(
+            shell_profile_line(home, "/usr/local/bin/fish", bin_dir).0,
+            home.join(".config/fish/config.fish")
+        );
+        assert_eq!(
+            shell_profile_line(home, "", bin_dir).0,
+            home.join(".profile")
+        );
+    }
+
+    #[test]
+    fn test_append_to_profile_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let profile = temp_dir.path().join(".bashrc");
+        fs::write(&profile, "alias ll='ls -l'").unwrap();
+        let line = "export PATH=\"/opt/bin:$PATH\"";
+
+        append_to_profile(&profile, line).unwrap();
+        append_to_profile(&profile, line).unwrap();
+        let content = fs::read_to_string(&profile).unwrap();
+        assert!(content.starts_with("alias ll='ls -l'\n"));
+        assert_eq!(content.matches(line).count(), 1);
+    }
+
+    #[test]
+    fn test_check_installed_entry() {
+        let binary = |version: &str, debug: bool| BinaryVersion {
+            binary_name: "sui".to_string(),
+            network_release: "testnet".to_string(),
+            version: version.to_string(),
+            debug,
+            path: None,
+            build: BuildOptions::default(),
+        };
+        let default = |version: &str, debug: bool| DefaultBinary {
+            network_release: "testnet".to_string(),
+            version: version.to_string(),
+            debug,
+        };
+        let installed = [binary("v1.44.2", false), binary("v1.45.0", true)];
+
+        assert!(
+            check_installed_entry("c", &installed, "sui", &default("v1.44.2", false)).is_none()
+        );
+        assert!(
+            check_installed_entry("c", &installed, "sui-debug", &default("v1.45.0", true))
+                .is_none()
+        );
+
+        let finding =
+            check_installed_entry("c", &installed, "sui", &default("v1.45.0", false)).unwrap();
+        assert_eq!(finding.status, Status::Warning);
+        assert!(
+            finding
+                .message
+                .contains("installed binaries only have v1.44.2")
+        );
+
+        let finding = check_installed_entry("c", &[], "sui", &default("v1.44.2", false)).unwrap();
+        assert_eq!(finding.status, Status::Warning);
+        assert!(finding.message.contains("is not in the installed binaries"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_check_binary_compatibility_detects_other_arch() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary = BinaryVersion {
+            binary_name: "sui".to_string(),
+            network_release: "testnet".to_string(),
+            version: "v1.44.2".to_string(),
+            debug: false,
+            path: None,
+            build: BuildOptions::default(),
+        };
+
+        let finding = check_binary_compatibility(
+            &binary,
+            &std::env::current_exe().unwrap(),
+            host_glibc_version(),
+        );
+        assert_eq!(finding.status, Status::Ok);
+
+        let other_machine: u16 = if env::consts::ARCH == "aarch64" {
+            62
+        } else {
+            183
+        };
+        let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+        elf.resize(18, 0);
+        elf.extend(other_machine.to_le_bytes());
+        elf.resize(64, 0);
+        let path = temp_dir.path().join("sui-v1.44.2");
+        fs::write(&path, elf).unwrap();
+        let finding = check_binary_compatibility(&binary, &path, None);
+        assert_eq!(finding.status, Status::Error);
+        assert!(finding.message.contains("but this machine is"));
+
+        let finding = check_binary_compatibility(
+            &binary,
+            &std::env::current_exe().unwrap(),
+            Some(GlibcVersion { major: 2, minor: 1 }),
+        );
+        if cfg!(target_env = "gnu") {
+            assert_eq!(finding.status, Status::Error);
+            assert!(finding.message.contains("requires glibc"));
+        }
+    }
 }
@@ -1,26 +1,77 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use std::fs::create_dir_all;
 
-use crate::handlers::install::{install_from_nightly, install_from_release, install_standalone};
+use crate::commands::CommandMetadata;
+use crate::handlers::download::{AssetNotFound, detect_os_arch, download_release};
+use crate::handlers::install::{
+    install_from_archive, install_from_archive_source, install_from_nightly, install_from_source,
+    install_standalone,
+};
+use crate::handlers::version::extract_version_from_release;
 use crate::paths::{binaries_dir, get_default_bin_dir};
 use crate::registry::{BinaryName, InstallationType};
-use crate::types::Version;
+use crate::types::{ArchiveSource, BuildOptions, Version};
+
+/// Options for installing a component
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Branch to build from instead of installing a release
+    pub nightly: Option<String>,
+    pub debug: bool,
+    pub yes: bool,
+    /// Additional binaries to install from the same release archive
+    pub with: Vec<String>,
+    /// Cargo settings for building from a branch
+    pub build: BuildOptions,
+    /// Build the release from its tag when there is no archive for the platform
+    pub build_from_source_fallback: bool,
+    /// Resolve versions to pre-releases too
+    pub include_prereleases: bool,
+}
 
 /// Install a component with the given parameters
 pub async fn install_component(
-    name: BinaryName,
-    network: String,
-    version: Option<Version>,
-    nightly: Option<String>,
-    debug: bool,
-    yes: bool,
+    command_metadata: CommandMetadata,
+    options: InstallOptions,
     github_token: Option<String>,
 ) -> Result<()> {
+    let CommandMetadata {
+        name,
+        network,
+        version,
+    } = command_metadata;
+    let InstallOptions {
+        nightly,
+        debug,
+        yes,
+        with,
+        build,
+        build_from_source_fallback,
+        include_prereleases,
+    } = options;
     let config = name.config();
 
+    for member in &with {
+        if !config.archive_members.contains(member) {
+            return Err(anyhow!(
+                "{member} is not shipped with {name}. Available: {}",
+                if config.archive_members.is_empty() {
+                    "none".to_string()
+                } else {
+                    config.archive_members.join(", ")
+                }
+            ));
+        }
+    }
+    if !with.is_empty() && nightly.is_some() {
+        return Err(anyhow!(
+            "Cannot install additional binaries with --with when building from a branch"
+        ));
+    }
+
     // Ensure installation directories exist
     let default_bin_dir = get_default_bin_dir();
     create_dir_all(&default_bin_dir).with_context(|| {
@@ -50,41 +101,58 @@ pub async fn install_component(
 
     // Handle nightly installs (same for all binary types)
     if let Some(branch) = &nightly {
-        install_from_nightly(&name, branch, debug, yes).await?;
+        install_from_nightly(&name, branch, debug, &build, yes).await?;
         return Ok(());
     }
 
+    let (os, arch) = detect_os_arch()?;
+
     // Data-driven dispatch based on config
     match config.installation_type {
         InstallationType::Archive => {
             // For network-based archives, determine the right network
-            let effective_network = if config.network_based {
-                // If the binary only supports specific networks, use the first/default
-                if !config.supported_networks.is_empty()
-                    && !config.supported_networks.contains(&network)
-                {
-                    config.default_network.clone()
-                } else {
-                    network.clone()
-                }
-            } else {
-                network.clone()
-            };
+            let effective_network = config.effective_network(&network);
 
             let target_dir = installed_bins_dir.join(&effective_network);
             create_dir_all(&target_dir)
                 .with_context(|| format!("Cannot create directory {}", target_dir.display()))?;
 
-            install_from_release(
+            let download = download_release(
+                config,
+                &effective_network,
+                version.as_deref(),
+                &os,
+                &arch,
+                github_token,
+                include_prereleases,
+            )
+            .await;
+            if build_from_source_fallback
+                && let Err(e) = &download
+                && let Some(AssetNotFound {
+                    version: Some(version),
+                    ..
+                }) = e.downcast_ref()
+            {
+                if !config.network_based {
+                    bail!(
+                        "{e}. Building {name} from source is only available for network releases"
+                    );
+                }
+                println!("{e}, building {name} {version} from source");
+                return install_from_source(&name, &effective_network, version, debug, yes).await;
+            }
+            let filename = download?;
+            let version = extract_version_from_release(&filename)?;
+            install_from_archive(
                 name.as_str(),
                 &effective_network,
-                version,
+                &version,
+                &filename,
                 debug,
                 yes,
-                config,
-                github_token,
-            )
-            .await?;
+                &with,
+            )?;
         }
         InstallationType::Standalone => {
             let standalone_dir = installed_bins_dir.join("standalone");
@@ -98,9 +166,60 @@ pub async fn install_component(
                 None
             };
 
-            install_standalone(version, config, binary_name_override, yes, github_token).await?;
+            install_standalone(
+                version,
+                config,
+                binary_name_override,
+                yes,
+                &(os, arch),
+                github_token,
+                include_prereleases,
+            )
+            .await?;
         }
     }
 
     Ok(())
 }
+
+/// Install a component from a release archive given as a local file or an arbitrary URL
+pub async fn install_component_from_archive(
+    name: BinaryName,
+    network: Option<String>,
+    version: Option<Version>,
+    source: ArchiveSource,
+    debug: bool,
+    yes: bool,
+    github_token: Option<String>,
+) -> Result<()> {
+    let config = name.config();
+
+    if config.installation_type != InstallationType::Archive {
+        return Err(anyhow!(
+            "Installing from an archive is only available for binaries that are released as archives. {name} is a standalone binary"
+        ));
+    }
+
+    if !config.supports_debug && debug {
+        return Err(anyhow!("Debug flag is only available for the `sui` binary"));
+    }
+
+    let default_bin_dir = get_default_bin_dir();
+    create_dir_all(&default_bin_dir).with_context(|| {
+        format!(
+            "Cannot create default bin directory {}",
+            default_bin_dir.display()
+        )
+    })?;
+
+    install_from_archive_source(
+        &name,
+        &source,
+        network.as_deref(),
+        version.as_deref(),
+        debug,
+        yes,
+        github_token,
+    )
+    .await
+}
@@ -8,8 +8,11 @@ mod remove;
 
 use anyhow::{Result, bail};
 
-use crate::commands::{CommandMetadata, ComponentCommands, parse_component_with_version};
+use crate::commands::{
+    CommandMetadata, ComponentCommands, parse_component_with_version, split_component_spec,
+};
 use crate::registry::BinaryRegistry;
+use crate::types::ArchiveSource;
 
 /// ComponentManager handles all component-related operations
 pub struct ComponentManager {
@@ -25,21 +28,56 @@ impl ComponentManager {
     /// Handle component commands
     pub async fn handle_command(&self, cmd: ComponentCommands) -> Result<()> {
         match cmd {
-            ComponentCommands::Doctor => self.run_doctor_checks().await,
+            ComponentCommands::Doctor { fix, yes } => self.run_doctor_checks(fix, yes).await,
             ComponentCommands::List => self.list_components(),
             ComponentCommands::Add {
                 component,
                 nightly,
                 debug,
                 yes,
+                from_file,
+                from_url,
+                with,
+                build,
+                build_from_source_fallback,
+                pre,
             } => {
                 let command_metadata = parse_component_with_version(&component)?;
-                self.install_component(command_metadata, nightly, debug, yes)
-                    .await
+                let archive_source = from_file
+                    .map(ArchiveSource::File)
+                    .or(from_url.map(ArchiveSource::Url));
+                if let Some(source) = archive_source {
+                    // Only a network spelled out in the binary spec overrides the one derived
+                    // from the archive name
+                    let explicit_network = split_component_spec(&component)
+                        .1
+                        .is_some_and(|spec| spec.starts_with(&command_metadata.network));
+                    return self
+                        .install_component_from_archive(
+                            command_metadata,
+                            explicit_network,
+                            source,
+                            debug,
+                            yes,
+                        )
+                        .await;
+                }
+                let options = install::InstallOptions {
+                    nightly,
+                    debug,
+                    yes,
+                    with,
+                    build: *build,
+                    build_from_source_fallback,
+                    include_prereleases: pre,
+                };
+                self.install_component(command_metadata, options).await
             }
             ComponentCommands::Remove { binary } => {
-                // Validate binary name against registry
-                if !BinaryRegistry::global().contains(&binary) {
+                // Validate binary name against registry, binaries installed with `--with` are
+                // part of the release archive of a registered binary
+                let registry = BinaryRegistry::global();
+                if !registry.contains(&binary) && registry.member_of(&binary).is_none() {
                     bail!(
                         "Unknown binary: {}. Use `suiup list` to see available binaries.",
                         binary
@@ -47,8 +85,19 @@ impl ComponentManager {
                 }
                 self.remove_component(&binary)
             }
-            ComponentCommands::Cleanup { all, days, dry_run } => {
-                self.handle_cleanup(all, days, dry_run)
+            ComponentCommands::Cleanup {
+                all,
+                days,
+                dry_run,
+                max_size,
+                metadata,
+                build_cache,
+            } => {
+                if build_cache {
+                    crate::handlers::cleanup::handle_cleanup_build_cache(dry_run)
+                } else {
+                    self.handle_cleanup(all, days, dry_run, max_size, metadata)
+                }
             }
         }
     }
@@ -62,7 +111,17 @@ impl ComponentManager {
     async fn install_component(
         &self,
         command_metadata: CommandMetadata,
-        nightly: Option<String>,
+        options: install::InstallOptions,
+    ) -> Result<()> {
+        install::install_component(command_metadata, options, self.github_token.clone()).await
+    }
+
+    /// Install a component from a local or remote release archive
+    async fn install_component_from_archive(
+        &self,
+        command_metadata: CommandMetadata,
+        explicit_network: bool,
+        source: ArchiveSource,
         debug: bool,
         yes: bool,
     ) -> Result<()> {
@@ -71,11 +130,11 @@ impl ComponentManager {
             network,
             version,
         } = command_metadata;
-        install::install_component(
+        install::install_component_from_archive(
             name,
-            network,
+            explicit_network.then_some(network),
             version,
-            nightly,
+            source,
             debug,
             yes,
             self.github_token.clone(),
@@ -89,12 +148,26 @@ impl ComponentManager {
     }
 
     /// Run diagnostic checks on the environment
-    pub async fn run_doctor_checks(&self) -> Result<()> {
-        doctor::run_doctor_checks().await
+    pub async fn run_doctor_checks(&self, fix: bool, yes: bool) -> Result<()> {
+        doctor::run_doctor_checks(fix, yes).await
     }
 
     /// Handle cleanup operations
-    fn handle_cleanup(&self, all: bool, days: u32, dry_run: bool) -> Result<()> {
-        crate::handlers::cleanup::handle_cleanup(all, days, dry_run)
+    fn handle_cleanup(
+        &self,
+        all: bool,
+        days: u32,
+        dry_run: bool,
+        max_size: Option<u64>,
+        metadata: bool,
+    ) -> Result<()> {
+        use crate::handlers::cleanup;
+        if let Some(max_size) = max_size {
+            cleanup::handle_cleanup_to_size(max_size, dry_run)
+        } else if metadata {
+            cleanup::handle_cleanup_metadata(all, days, dry_run)
+        } else {
+            cleanup::handle_cleanup(all, days, dry_run)
+        }
     }
 }
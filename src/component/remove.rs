@@ -2,17 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use tracing::debug;
 
-use crate::fs_utils::{read_json_file, write_json_file};
-use crate::paths::{default_file_path, get_default_bin_dir};
-use crate::types::InstalledBinaries;
+use crate::paths::{default_file_path, get_default_bin_dir, installed_binaries_file};
+use crate::transaction::Transaction;
+use crate::types::{DefaultBinaries, InstalledBinaries};
 
 /// Remove a component and its associated files
 pub fn remove_component(binary: &str) -> Result<()> {
+    let mut tx = Transaction::new()?;
     let mut installed_binaries = InstalledBinaries::new()?;
 
     let binaries_to_remove = installed_binaries
@@ -28,27 +29,19 @@ pub fn remove_component(binary: &str) -> Result<()> {
 
     println!("Binaries to remove: {binaries_to_remove:?}");
 
-    // Verify all binaries exist before removing any
-    for p in &binaries_to_remove {
-        if let Some(p) = p.path.as_ref()
-            && !PathBuf::from(p).exists()
-        {
-            println!("Binary {p} does not exist. Aborting the command.");
-            return Ok(());
-        }
-    }
-
     // Load default binaries
-    let default_file = default_file_path()?;
-    let mut default_binaries: std::collections::BTreeMap<String, (String, String, bool)> =
-        read_json_file(&default_file)?;
+    let mut default_binaries = DefaultBinaries::read_from_file()?;
 
-    // Remove the installed binaries
+    // Remove the installed binaries. Entries of binaries that are already gone are dropped too
     for binary in &binaries_to_remove {
         if let Some(p) = binary.path.as_ref() {
+            if !Path::new(p).exists() {
+                println!("Binary {p} does not exist, removing its entry");
+                continue;
+            }
             println!("Found binary path: {p}");
             debug!("Removing binary: {p}");
-            std::fs::remove_file(p).with_context(|| format!("Cannot remove file {}", p))?;
+            tx.remove_file(Path::new(p))?;
             debug!("File removed: {p}");
             println!("Removed binary: {} from {p}", binary.binary_name);
         }
@@ -63,8 +56,7 @@ pub fn remove_component(binary: &str) -> Result<()> {
     for bin_name in default_binaries_to_remove {
         let default_bin_path = get_default_bin_dir().join(bin_name);
         if default_bin_path.exists() {
-            std::fs::remove_file(&default_bin_path)
-                .with_context(|| format!("Cannot remove file {}", default_bin_path.display()))?;
+            tx.remove_file(&default_bin_path)?;
             debug!(
                 "Removed {} from default binaries folder",
                 default_bin_path.display()
@@ -76,12 +68,13 @@ pub fn remove_component(binary: &str) -> Result<()> {
     }
 
     // Update default binaries file
-    write_json_file(&default_file, &default_binaries)?;
+    tx.write_json(&default_file_path()?, &default_binaries)?;
 
     // Update installed binaries metadata
     installed_binaries.remove_binary(binary);
     debug!("Removed {binary} from installed_binaries JSON file. Saving updated data");
-    installed_binaries.save_to_file()?;
+    tx.write_json(&installed_binaries_file()?, &installed_binaries)?;
+    tx.commit();
 
     Ok(())
 }
@@ -3,6 +3,7 @@
 
 use anyhow::{Result, anyhow};
 use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
@@ -21,9 +22,49 @@ pub fn write_json_file<T: Serialize>(path: &Path, data: &T) -> Result<()> {
             path.display()
         )
     })?;
-    let mut file =
-        File::create(path).map_err(|e| anyhow!("Cannot create file {}: {e}", path.display()))?;
-    file.write_all(s.as_bytes())
+    write_file_atomic(path, s.as_bytes())
+}
+
+/// Writes the file through a temporary file in the same directory that is then renamed over
+/// `path`, so that readers never see a partially written file.
+pub fn write_file_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let mut file = tempfile::NamedTempFile::new_in(parent)
+        .map_err(|e| anyhow!("Cannot create file in {}: {e}", parent.display()))?;
+    file.write_all(contents)
+        .and_then(|_| file.as_file().sync_all())
+        .map_err(|e| anyhow!("Cannot write to {}: {e}", path.display()))?;
+    file.persist(path)
         .map_err(|e| anyhow!("Cannot write to {}: {e}", path.display()))?;
     Ok(())
 }
+
+/// Computes the hex encoded SHA-256 digest of a file
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).map_err(|e| anyhow!("Cannot open file {}: {e}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| anyhow!("Cannot read file {}: {e}", path.display()))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_json_file_replaces_contents_without_leftovers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("state.json");
+        write_json_file(&path, &vec!["sui"]).unwrap();
+        write_json_file(&path, &vec!["mvr"]).unwrap();
+
+        let data: Vec<String> = read_json_file(&path).unwrap();
+        assert_eq!(data, vec!["mvr".to_string()]);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
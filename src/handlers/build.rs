@@ -0,0 +1,387 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Builds binaries from source with `cargo install`. Before a build, the Rust toolchain and the
+//! system dependencies are checked. Builds of the same repository share a target directory in the
+//! suiup cache, so that later builds only recompile what changed, and the complete cargo output
+//! is written to a log file in the cache.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Error, bail};
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::handlers::repair::confirm;
+use crate::history::format_timestamp;
+use crate::lock::lock_build;
+use crate::paths::{build_cache_dir, build_logs_dir};
+use crate::registry::BinaryConfig;
+use crate::toolchain::{
+    Platform, RequiredToolchain, command_version, fetch_toolchain_file, has_system_dependency,
+    has_toolchain, install_hint, installed_components, installed_toolchains, missing_components,
+};
+use crate::types::BuildOptions;
+
+/// Number of lines from the end of the build log shown when a build fails
+const FAILURE_LOG_LINES: usize = 30;
+
+const RUSTUP_HINT: &str = "Install Rust with rustup, see https://rustup.rs";
+
+/// A branch or tag of a binary's repository to build
+#[derive(Debug, Clone, Copy)]
+pub enum GitRef<'a> {
+    Branch(&'a str),
+    Tag(&'a str),
+}
+
+impl GitRef<'_> {
+    fn name(&self) -> &str {
+        match self {
+            GitRef::Branch(name) | GitRef::Tag(name) => name,
+        }
+    }
+
+    fn cargo_flag(&self) -> &'static str {
+        match self {
+            GitRef::Branch(_) => "--branch",
+            GitRef::Tag(_) => "--tag",
+        }
+    }
+}
+
+/// Checks that the Rust toolchain and the system dependencies for building the binary are
+/// installed, before starting a long build. The toolchain is the binary's `nightly_toolchain` or
+/// the one in the repository's `rust-toolchain.toml`; when rustup is available, a missing
+/// toolchain or component is installed after asking. Returns the toolchain to build with.
+pub async fn prepare_build(
+    config: &BinaryConfig,
+    git_ref: GitRef<'_>,
+    yes: bool,
+) -> Result<Option<String>, Error> {
+    let mut required = fetch_toolchain_file(&config.repository, git_ref.name())
+        .await
+        .unwrap_or_default();
+    if let Some(channel) = &config.nightly_toolchain {
+        required.channel = Some(channel.clone());
+    }
+
+    let toolchain = match command_version("rustup") {
+        Some(_) => ensure_toolchain(&required, yes)?,
+        None => {
+            if let Some(channel) = &required.channel {
+                if config.nightly_toolchain.is_some() {
+                    bail!(
+                        "Building {} requires the Rust {channel} toolchain, which needs rustup. {RUSTUP_HINT}",
+                        config.name
+                    );
+                }
+                println!(
+                    "Warning: {} uses the Rust {channel} toolchain, but rustup is not installed. Building with the installed toolchain",
+                    config.repository
+                );
+            }
+            None
+        }
+    };
+
+    for command in ["rustc", "cargo"] {
+        let mut cmd = Command::new(command);
+        if let Some(toolchain) = &toolchain {
+            cmd.arg(format!("+{toolchain}"));
+        }
+        match cmd.arg("--version").output() {
+            Ok(output) if output.status.success() => print!(
+                "{command} is installed: {}",
+                String::from_utf8_lossy(&output.stdout)
+            ),
+            _ => bail!("{command} is not installed. {RUSTUP_HINT}"),
+        }
+    }
+
+    let missing: Vec<&String> = config
+        .build_dependencies
+        .iter()
+        .filter(|dependency| !has_system_dependency(dependency))
+        .collect();
+    if !missing.is_empty() {
+        let platform = Platform::detect();
+        let hints: Vec<String> = missing
+            .iter()
+            .map(|dependency| format!("  {dependency}: {}", install_hint(dependency, platform)))
+            .collect();
+        bail!(
+            "Building {} requires these missing dependencies:\n{}",
+            config.name,
+            hints.join("\n")
+        );
+    }
+
+    Ok(toolchain)
+}
+
+/// Installs the required toolchain and components with rustup when they are missing, asking
+/// first unless `yes` is set. Returns the toolchain channel, if one is required.
+fn ensure_toolchain(required: &RequiredToolchain, yes: bool) -> Result<Option<String>, Error> {
+    let Some(channel) = &required.channel else {
+        return Ok(None);
+    };
+
+    let mut rustup = Command::new("rustup");
+    if !has_toolchain(&installed_toolchains()?, channel) {
+        if !yes
+            && !confirm(&format!(
+                "The Rust {channel} toolchain is not installed. Install it with rustup? [y/N] "
+            ))?
+        {
+            bail!(
+                "The build requires the Rust {channel} toolchain. Install it with `rustup toolchain install {channel}`"
+            );
+        }
+        rustup.args(["toolchain", "install", channel, "--profile", "minimal"]);
+        if !required.components.is_empty() {
+            rustup.args(["--component", &required.components.join(",")]);
+        }
+    } else {
+        let missing = missing_components(&installed_components(channel)?, &required.components);
+        if missing.is_empty() {
+            return Ok(Some(channel.clone()));
+        }
+        if !yes
+            && !confirm(&format!(
+                "The {} component(s) of the Rust {channel} toolchain are not installed. Install them with rustup? [y/N] ",
+                missing.join(", ")
+            ))?
+        {
+            bail!(
+                "The build requires the {} component(s). Install them with `rustup component add --toolchain {channel} {}`",
+                missing.join(", "),
+                missing.join(" ")
+            );
+        }
+        rustup.args(["component", "add", "--toolchain", channel]);
+        rustup.args(&missing);
+    }
+
+    let status = rustup.status().context("Cannot run rustup")?;
+    if !status.success() {
+        bail!("rustup failed to install the Rust {channel} toolchain");
+    }
+    Ok(Some(channel.clone()))
+}
+
+/// Runs `cargo install` for `binary` from the given branch or tag of the binary's repository,
+/// placing the result in `root/bin`. `debug` builds with the dev profile unless `build` sets a
+/// profile, and `toolchain` is the rustup toolchain to build with.
+pub fn cargo_install(
+    config: &BinaryConfig,
+    binary: &str,
+    git_ref: GitRef,
+    root: &Path,
+    debug: bool,
+    build: &BuildOptions,
+    toolchain: Option<&str>,
+) -> Result<(), Error> {
+    let mut args = vec![];
+    if let Some(toolchain) = toolchain {
+        args.push(format!("+{}", toolchain));
+    }
+    args.extend(
+        [
+            "install",
+            "--locked",
+            "--force",
+            "--git",
+            &config.repo_url(),
+            git_ref.cargo_flag(),
+            git_ref.name(),
+        ]
+        .map(String::from),
+    );
+    if let Some(cargo_package) = &config.cargo_package {
+        args.push(cargo_package.clone());
+        args.push("--bin".to_string());
+    }
+    args.push(binary.to_string());
+    args.push("--root".to_string());
+    args.push(root.to_string_lossy().to_string());
+    if debug && build.profile.is_none() {
+        args.push("--debug".to_string());
+    }
+    args.extend(build.cargo_args());
+
+    let target_dir = build_target_dir(config);
+    std::fs::create_dir_all(&target_dir)
+        .with_context(|| format!("Cannot create directory {}", target_dir.display()))?;
+    // Cargo locks the target directory itself, this lock keeps `cleanup --build-cache` from
+    // removing it during the build
+    let _lock = lock_build(&target_dir)?;
+
+    let log_path = build_log_file(binary, git_ref.name())?;
+    let mut cmd = Command::new("cargo");
+    cmd.args(&args).env("CARGO_TARGET_DIR", &target_dir);
+
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.green} {msg}")
+            .unwrap()
+            .tick_strings(&["-", "\\", "|", "/"]),
+    );
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb.set_message("Compiling...please wait");
+
+    let status = run_logged(cmd, &log_path, &pb)?;
+    pb.finish_with_message("Done!");
+
+    if !status.success() {
+        bail!(
+            "Error during installation:\n{}\nThe full build log is at {}",
+            log_tail(&log_path, FAILURE_LOG_LINES),
+            log_path.display()
+        );
+    }
+    println!("Build log: {}", log_path.display());
+    Ok(())
+}
+
+/// The target directory shared by all builds from the binary's repository
+pub fn build_target_dir(config: &BinaryConfig) -> PathBuf {
+    build_cache_dir().join(config.repository.replace('/', "_"))
+}
+
+/// A new log file for a build, named after the binary, branch or tag and start time
+fn build_log_file(binary: &str, git_ref: &str) -> Result<PathBuf, Error> {
+    let dir = build_logs_dir();
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Cannot create directory {}", dir.display()))?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    // e.g. 2024-02-29T123456
+    let timestamp = format_timestamp(now)
+        .trim_end_matches(" UTC")
+        .replace(' ', "T")
+        .replace(':', "");
+    let git_ref = git_ref.replace(['/', '\\'], "_");
+    Ok(dir.join(format!("{binary}-{git_ref}-{timestamp}.log")))
+}
+
+/// Runs the command with its stdout and stderr written to `log_path`, showing the latest
+/// stderr line, which is cargo's progress, in the spinner.
+fn run_logged(mut cmd: Command, log_path: &Path, pb: &ProgressBar) -> Result<ExitStatus, Error> {
+    let log = File::create(log_path)
+        .with_context(|| format!("Cannot create build log {}", log_path.display()))?;
+    let log = Arc::new(Mutex::new(log));
+
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Cannot run cargo")?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    let stdout_log = Arc::clone(&log);
+    let stdout_thread = std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = writeln!(stdout_log.lock().expect("log lock"), "{line}");
+        }
+    });
+    for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+        let _ = writeln!(log.lock().expect("log lock"), "{line}");
+        let line = line.trim();
+        if !line.is_empty() {
+            pb.set_message(line.chars().take(100).collect::<String>());
+        }
+    }
+    let _ = stdout_thread.join();
+
+    child.wait().context("Cannot wait for cargo")
+}
+
+/// The last `lines` lines of a build log
+fn log_tail(log_path: &Path, lines: usize) -> String {
+    let content = std::fs::read_to_string(log_path).unwrap_or_default();
+    let all: Vec<&str> = content.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn run_logged_captures_stdout_and_stderr() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log_path = dir.path().join("build.log");
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            "echo compiled; echo '   Compiling sui v1.0' >&2; exit 3",
+        ]);
+
+        let status = run_logged(cmd, &log_path, &ProgressBar::hidden()).unwrap();
+        assert_eq!(status.code(), Some(3));
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        assert!(log.contains("compiled\n"));
+        assert!(log.contains("   Compiling sui v1.0\n"));
+    }
+
+    #[test]
+    fn build_options_cargo_args() {
+        assert!(BuildOptions::default().cargo_args().is_empty());
+        let build = BuildOptions {
+            features: vec!["tracing".to_string(), "gas-profiler".to_string()],
+            no_default_features: true,
+            profile: Some("dev-assert".to_string()),
+            cargo_args: vec!["--jobs".to_string(), "4".to_string()],
+        };
+        assert_eq!(
+            build.cargo_args(),
+            [
+                "--features",
+                "tracing,gas-profiler",
+                "--no-default-features",
+                "--profile",
+                "dev-assert",
+                "--jobs",
+                "4"
+            ]
+        );
+        assert_eq!(
+            build.to_string(),
+            "features: tracing,gas-profiler; no default features; profile: dev-assert; cargo args: --jobs 4"
+        );
+    }
+
+    #[test]
+    fn build_options_nightly_version() {
+        assert_eq!(BuildOptions::default().nightly_version(), "nightly");
+        let tracing = BuildOptions {
+            features: vec!["tracing".to_string()],
+            ..Default::default()
+        };
+        let version = tracing.nightly_version();
+        assert!(version.starts_with("nightly-") && version.len() == "nightly-".len() + 8);
+        assert_eq!(version, tracing.clone().nightly_version());
+        let profile = BuildOptions {
+            profile: Some("dev-assert".to_string()),
+            ..Default::default()
+        };
+        assert_ne!(version, profile.nightly_version());
+    }
+
+    #[test]
+    fn log_tail_keeps_last_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log_path = dir.path().join("build.log");
+        std::fs::write(&log_path, "one\ntwo\nthree\n").unwrap();
+        assert_eq!(log_tail(&log_path, 2), "two\nthree");
+        assert_eq!(log_tail(&log_path, 10), "one\ntwo\nthree");
+    }
+}
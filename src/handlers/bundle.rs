@@ -0,0 +1,317 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline bundles: a single tar file with release archives and the metadata needed to install
+//! them on a machine without network access.
+
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Error, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::archive_used;
+use crate::commands::parse_component_with_version;
+use crate::fs_utils::{read_json_file, sha256_file, write_json_file};
+use crate::handlers::download::{detect_os_arch, download_release, target_os_arch};
+use crate::handlers::install::install_from_archive;
+use crate::handlers::release::release_list_cache_files;
+use crate::handlers::version::extract_version_from_release;
+use crate::paths::{get_default_bin_dir, get_suiup_cache_dir, release_archive_dir};
+use crate::registry::InstallationType;
+
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const ARCHIVES_DIR: &str = "archives";
+const METADATA_DIR: &str = "metadata";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub os: String,
+    pub arch: String,
+    pub binaries: Vec<BundleBinary>,
+    pub metadata: Vec<BundleFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BundleBinary {
+    pub binary_name: String,
+    pub network_release: String,
+    pub version: String,
+    pub archive: BundleFile,
+}
+
+/// A file inside the bundle, with its path relative to the bundle root
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BundleFile {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// Downloads the release archives for the given binaries and packs them, together with the
+/// cached release metadata, into a single bundle file.
+pub async fn handle_bundle_create(
+    out: &Path,
+    specs: &[String],
+    os: Option<&str>,
+    arch: Option<&str>,
+    include_prereleases: bool,
+    github_token: Option<String>,
+) -> Result<(), Error> {
+    if specs.is_empty() {
+        bail!(
+            "Specify at least one binary to bundle, e.g. `suiup bundle create --out sui.tar sui@testnet`"
+        );
+    }
+    let (os, arch) = target_os_arch(os, arch)?;
+
+    let staging = tempfile::tempdir().context("Cannot create temporary directory")?;
+    let archives_dir = staging.path().join(ARCHIVES_DIR);
+    let metadata_dir = staging.path().join(METADATA_DIR);
+    std::fs::create_dir_all(&archives_dir)
+        .with_context(|| format!("Cannot create directory {}", archives_dir.display()))?;
+    std::fs::create_dir_all(&metadata_dir)
+        .with_context(|| format!("Cannot create directory {}", metadata_dir.display()))?;
+
+    let mut binaries = vec![];
+    let mut metadata = vec![];
+    for spec in specs {
+        let command_metadata = parse_component_with_version(spec)?;
+        let name = command_metadata.name;
+        let config = name.config();
+        if config.installation_type != InstallationType::Archive {
+            bail!(
+                "{name} is a standalone binary and cannot be bundled. Only binaries released as archives are supported"
+            );
+        }
+
+        let network = config.effective_network(&command_metadata.network);
+        let filename = download_release(
+            config,
+            &network,
+            command_metadata.version.as_deref(),
+            &os,
+            &arch,
+            github_token.clone(),
+            include_prereleases,
+        )
+        .await?;
+        let version = extract_version_from_release(&filename)?;
+
+        // Binaries from the same repository share one archive, e.g. sui and sui-node
+        let bundled_archive = archives_dir.join(&filename);
+        if !bundled_archive.exists() {
+            let cached_archive = release_archive_dir().join(&filename);
+            std::fs::copy(&cached_archive, &bundled_archive).with_context(|| {
+                format!("Cannot copy archive {} to bundle", cached_archive.display())
+            })?;
+        }
+        binaries.push(BundleBinary {
+            binary_name: name.to_string(),
+            network_release: network,
+            version,
+            archive: BundleFile {
+                path: format!("{ARCHIVES_DIR}/{filename}"),
+                sha256: sha256_file(&bundled_archive)?,
+            },
+        });
+
+        let (releases_file, etag_file) = release_list_cache_files(&config.repository);
+        for file in [releases_file, etag_file] {
+            let Some(file_name) = file.file_name().and_then(|f| f.to_str()) else {
+                continue;
+            };
+            let bundled_file = metadata_dir.join(file_name);
+            if file.exists() && !bundled_file.exists() {
+                std::fs::copy(&file, &bundled_file).with_context(|| {
+                    format!("Cannot copy metadata file {} to bundle", file.display())
+                })?;
+                metadata.push(BundleFile {
+                    path: format!("{METADATA_DIR}/{file_name}"),
+                    sha256: sha256_file(&bundled_file)?,
+                });
+            }
+        }
+    }
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        os,
+        arch,
+        binaries,
+        metadata,
+    };
+    write_json_file(&staging.path().join(MANIFEST_FILE), &manifest)?;
+
+    let file =
+        File::create(out).with_context(|| format!("Cannot create bundle {}", out.display()))?;
+    let mut builder = tar::Builder::new(file);
+    builder
+        .append_dir_all(".", staging.path())
+        .with_context(|| format!("Cannot write bundle {}", out.display()))?;
+    builder
+        .finish()
+        .with_context(|| format!("Cannot write bundle {}", out.display()))?;
+
+    println!(
+        "Created bundle {} with {} binaries for {}-{}",
+        out.display(),
+        manifest.binaries.len(),
+        manifest.os,
+        manifest.arch
+    );
+    Ok(())
+}
+
+/// Installs all binaries from a bundle created with `suiup bundle create`, without accessing
+/// the network.
+pub fn handle_bundle_install(bundle: &Path, yes: bool) -> Result<(), Error> {
+    if !bundle.is_file() {
+        bail!("Bundle file {} does not exist", bundle.display());
+    }
+
+    let staging = tempfile::tempdir().context("Cannot create temporary directory")?;
+    let file =
+        File::open(bundle).with_context(|| format!("Cannot open bundle {}", bundle.display()))?;
+    tar::Archive::new(file)
+        .unpack(staging.path())
+        .with_context(|| format!("Cannot unpack bundle {}", bundle.display()))?;
+
+    let manifest: BundleManifest = read_json_file(&staging.path().join(MANIFEST_FILE))
+        .with_context(|| format!("{} is not a valid suiup bundle", bundle.display()))?;
+    if manifest.format_version != BUNDLE_FORMAT_VERSION {
+        bail!(
+            "Unsupported bundle format version {}. This suiup supports version {BUNDLE_FORMAT_VERSION}",
+            manifest.format_version
+        );
+    }
+
+    let (os, arch) = detect_os_arch()?;
+    if manifest.os != os || manifest.arch != arch {
+        bail!(
+            "Bundle was created for {}-{}, but this machine is {os}-{arch}",
+            manifest.os,
+            manifest.arch
+        );
+    }
+
+    // Verify everything before touching any state
+    for file in manifest
+        .binaries
+        .iter()
+        .map(|b| &b.archive)
+        .chain(&manifest.metadata)
+    {
+        verify_bundle_file(staging.path(), file)?;
+    }
+
+    let archives_dir = release_archive_dir();
+    std::fs::create_dir_all(&archives_dir)
+        .with_context(|| format!("Cannot create directory {}", archives_dir.display()))?;
+    let default_bin_dir = get_default_bin_dir();
+    std::fs::create_dir_all(&default_bin_dir)
+        .with_context(|| format!("Cannot create directory {}", default_bin_dir.display()))?;
+
+    // Release lists that are already cached locally might be newer, keep them
+    let cache_dir = get_suiup_cache_dir();
+    for file in &manifest.metadata {
+        let target = cache_dir.join(file_name(&file.path)?);
+        if !target.exists() {
+            std::fs::copy(staging.path().join(&file.path), &target)
+                .with_context(|| format!("Cannot copy metadata file to {}", target.display()))?;
+        }
+    }
+
+    for binary in &manifest.binaries {
+        let filename = file_name(&binary.archive.path)?;
+        let target = archives_dir.join(filename);
+        std::fs::copy(staging.path().join(&binary.archive.path), &target)
+            .with_context(|| format!("Cannot copy archive to {}", target.display()))?;
+        archive_used(&target);
+
+        install_from_archive(
+            &binary.binary_name,
+            &binary.network_release,
+            &binary.version,
+            filename,
+            false,
+            yes,
+            &[],
+        )?;
+    }
+
+    println!(
+        "Installed {} binaries from bundle {}",
+        manifest.binaries.len(),
+        bundle.display()
+    );
+    Ok(())
+}
+
+/// Checks that a bundle file stays within the bundle and matches its recorded hash
+fn verify_bundle_file(root: &Path, file: &BundleFile) -> Result<(), Error> {
+    let relative = PathBuf::from(&file.path);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        bail!("Invalid path {} in bundle manifest", file.path);
+    }
+
+    let path = root.join(&relative);
+    if !path.is_file() {
+        bail!("Bundle is missing {}", file.path);
+    }
+    let actual = sha256_file(&path)?;
+    if actual != file.sha256 {
+        bail!(
+            "Checksum mismatch for {} in bundle: expected {}, got {actual}",
+            file.path,
+            file.sha256
+        );
+    }
+    Ok(())
+}
+
+fn file_name(path: &str) -> Result<&str, Error> {
+    Path::new(path)
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| anyhow!("Invalid path {path} in bundle manifest"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn verify_bundle_file_checks_hash_and_path() {
+        let root = TempDir::new().unwrap();
+        std::fs::create_dir_all(root.path().join(ARCHIVES_DIR)).unwrap();
+        let archive = root.path().join(ARCHIVES_DIR).join("sui.tgz");
+        std::fs::write(&archive, b"archive").unwrap();
+        let sha256 = sha256_file(&archive).unwrap();
+
+        let valid = BundleFile {
+            path: format!("{ARCHIVES_DIR}/sui.tgz"),
+            sha256: sha256.clone(),
+        };
+        assert!(verify_bundle_file(root.path(), &valid).is_ok());
+
+        let tampered = BundleFile {
+            sha256: "0".repeat(64),
+            ..valid.clone()
+        };
+        let err = verify_bundle_file(root.path(), &tampered).unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+
+        let escaping = BundleFile {
+            path: "../sui.tgz".to_string(),
+            sha256,
+        };
+        let err = verify_bundle_file(root.path(), &escaping).unwrap_err();
+        assert!(err.to_string().contains("Invalid path"));
+    }
+}
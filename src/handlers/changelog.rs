@@ -0,0 +1,256 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::cmp::Ordering;
+
+use anyhow::{Error, anyhow, bail};
+use colored::Colorize;
+
+use crate::commands::parse_component_with_version;
+use crate::handlers::release::ensure_version_prefix;
+use crate::handlers::version::{compare_versions, is_nightly};
+use crate::registry::InstallationType;
+use crate::standalone::{StandaloneInstaller, standalone_tag_version};
+use crate::types::{InstalledBinaries, Release};
+
+/// Handles the `changelog` command: prints the release notes of every release after `from` up
+/// to `to`. `from` defaults to the installed version and `to` to the latest release.
+pub async fn handle_changelog(
+    spec: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+    github_token: Option<String>,
+) -> Result<(), Error> {
+    let metadata = parse_component_with_version(spec)?;
+    if metadata.version.is_some() && to.is_some() {
+        bail!("Use either a version in {spec} or --to, not both");
+    }
+    let name = metadata.name;
+    let config = name.config();
+    // Releases of standalone binaries are not tied to a network
+    let network = config
+        .network_based
+        .then(|| config.effective_network(&metadata.network));
+    let network_release = network.as_deref().unwrap_or(&config.default_network);
+
+    let releases = if config.installation_type == InstallationType::Standalone {
+        let mut installer = StandaloneInstaller::for_binary(config, github_token);
+        installer.get_releases().await?;
+        installer.releases().to_vec()
+    } else {
+        config.release_source(github_token).releases().await?
+    };
+
+    let to = match to.or(metadata.version.as_deref()) {
+        None | Some("latest") => latest_version(&releases, network.as_deref())
+            .ok_or_else(|| anyhow!("No {network_release} releases of {name} found"))?,
+        Some(version) => ensure_version_prefix(version),
+    };
+    let from = match from {
+        Some(version) => ensure_version_prefix(version),
+        None => InstalledBinaries::read_from_file()?
+            .binaries()
+            .iter()
+            .filter(|b| {
+                b.binary_name == config.name
+                    && b.network_release == network_release
+                    && !is_nightly(&b.version)
+            })
+            .map(|b| b.version.clone())
+            .max_by(|a, b| compare_versions(a, b))
+            .ok_or_else(|| {
+                anyhow!(
+                    "{name} is not installed for {network_release}. Use --from to choose the version the changelog starts after"
+                )
+            })?,
+    };
+    if compare_versions(&from, &to) == Ordering::Greater {
+        bail!("--from {from} is newer than --to {to}");
+    }
+
+    let changes = releases_between(&releases, network.as_deref(), &from, &to);
+    if changes.is_empty() {
+        println!("No {network_release} releases of {name} after {from} up to {to}");
+        return Ok(());
+    }
+    println!(
+        "{}\n",
+        format!("Changes in {name} {network_release} from {from} to {to}:").bold()
+    );
+    print!("{}", render_changelog(&changes));
+    Ok(())
+}
+
+/// The version of a release, when it is a release for `network`. Releases of binaries that are
+/// not network based have no network.
+fn release_version(release: &Release, network: Option<&str>) -> Option<String> {
+    match network {
+        Some(network) => {
+            if release.assets.iter().any(|a| a.name.contains(network)) {
+                release.version()
+            } else {
+                None
+            }
+        }
+        None => standalone_tag_version(&release.tag_name).or_else(|| release.version()),
+    }
+}
+
+/// The newest version that is neither a draft nor a pre-release
+fn latest_version(releases: &[Release], network: Option<&str>) -> Option<String> {
+    releases
+        .iter()
+        .filter(|release| release.is_installable(false))
+        .filter_map(|release| release_version(release, network))
+        .max_by(|a, b| compare_versions(a, b))
+}
+
+/// The releases with a version after `from` up to `to` included, newest first. Drafts and
+/// pre-releases are skipped.
+pub(crate) fn releases_between<'a>(
+    releases: &'a [Release],
+    network: Option<&str>,
+    from: &str,
+    to: &str,
+) -> Vec<&'a Release> {
+    let mut between: Vec<(String, &Release)> = releases
+        .iter()
+        .filter(|release| release.is_installable(false))
+        .filter_map(|release| Some((release_version(release, network)?, release)))
+        .filter(|(version, _)| {
+            compare_versions(version, from) == Ordering::Greater
+                && compare_versions(version, to) != Ordering::Greater
+        })
+        .collect();
+    between.sort_by(|(a, _), (b, _)| compare_versions(b, a));
+    between.into_iter().map(|(_, release)| release).collect()
+}
+
+/// Renders the release notes of releases as terminal text. Markdown headings are shown in bold,
+/// and breaking change sections, as well as lines that mention breaking changes, are
+/// highlighted.
+pub(crate) fn render_changelog(releases: &[&Release]) -> String {
+    let mut out = String::new();
+    for release in releases {
+        let title = match release.published_date() {
+            Some(date) => format!("{} ({date})", release.tag_name),
+            None => release.tag_name.clone(),
+        };
+        out.push_str(&format!("{}\n", title.bold().underline()));
+        let notes = release.body.as_deref().unwrap_or_default().trim();
+        if notes.is_empty() {
+            out.push_str(&format!("  {}\n", "No release notes".dimmed()));
+        }
+        // Level of the heading of the breaking change section the line is in
+        let mut breaking_level: Option<usize> = None;
+        for line in notes.lines() {
+            let line = line.trim_end();
+            let level = line.chars().take_while(|c| *c == '#').count();
+            if level > 0 && line[level..].starts_with(' ') {
+                let heading = line[level..].trim();
+                if breaking_level.is_some_and(|breaking| level <= breaking) {
+                    breaking_level = None;
+                }
+                if breaking_level.is_none() && is_breaking(heading) {
+                    breaking_level = Some(level);
+                }
+                if breaking_level.is_some() {
+                    out.push_str(&format!("  {}\n", heading.red().bold()));
+                } else {
+                    out.push_str(&format!("  {}\n", heading.bold()));
+                }
+            } else if breaking_level.is_some() || is_breaking(line) {
+                out.push_str(&format!("  {}\n", line.yellow()));
+            } else {
+                out.push_str(&format!("  {line}\n"));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn is_breaking(text: &str) -> bool {
+    text.to_ascii_lowercase().contains("breaking")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Asset;
+
+    fn release(tag: &str, body: &str) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            body: Some(body.to_string()),
+            assets: vec![Asset {
+                name: format!("sui-{tag}-ubuntu-x86_64.tgz"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn collects_releases_between_versions() {
+        let mut prerelease = release("testnet-v1.46.0", "");
+        prerelease.prerelease = true;
+        let releases = vec![
+            prerelease,
+            release("testnet-v1.45.0", ""),
+            release("devnet-v1.45.1", ""),
+            release("testnet-v1.44.2", ""),
+            release("testnet-v1.44.10", ""),
+            release("testnet-v1.43.0", ""),
+        ];
+        let tags = |releases: Vec<&Release>| -> Vec<String> {
+            releases.iter().map(|r| r.tag_name.clone()).collect()
+        };
+        assert_eq!(
+            tags(releases_between(
+                &releases,
+                Some("testnet"),
+                "v1.43.0",
+                "v1.46.0"
+            )),
+            vec!["testnet-v1.45.0", "testnet-v1.44.10", "testnet-v1.44.2"]
+        );
+        assert_eq!(
+            tags(releases_between(
+                &releases,
+                Some("testnet"),
+                "v1.44.2",
+                "v1.44.10"
+            )),
+            vec!["testnet-v1.44.10"]
+        );
+        assert_eq!(
+            latest_version(&releases, Some("testnet")),
+            Some("v1.45.0".to_string())
+        );
+        assert_eq!(
+            latest_version(&releases, Some("devnet")),
+            Some("v1.45.1".to_string())
+        );
+    }
+
+    #[test]
+    fn renders_release_notes_with_breaking_changes() {
+        let releases = [release(
+            "testnet-v1.45.0",
+            "## Protocol\n- Faster checkpoints\n## Breaking changes\n### CLI\n- Removed `sui client foo`\n## Other\n- Docs",
+        )];
+        let rendered = render_changelog(&releases.iter().collect::<Vec<_>>());
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert!(lines[0].contains("testnet-v1.45.0"));
+        assert!(lines[1].contains("Protocol"));
+        assert!(!lines[1].contains('#'));
+        assert_eq!(lines[2], "  - Faster checkpoints");
+        assert!(lines[3].contains("Breaking changes"));
+        assert!(lines[5].contains("Removed `sui client foo`"));
+        assert_eq!(lines[7], "  - Docs");
+
+        let rendered = render_changelog(&[&release("testnet-v1.44.0", "")]);
+        assert!(rendered.contains("No release notes"));
+    }
+}
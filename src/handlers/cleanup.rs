@@ -1,10 +1,14 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
 use anyhow::{Context, Result};
 
-use crate::paths::release_archive_dir;
+use crate::cache::evict_to_size;
+use crate::handlers::build::build_target_dir;
+use crate::lock::{FileLock, build_lock_file};
+use crate::paths::{build_cache_dir, build_logs_dir, get_suiup_cache_dir, release_archive_dir};
+use crate::registry::BinaryRegistry;
 
 /// Handles the `cleanup` command
 pub fn handle_cleanup(all: bool, days: u32, dry_run: bool) -> Result<()> {
@@ -134,6 +138,236 @@ pub fn handle_cleanup(all: bool, days: u32, dry_run: bool) -> Result<()> {
     Ok(())
 }
 
+/// Handles `cleanup --max-size`: removes the least recently used release archives until the
+/// cache fits in `max_size`
+pub fn handle_cleanup_to_size(max_size: u64, dry_run: bool) -> Result<()> {
+    let release_archive_dir = release_archive_dir();
+    println!(
+        "Release archives directory: {}",
+        release_archive_dir.display()
+    );
+    println!(
+        "Current cache size: {}",
+        format_file_size(calculate_dir_size(&release_archive_dir)?)
+    );
+    println!(
+        "Removing least recently used release archives to fit in {}...",
+        format_file_size(max_size)
+    );
+    let removed = evict_to_size(max_size, None, dry_run)?;
+    let removed_size: u64 = removed.iter().map(|a| a.size).sum();
+
+    if dry_run {
+        for archive in &removed {
+            println!(
+                "Would remove: {} ({})",
+                archive.path.display(),
+                format_file_size(archive.size)
+            );
+        }
+        println!(
+            "Would remove {} files totaling {} (dry run)",
+            removed.len(),
+            format_file_size(removed_size)
+        );
+    } else {
+        println!(
+            "Cleanup complete. {} files removed, {} freed",
+            removed.len(),
+            format_file_size(removed_size)
+        );
+        let total_size_after = calculate_dir_size(&release_archive_dir)?;
+        println!("New cache size: {}", format_file_size(total_size_after));
+    }
+    Ok(())
+}
+
+/// Handles `cleanup --metadata`: removes cached release lists that were not refreshed in the
+/// last `days` days, or all of them, together with their ETags. ETags without a release list are
+/// always removed: GitHub answers "not modified" to them and there would be no cached list to
+/// fall back to.
+pub fn handle_cleanup_metadata(all: bool, days: u32, dry_run: bool) -> Result<()> {
+    let cache_dir = get_suiup_cache_dir();
+    println!("Release lists directory: {}", cache_dir.display());
+    if !cache_dir.exists() {
+        println!("Cache directory does not exist, nothing to clean up.");
+        return Ok(());
+    }
+
+    let cutoff_duration = Duration::from_secs(60 * 60 * 24 * days as u64);
+    if all {
+        println!("Removing all cached release lists and ETags...");
+    } else {
+        println!(
+            "Removing release lists not refreshed in {} days and stale ETags...",
+            days
+        );
+    }
+
+    let mut stale = vec![];
+    for entry in fs::read_dir(&cache_dir)
+        .with_context(|| format!("Cannot read cache directory {}", cache_dir.display()))?
+    {
+        let entry = entry.with_context(|| {
+            format!("Cannot read an entry in directory {}", cache_dir.display())
+        })?;
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !path.is_file() {
+            continue;
+        }
+
+        if let Some(repo) = file_name
+            .strip_prefix("releases_")
+            .and_then(|name| name.strip_suffix(".txt"))
+        {
+            if all || file_age(&path)? > cutoff_duration {
+                stale.push(path.clone());
+                let etag = cache_dir.join(format!("etag_{repo}.txt"));
+                if etag.exists() {
+                    stale.push(etag);
+                }
+            }
+        } else if let Some(repo) = file_name
+            .strip_prefix("etag_")
+            .and_then(|name| name.strip_suffix(".txt"))
+        {
+            if all || !cache_dir.join(format!("releases_{repo}.txt")).exists() {
+                stale.push(path);
+            }
+        } else if file_name.starts_with("standalone_releases_")
+            && file_name.ends_with(".json")
+            && (all || file_age(&path)? > cutoff_duration)
+        {
+            stale.push(path);
+        }
+    }
+    stale.sort();
+    stale.dedup();
+
+    for path in &stale {
+        if dry_run {
+            println!("Would remove: {}", path.display());
+        } else {
+            println!("Removing: {}", path.display());
+            fs::remove_file(path)
+                .with_context(|| format!("Cannot remove file {}", path.display()))?;
+        }
+    }
+    if dry_run {
+        println!("Would remove {} files (dry run)", stale.len());
+    } else {
+        println!("Cleanup complete. {} files removed", stale.len());
+    }
+    Ok(())
+}
+
+/// Handles `cleanup --build-cache`: removes the target directories and logs of source builds.
+/// Target directories and logs of builds that are running are kept.
+pub fn handle_cleanup_build_cache(dry_run: bool) -> Result<()> {
+    let build_dir = build_cache_dir();
+    let logs_dir = build_logs_dir();
+    println!("Build cache directory: {}", build_dir.display());
+
+    let mut removed_size = 0;
+    // Target directories of builds that are running, whose logs are kept too
+    let mut running = vec![];
+    if build_dir.is_dir() {
+        for entry in fs::read_dir(&build_dir)
+            .with_context(|| format!("Cannot read directory {}", build_dir.display()))?
+        {
+            let path = entry
+                .with_context(|| {
+                    format!("Cannot read an entry in directory {}", build_dir.display())
+                })?
+                .path();
+            if !path.is_dir() {
+                continue;
+            }
+            let Some(_lock) = FileLock::try_acquire(&build_lock_file(&path))? else {
+                println!("Skipping {}, a build is using it", path.display());
+                running.push(path);
+                continue;
+            };
+            let size = calculate_dir_size(&path)?;
+            removed_size += size;
+            if dry_run {
+                println!(
+                    "Would remove: {} ({})",
+                    path.display(),
+                    format_file_size(size)
+                );
+            } else {
+                println!("Removing: {} ({})", path.display(), format_file_size(size));
+                fs::remove_dir_all(&path)
+                    .with_context(|| format!("Cannot remove directory {}", path.display()))?;
+            }
+        }
+    }
+
+    if logs_dir.is_dir() {
+        for entry in fs::read_dir(&logs_dir)
+            .with_context(|| format!("Cannot read directory {}", logs_dir.display()))?
+        {
+            let path = entry
+                .with_context(|| {
+                    format!("Cannot read an entry in directory {}", logs_dir.display())
+                })?
+                .path();
+            if !path.is_file() {
+                continue;
+            }
+            if log_target_dir(&path).is_some_and(|target_dir| running.contains(&target_dir)) {
+                println!("Skipping {}, its build is running", path.display());
+                continue;
+            }
+            let size = fs::metadata(&path)
+                .with_context(|| format!("Cannot read metadata for {}", path.display()))?
+                .len();
+            removed_size += size;
+            if dry_run {
+                println!(
+                    "Would remove: {} ({})",
+                    path.display(),
+                    format_file_size(size)
+                );
+            } else {
+                println!("Removing: {} ({})", path.display(), format_file_size(size));
+                fs::remove_file(&path)
+                    .with_context(|| format!("Cannot remove file {}", path.display()))?;
+            }
+        }
+    }
+
+    if dry_run {
+        println!("Would free {} (dry run)", format_file_size(removed_size));
+    } else {
+        println!("Cleanup complete. {} freed", format_file_size(removed_size));
+    }
+    Ok(())
+}
+
+/// The target directory of the build that wrote a log. Logs are named after the binary they
+/// build, e.g. `sui-node-main-2024-02-29T123456.log`.
+fn log_target_dir(log: &Path) -> Option<PathBuf> {
+    let file_name = log.file_name()?.to_str()?;
+    BinaryRegistry::global()
+        .all()
+        .iter()
+        .filter(|config| file_name.starts_with(&format!("{}-", config.name)))
+        .max_by_key(|config| config.name.len())
+        .map(build_target_dir)
+}
+
+fn file_age(path: &PathBuf) -> Result<Duration> {
+    let modified_time = fs::metadata(path)
+        .with_context(|| format!("Cannot read metadata for {}", path.display()))?
+        .modified()?;
+    Ok(SystemTime::now()
+        .duration_since(modified_time)
+        .unwrap_or_default())
+}
+
 fn calculate_dir_size(dir: &PathBuf) -> Result<u64> {
     if !dir.exists() {
         return Ok(0);
@@ -159,7 +393,7 @@ fn calculate_dir_size(dir: &PathBuf) -> Result<u64> {
 }
 
 /// Format file size in human readable format
-fn format_file_size(size: u64) -> String {
+pub(crate) fn format_file_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB", "EB"];
 
     if size == 0 {
@@ -1,29 +1,55 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cache::archive_used;
 use crate::handlers::release::{
     ensure_version_prefix, find_last_release_by_network, find_networks_with_version,
+    sends_github_token,
 };
-use crate::handlers::version::extract_version_from_release;
+use crate::lock::lock_download;
 use crate::registry::BinaryConfig;
-use crate::{handlers::release::release_list, paths::release_archive_dir, types::Release};
+use crate::release_source::{ReleaseSource, download_asset};
+use crate::{
+    paths::release_archive_dir,
+    types::{Asset, Release},
+};
 use anyhow::{Context, Error, anyhow, bail};
 use futures_util::StreamExt;
 use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
 use md5::Context as Md5Context;
-use reqwest::{
-    Client,
-    header::{HeaderMap, HeaderValue, USER_AGENT},
-};
-use serde::de::DeserializeOwned;
+use reqwest::Client;
 use std::fs::File;
 use std::io::Read;
-use std::{cmp::min, io::Write, path::PathBuf, time::Instant};
+use std::{
+    cmp::min,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
 use tracing::debug;
 
+/// Error for a release that has no archive for the requested OS and architecture
+#[derive(Debug)]
+pub struct AssetNotFound {
+    pub os: String,
+    pub arch: String,
+    /// The version of the release, when it is known
+    pub version: Option<String>,
+}
+
+impl std::fmt::Display for AssetNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Asset not found for {}-{}", self.os, self.arch)
+    }
+}
+
+impl std::error::Error for AssetNotFound {}
+
 fn find_cached_release_archive(
-    tag: &str,
+    config: &BinaryConfig,
+    network: &str,
+    version: &str,
     os: &str,
     arch: &str,
 ) -> Result<Option<String>, anyhow::Error> {
@@ -32,18 +58,33 @@ fn find_cached_release_archive(
         return Ok(None);
     }
 
+    let tag = format!("{}-{}", network, version);
+    let pattern = config.asset_pattern();
+    let values = config.asset_values(Some(network), Some(version), os, arch);
     for entry in std::fs::read_dir(&cache_dir)
         .with_context(|| format!("Cannot read cache directory {}", cache_dir.display()))?
     {
         let entry =
             entry.with_context(|| format!("Cannot read entry in {}", cache_dir.display()))?;
         let filename = entry.file_name().to_string_lossy().to_string();
+        // Downloads in progress and their locks are not archives
+        if filename.ends_with(".part") || filename.ends_with(".lock") {
+            continue;
+        }
 
-        if filename.contains(tag)
-            && filename.contains(os)
-            && filename.contains(arch)
-            && (filename.ends_with(".tgz") || filename.ends_with(".zip"))
-        {
+        let matches = match &pattern {
+            Some(pattern) => pattern.matches(&filename, &values),
+            None => {
+                filename.contains(&tag)
+                    && filename.contains(os)
+                    && filename.contains(arch)
+                    && [".tgz", ".tar.gz", ".tar.xz", ".tar.zst", ".zip"]
+                        .iter()
+                        .any(|ext| filename.ends_with(ext))
+            }
+        };
+        if matches {
+            archive_used(&entry.path());
             return Ok(Some(filename));
         }
     }
@@ -126,6 +167,58 @@ fn generate_network_suggestions_error(
 
 /// Detects the current OS and architecture
 pub fn detect_os_arch() -> Result<(String, String), Error> {
+    let (os, arch) = host_os_arch()?;
+    println!("Detected: {os}-{arch}...");
+    Ok((os.to_string(), arch.to_string()))
+}
+
+/// Resolves the OS and architecture to download binaries for. Values that are not provided
+/// are taken from the `SUIUP_TARGET` environment variable (e.g. `linux-x86_64`), and default to
+/// the host platform otherwise.
+pub fn target_os_arch(os: Option<&str>, arch: Option<&str>) -> Result<(String, String), Error> {
+    let env_target = std::env::var("SUIUP_TARGET")
+        .ok()
+        .filter(|target| !target.trim().is_empty());
+    let (env_os, env_arch) = match &env_target {
+        Some(target) => {
+            let (os, arch) = target.trim().split_once('-').ok_or_else(|| {
+                anyhow!("Invalid SUIUP_TARGET: {target}. Expected <os>-<arch>, e.g. linux-x86_64")
+            })?;
+            (Some(os), Some(arch))
+        }
+        None => (None, None),
+    };
+    let os = os.or(env_os);
+    let arch = arch.or(env_arch);
+
+    if os.is_none() && arch.is_none() {
+        return detect_os_arch();
+    }
+
+    let (host_os, host_arch) = host_os_arch()?;
+    let os = match os {
+        Some(os) => normalize_os(os)?,
+        None => host_os,
+    };
+    let arch = normalize_arch(os, arch.unwrap_or(host_arch))?;
+
+    println!("Target: {os}-{arch}...");
+    Ok((os.to_string(), arch.to_string()))
+}
+
+/// Parses an `<os>-<arch>` platform, e.g. `linux-x86_64`, into the names used in release
+/// asset names
+pub(crate) fn parse_platform(platform: &str) -> Result<(String, String), Error> {
+    let (os, arch) = platform.trim().split_once('-').ok_or_else(|| {
+        anyhow!("Invalid platform: {platform}. Expected <os>-<arch>, e.g. linux-x86_64")
+    })?;
+    let os = normalize_os(os)?;
+    let arch = normalize_arch(os, arch)?;
+    Ok((os.to_string(), arch.to_string()))
+}
+
+/// The host OS and architecture, named as in the release archives
+pub(crate) fn host_os_arch() -> Result<(&'static str, &'static str), Error> {
     let os = match whoami::platform() {
         whoami::Platform::Linux => "ubuntu",
         whoami::Platform::Windows => "windows",
@@ -138,109 +231,171 @@ pub fn detect_os_arch() -> Result<(String, String), Error> {
         "aarch64" => "aarch64",
         _ => bail!("Unsupported architecture. Supported only: x86_64, aarch64"),
     };
+    Ok((os, arch))
+}
 
-    println!("Detected: {os}-{arch}...");
-    Ok((os.to_string(), arch.to_string()))
+/// Maps an OS name to the one used in release asset names
+fn normalize_os(os: &str) -> Result<&'static str, Error> {
+    match os.to_lowercase().as_str() {
+        "linux" | "ubuntu" => Ok("ubuntu"),
+        "macos" | "darwin" | "mac" => Ok("macos"),
+        "windows" => Ok("windows"),
+        _ => bail!("Unsupported OS: {os}. Supported only: linux, windows, macos"),
+    }
+}
+
+/// Maps an architecture name to the one used in release asset names for the given OS
+fn normalize_arch(os: &str, arch: &str) -> Result<&'static str, Error> {
+    match arch.to_lowercase().as_str() {
+        "x86_64" | "amd64" => Ok("x86_64"),
+        "aarch64" | "arm64" if os == "macos" => Ok("arm64"),
+        "aarch64" | "arm64" => Ok("aarch64"),
+        _ => bail!("Unsupported architecture: {arch}. Supported only: x86_64, aarch64"),
+    }
+}
+
+/// Downloads the release archive with the given version, or the latest release for the network
+/// when no version is given, and returns its file name. Pre-releases are only downloaded with
+/// `include_prereleases`.
+pub async fn download_release(
+    config: &BinaryConfig,
+    network: &str,
+    version: Option<&str>,
+    os: &str,
+    arch: &str,
+    github_token: Option<String>,
+    include_prereleases: bool,
+) -> Result<String, anyhow::Error> {
+    let source = config.release_source(github_token);
+    match version {
+        Some(version) => {
+            download_release_at_version(
+                source.as_ref(),
+                config,
+                network,
+                version,
+                (os, arch),
+                include_prereleases,
+            )
+            .await
+        }
+        None => {
+            download_latest_release(
+                source.as_ref(),
+                config,
+                network,
+                os,
+                arch,
+                include_prereleases,
+            )
+            .await
+        }
+    }
 }
 
 /// Downloads a release with a specific version
 /// The network is used to filter the release
 pub async fn download_release_at_version(
-    repo_slug: &str,
+    source: &dyn ReleaseSource,
     config: &BinaryConfig,
     network: &str,
     version: &str,
-    github_token: Option<String>,
+    (os, arch): (&str, &str),
+    include_prereleases: bool,
 ) -> Result<String, anyhow::Error> {
-    let (os, arch) = detect_os_arch()?;
-
     // Ensure version has 'v' prefix for GitHub release tags
     let version = ensure_version_prefix(version);
 
     let tag = format!("{}-{}", network, version);
 
-    if let Some(filename) = find_cached_release_archive(&tag, &os, &arch)? {
+    if let Some(filename) = find_cached_release_archive(config, network, &version, os, arch)? {
         println!("Found {filename} in cache");
         return Ok(filename);
     }
 
     println!("Searching for release with tag: {}...", tag);
-    let client = reqwest::Client::new();
-    let mut headers = HeaderMap::new();
-
-    let releases = release_list(repo_slug, github_token.clone()).await?.0;
+    let releases = source.releases().await?;
 
     if let Some(release) = releases
         .iter()
-        .find(|r| r.assets.iter().any(|a| a.name.contains(&tag)))
+        .find(|r| !r.draft && r.assets.iter().any(|a| a.name.contains(&tag)))
     {
-        download_asset_from_github(release, &os, &arch, github_token).await
+        ensure_installable(release, &tag, include_prereleases)?;
+        download_release_asset(source, release, config, network, Some(&version), os, arch).await
     } else {
-        headers.insert(USER_AGENT, HeaderValue::from_static("suiup"));
-
-        // Add authorization header if token is provided
-        if let Some(token) = &github_token {
-            let auth_header = HeaderValue::from_str(&format!("token {}", token))
-                .map_err(|e| anyhow!("Invalid GitHub token for Authorization header: {e}"))?;
-            headers.insert("Authorization", auth_header);
-        }
-
-        let url = format!(
-            "https://api.github.com/repos/{repo_slug}/releases/tags/{}",
-            tag
-        );
-        let response = client
-            .get(&url)
-            .headers(headers)
-            .send()
-            .await
-            .with_context(|| format!("Failed to send request to {url}"))?;
-
-        if !response.status().is_success() {
+        let Some(release) = source.release_by_tag(&tag).await? else {
             return Err(generate_network_suggestions_error(
                 config,
                 &releases,
                 Some(&version),
                 network,
             ));
-        }
+        };
+        ensure_installable(&release, &tag, include_prereleases)?;
+        download_release_asset(source, &release, config, network, Some(&version), os, arch).await
+    }
+}
 
-        let release: Release = parse_json_response(response, &url, "GitHub release").await?;
-        download_asset_from_github(&release, &os, &arch, github_token).await
+/// Checks that a release requested by its tag can be installed: drafts never are, and
+/// pre-releases only with `--pre`
+pub(crate) fn ensure_installable(
+    release: &Release,
+    tag: &str,
+    include_prereleases: bool,
+) -> Result<(), Error> {
+    if release.draft {
+        bail!("Release {tag} is a draft and cannot be installed");
     }
+    if release.prerelease && !include_prereleases {
+        bail!("Release {tag} is a pre-release. Use --pre to install pre-releases");
+    }
+    Ok(())
 }
 
-/// Downloads the latest release for a given network
+/// Downloads the latest release for a given network, skipping drafts, and pre-releases unless
+/// `include_prereleases` is set
 pub async fn download_latest_release(
-    repo_slug: &str,
+    source: &dyn ReleaseSource,
     config: &BinaryConfig,
     network: &str,
-    github_token: Option<String>,
+    os: &str,
+    arch: &str,
+    include_prereleases: bool,
 ) -> Result<String, anyhow::Error> {
     println!("Downloading release list");
-    debug!("Downloading release list for repo: {repo_slug} and network: {network}");
-    let releases = release_list(repo_slug, github_token.clone()).await?;
-
-    let (os, arch) = detect_os_arch()?;
-
-    let last_release = find_last_release_by_network(releases.0.clone(), network)
+    debug!(
+        "Downloading release list from {} for network: {network}",
+        source.describe()
+    );
+    let releases: Vec<Release> = source
+        .releases()
+        .await?
+        .into_iter()
+        .filter(|release| release.is_installable(include_prereleases))
+        .collect();
+
+    let last_release = find_last_release_by_network(releases.clone(), network)
         .await
-        .ok_or_else(|| generate_network_suggestions_error(config, &releases.0, None, network))?;
+        .ok_or_else(|| generate_network_suggestions_error(config, &releases, None, network))?;
 
     println!(
         "Last {network} release: {}",
-        extract_version_from_release(&last_release.assets[0].name)?
+        last_release
+            .version()
+            .ok_or_else(|| anyhow!("Could not extract version from release"))?
     );
 
-    download_asset_from_github(&last_release, &os, &arch, github_token).await
+    download_release_asset(source, &last_release, config, network, None, os, arch).await
 }
 
 pub async fn download_file(
     url: &str,
-    download_to: &PathBuf,
+    download_to: &Path,
     name: &str,
     github_token: Option<String>,
 ) -> Result<String, Error> {
+    // Another process might be downloading the same file, it is reused once that finishes
+    let _lock = lock_download(download_to)?;
     let client = Client::new();
 
     // Start with a basic request
@@ -248,7 +403,7 @@ pub async fn download_file(
 
     // Add authorization header if token is provided and the URL is from GitHub
     if let Some(token) = github_token
-        && url.contains("github.com")
+        && sends_github_token(url)
     {
         request = request.header("Authorization", format!("token {}", token));
     }
@@ -331,14 +486,41 @@ pub async fn download_file(
         })?;
     }
 
+    // The download goes through a temporary file, so the cache never has a partial archive,
+    // even when the download is interrupted
+    let partial = partial_download_file(download_to);
+    if let Err(e) = write_download(response, &partial, download_to, total_size, name).await {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, download_to)
+        .with_context(|| format!("Cannot move download into {}", download_to.display()))?;
+
+    Ok(name.to_string())
+}
+
+/// The temporary file a download is written to, `<file>.part`
+pub fn partial_download_file(download_to: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.part", download_to.display()))
+}
+
+/// Streams the response into `partial` and checks it against the `.md5` file of
+/// `download_to`, if there is one
+async fn write_download(
+    response: reqwest::Response,
+    partial: &Path,
+    download_to: &Path,
+    total_size: u64,
+    name: &str,
+) -> Result<(), Error> {
     let pb = ProgressBar::new(total_size);
     pb.set_style(ProgressStyle::default_bar()
         .template("Downloading release: {spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta}) {msg}")
         .unwrap()
         .progress_chars("=>-"));
 
-    let mut file = std::fs::File::create(download_to)
-        .with_context(|| format!("Cannot create download file {}", download_to.display()))?;
+    let mut file = std::fs::File::create(partial)
+        .with_context(|| format!("Cannot create download file {}", partial.display()))?;
     let mut downloaded: u64 = 0;
     let mut stream = response.bytes_stream();
     let start = Instant::now();
@@ -346,7 +528,7 @@ pub async fn download_file(
     while let Some(item) = stream.next().await {
         let chunk = item?;
         file.write_all(&chunk)
-            .with_context(|| format!("Cannot write to download file {}", download_to.display()))?;
+            .with_context(|| format!("Cannot write to download file {}", partial.display()))?;
         let new = min(downloaded + (chunk.len() as u64), total_size);
         downloaded = new;
         pb.set_position(new);
@@ -363,10 +545,10 @@ pub async fn download_file(
     // After download, check md5 if .md5 file exists
     let md5_path = download_to.with_extension("md5");
     if md5_path.exists() {
-        let mut file = File::open(download_to).with_context(|| {
+        let mut file = File::open(partial).with_context(|| {
             format!(
                 "Cannot open downloaded file for MD5 check {}",
-                download_to.display()
+                partial.display()
             )
         })?;
         let mut hasher = Md5Context::new();
@@ -375,7 +557,7 @@ pub async fn download_file(
             let n = file.read(&mut buffer).with_context(|| {
                 format!(
                     "Cannot read downloaded file for MD5 check {}",
-                    download_to.display()
+                    partial.display()
                 )
             })?;
             if n == 0 {
@@ -397,52 +579,56 @@ pub async fn download_file(
 
         println!("MD5 check passed for {name}");
     }
-
-    Ok(name.to_string())
-}
-
-async fn parse_json_response<T>(
-    response: reqwest::Response,
-    request_url: &str,
-    response_name: &str,
-) -> Result<T, anyhow::Error>
-where
-    T: DeserializeOwned,
-{
-    let response_body = response
-        .text()
-        .await
-        .with_context(|| format!("Cannot read {response_name} response body from {request_url}"))?;
-
-    serde_json::from_str(&response_body).map_err(|e| {
-        anyhow!(
-            "Failed to deserialize {response_name} response from {request_url}: {e}\nResponse body:\n{response_body}"
-        )
-    })
+    Ok(())
 }
 
-/// Downloads the archived release from GitHub and returns the file name
-/// The `network, os, and arch` parameters are used to retrieve the correct release for the target
-/// architecture and OS
-async fn download_asset_from_github(
+/// Downloads the release asset for the given platform and returns its file name. Binaries with
+/// an asset pattern use it to pick the asset, otherwise the first asset with the OS and
+/// architecture in its name is used.
+async fn download_release_asset(
+    source: &dyn ReleaseSource,
     release: &Release,
+    config: &BinaryConfig,
+    network: &str,
+    version: Option<&str>,
     os: &str,
     arch: &str,
-    github_token: Option<String>,
 ) -> Result<String, anyhow::Error> {
-    let asset = release
-        .assets
-        .iter()
-        .find(|&a| a.name.contains(arch) && a.name.contains(os.to_string().to_lowercase().as_str()))
-        .ok_or_else(|| anyhow!("Asset not found for {os}-{arch}"))?;
+    let asset =
+        find_release_asset(release, config, network, version, os, arch).ok_or_else(|| {
+            AssetNotFound {
+                os: os.to_string(),
+                arch: arch.to_string(),
+                version: version.map(str::to_string).or_else(|| release.version()),
+            }
+        })?;
 
-    let url = asset.clone().browser_download_url;
-    let name = asset.clone().name;
-    let path = release_archive_dir();
-    let mut file_path = path.clone();
-    file_path.push(&asset.name);
+    let file_path = release_archive_dir().join(&asset.name);
+    download_asset(source, asset, &file_path, &asset.name).await?;
+    archive_used(&file_path);
+    Ok(asset.name.clone())
+}
 
-    download_file(&url, &file_path, &name, github_token).await
+pub(crate) fn find_release_asset<'a>(
+    release: &'a Release,
+    config: &BinaryConfig,
+    network: &str,
+    version: Option<&str>,
+    os: &str,
+    arch: &str,
+) -> Option<&'a Asset> {
+    match config.asset_pattern() {
+        Some(pattern) => {
+            let values = config.asset_values(Some(network), version, os, arch);
+            release
+                .assets
+                .iter()
+                .find(|a| pattern.matches(&a.name, &values))
+        }
+        None => release.assets.iter().find(|&a| {
+            a.name.contains(arch) && a.name.contains(os.to_string().to_lowercase().as_str())
+        }),
+    }
 }
 
 #[cfg(test)]
@@ -458,11 +644,90 @@ mod tests {
                 .map(|name| Asset {
                     name: name.to_string(),
                     browser_download_url: format!("https://example.com/{}", name),
+                    ..Default::default()
                 })
                 .collect(),
+            ..Default::default()
         }
     }
 
+    #[tokio::test]
+    async fn interrupted_download_leaves_no_file() {
+        use std::io::Write;
+
+        // The server announces 1000 bytes and closes the connection after 10
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/tool.tgz", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = std::io::Read::read(&mut stream, &mut request);
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Length: 1000\r\nConnection: close\r\n\r\n0123456789",
+            );
+        });
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let destination = dir.path().join("tool.tgz");
+        assert!(
+            download_file(&url, &destination, "tool.tgz", None)
+                .await
+                .is_err()
+        );
+        assert!(!destination.exists());
+        assert!(!partial_download_file(&destination).exists());
+    }
+
+    #[test]
+    fn test_find_release_asset_uses_asset_pattern() {
+        let release = create_test_release(vec![
+            "sui-testnet-v1.44.2-ubuntu-x86_64-debug.tgz",
+            "sui-testnet-v1.44.2-ubuntu-x86_64.tgz",
+            "sui-testnet-v1.44.2-ubuntu-aarch64.tgz",
+        ]);
+
+        // The heuristic picks the first asset containing the OS and architecture
+        let mut config = BinaryRegistry::global().get("sui").unwrap().clone();
+        config.asset_pattern = None;
+        let asset = find_release_asset(&release, &config, "testnet", None, "ubuntu", "x86_64");
+        assert_eq!(
+            asset.unwrap().name,
+            "sui-testnet-v1.44.2-ubuntu-x86_64-debug.tgz"
+        );
+
+        config.asset_pattern = Some("sui-{network}-{version}-{os}-{arch}.tgz".to_string());
+        let asset = find_release_asset(&release, &config, "testnet", None, "ubuntu", "x86_64");
+        assert_eq!(asset.unwrap().name, "sui-testnet-v1.44.2-ubuntu-x86_64.tgz");
+
+        let asset = find_release_asset(
+            &release,
+            &config,
+            "testnet",
+            Some("v1.44.3"),
+            "ubuntu",
+            "x86_64",
+        );
+        assert!(asset.is_none());
+    }
+
+    #[test]
+    fn test_normalize_os_and_arch() {
+        assert_eq!(normalize_os("linux").unwrap(), "ubuntu");
+        assert_eq!(normalize_os("Darwin").unwrap(), "macos");
+        assert!(normalize_os("freebsd").is_err());
+
+        assert_eq!(normalize_arch("ubuntu", "amd64").unwrap(), "x86_64");
+        assert_eq!(normalize_arch("ubuntu", "arm64").unwrap(), "aarch64");
+        assert_eq!(normalize_arch("macos", "aarch64").unwrap(), "arm64");
+        assert!(normalize_arch("ubuntu", "riscv64").is_err());
+
+        assert_eq!(
+            parse_platform("macos-aarch64").unwrap(),
+            ("macos".to_string(), "arm64".to_string())
+        );
+        assert!(parse_platform("linux").is_err());
+    }
+
     #[test]
     fn test_generate_network_suggestions_error_with_version() {
         let config = BinaryRegistry::global().get("sui").unwrap();
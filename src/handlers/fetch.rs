@@ -0,0 +1,83 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::Path;
+
+use anyhow::{Context, Error, bail};
+
+use super::extract_binary;
+use crate::commands::parse_component_with_version;
+use crate::handlers::download::{download_release, target_os_arch};
+use crate::handlers::version::extract_version_from_release;
+use crate::paths::release_archive_dir;
+use crate::registry::InstallationType;
+use crate::standalone::StandaloneInstaller;
+
+/// Downloads the given binaries for the target OS and architecture and places them in `dir`.
+///
+/// Unlike `install`, this does not record the binaries as installed nor changes the defaults,
+/// which makes it suitable for preparing binaries for another machine, e.g. a Docker image.
+pub async fn handle_fetch(
+    specs: &[String],
+    dir: &Path,
+    os: Option<&str>,
+    arch: Option<&str>,
+    include_prereleases: bool,
+    github_token: Option<String>,
+) -> Result<(), Error> {
+    if specs.is_empty() {
+        bail!("Specify at least one binary to fetch, e.g. `suiup fetch sui@testnet --dir ./bin`");
+    }
+    let (os, arch) = target_os_arch(os, arch)?;
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Cannot create directory {}", dir.display()))?;
+
+    let extension = if os == "windows" { ".exe" } else { "" };
+
+    for spec in specs {
+        let command_metadata = parse_component_with_version(spec)?;
+        let name = command_metadata.name;
+        let config = name.config();
+        let binary = format!("{name}{extension}");
+        let destination = dir.join(&binary);
+
+        let version = match config.installation_type {
+            InstallationType::Archive => {
+                let network = config.effective_network(&command_metadata.network);
+                let filename = download_release(
+                    config,
+                    &network,
+                    command_metadata.version.as_deref(),
+                    &os,
+                    &arch,
+                    github_token.clone(),
+                    include_prereleases,
+                )
+                .await?;
+                extract_binary(
+                    &release_archive_dir().join(&filename),
+                    &binary,
+                    &destination,
+                )?;
+                extract_version_from_release(&filename)?
+            }
+            InstallationType::Standalone => {
+                let mut installer = StandaloneInstaller::for_binary(config, github_token.clone())
+                    .with_prereleases(include_prereleases);
+                installer
+                    .fetch_version(
+                        command_metadata.version,
+                        name.as_str(),
+                        &os,
+                        &arch,
+                        &destination,
+                    )
+                    .await?
+            }
+        };
+
+        println!("Fetched {name} {version} to {}", destination.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Error, bail};
+
+use crate::commands::ComponentCommands;
+use crate::fs_utils::sha256_file;
+use crate::handle_commands::handle_cmd;
+use crate::handlers::repair::{binary_file_path, confirm, default_source_path, executable_name};
+use crate::handlers::version::is_nightly;
+use crate::history::{StateSnapshot, read_operations, record_operation, suspend_recording};
+use crate::lock::lock_state;
+use crate::paths::{binaries_dir, default_file_path, get_default_bin_dir, installed_binaries_file};
+use crate::registry::BinaryRegistry;
+use crate::transaction::Transaction;
+use crate::types::{BinaryVersion, DefaultBinaries, InstalledBinaries};
+
+/// Handles the `history` command
+pub fn handle_history(limit: Option<usize>) -> Result<(), Error> {
+    let operations = read_operations()?;
+    if operations.is_empty() {
+        println!("No operations recorded yet.");
+        return Ok(());
+    }
+    let skip = limit.map_or(0, |limit| operations.len().saturating_sub(limit));
+    for operation in operations.iter().skip(skip).rev() {
+        print!("{operation}");
+    }
+    Ok(())
+}
+
+/// Handles the `undo` command: brings the installed and default binaries back to the state
+/// before the last `count` operations, reinstalling binaries that were removed since.
+pub async fn handle_undo(
+    count: usize,
+    yes: bool,
+    github_token: Option<String>,
+) -> Result<(), Error> {
+    if count == 0 {
+        bail!("Specify at least one operation to undo");
+    }
+    let operations = read_operations()?;
+    if operations.len() < count {
+        bail!(
+            "Cannot undo {count} operations, the history has {}. Use `suiup history` to list them",
+            operations.len()
+        );
+    }
+
+    let undone = &operations[operations.len() - count..];
+    let target = undone[0].before.clone();
+    let current = StateSnapshot::read()?;
+
+    println!("Undoing:");
+    for operation in undone.iter().rev() {
+        print!("{operation}");
+    }
+    let changes = current.diff(&target);
+    if changes.is_empty() {
+        println!("The installed binaries already match the state before these operations.");
+        return Ok(());
+    }
+    println!("Changes:");
+    for line in &changes {
+        println!("    {line}");
+    }
+    if !yes && !confirm("Apply these changes? [y/N] ")? {
+        println!("No changes were made.");
+        return Ok(());
+    }
+
+    let guard = suspend_recording();
+    for binary in &target.installed {
+        if existing_binary_path(binary).is_none() {
+            reinstall(binary, github_token.as_deref()).await?;
+        }
+    }
+    restore_state(&current, &target)?;
+    drop(guard);
+
+    let _lock = lock_state()?;
+    record_operation(current, StateSnapshot::read()?)?;
+    println!("Undo completed.");
+    Ok(())
+}
+
+/// Installs a binary that was removed after the state that is restored
+async fn reinstall(binary: &BinaryVersion, github_token: Option<&str>) -> Result<(), Error> {
+    let registry = BinaryRegistry::global();
+    // Binaries installed with `--with` are reinstalled from the archive of their binary
+    let (name, with) = match registry.member_of(&binary.binary_name) {
+        Some(parent) => (parent.name.as_str(), vec![binary.binary_name.clone()]),
+        None => (binary.binary_name.as_str(), vec![]),
+    };
+    if is_nightly(&binary.version) || !registry.contains(name) {
+        println!(
+            "Cannot reinstall {binary} [{}] automatically, install it again with `suiup install`",
+            binary.network_release
+        );
+        return Ok(());
+    }
+
+    let component = if binary.network_release == "standalone" {
+        format!("{name}@{}", binary.version)
+    } else {
+        format!("{name}@{}-{}", binary.network_release, binary.version)
+    };
+    println!("Reinstalling {binary} [{}]", binary.network_release);
+    handle_cmd(
+        ComponentCommands::Add {
+            component,
+            debug: binary.debug,
+            nightly: None,
+            yes: true,
+            from_file: None,
+            from_url: None,
+            with,
+            build: Box::default(),
+            build_from_source_fallback: false,
+            // The exact version that was installed, which might be a pre-release
+            pre: true,
+        },
+        github_token,
+    )
+    .await
+}
+
+/// Writes the target state files, removes binaries that are not part of it and copies the
+/// default binaries.
+fn restore_state(current: &StateSnapshot, target: &StateSnapshot) -> Result<(), Error> {
+    let mut tx = Transaction::new()?;
+
+    let mut installed = InstalledBinaries::read_from_file()?;
+    for binary in &current.installed {
+        if !target.installed.contains(binary) {
+            installed.remove_version(binary);
+            if let Some(path) = existing_binary_path(binary)
+                && !target
+                    .installed
+                    .iter()
+                    .any(|b| existing_binary_path(b).as_ref() == Some(&path))
+            {
+                tx.remove_file(&path)?;
+            }
+        }
+    }
+    for binary in &target.installed {
+        if existing_binary_path(binary).is_some() {
+            installed.add_binary(binary.clone());
+        }
+    }
+    tx.write_json(&installed_binaries_file()?, &installed)?;
+
+    let default_bin_dir = get_default_bin_dir();
+    let mut defaults = DefaultBinaries::read_from_file()?;
+    for name in current.defaults.keys() {
+        if !target.defaults.contains_key(name) {
+            defaults.remove(name);
+            let dst = default_bin_dir.join(executable_name(name));
+            if dst.is_file() {
+                tx.remove_file(&dst)?;
+            }
+        }
+    }
+    for (name, default) in &target.defaults {
+        let src = default_source_path(name, default);
+        if !src.is_file() {
+            println!(
+                "Cannot restore the default {name}, {} is missing",
+                src.display()
+            );
+            defaults.remove(name);
+            continue;
+        }
+        let dst = default_bin_dir.join(executable_name(name));
+        if !dst.is_file() || sha256_file(&dst)? != sha256_file(&src)? {
+            tx.copy_file(&src, &dst)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&dst, std::fs::Permissions::from_mode(0o755))
+                    .with_context(|| {
+                        format!("Cannot set executable permissions on {}", dst.display())
+                    })?;
+            }
+        }
+        defaults.set(name, default.clone());
+    }
+    tx.write_json(&default_file_path()?, &defaults)?;
+
+    tx.commit();
+    Ok(())
+}
+
+/// The file of an installed binary, if it still exists
+fn existing_binary_path(binary: &BinaryVersion) -> Option<PathBuf> {
+    binary
+        .path
+        .as_ref()
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(std::iter::once(binary_file_path(
+            &binaries_dir(),
+            &binary.binary_name,
+            &binary.network_release,
+            &binary.version,
+            binary.debug,
+        )))
+        .find(|path| path.is_file())
+}
@@ -2,178 +2,292 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
 
 use super::check_if_binaries_exist;
 use super::version::extract_version_from_release;
-use crate::handlers::download::{download_latest_release, download_release_at_version};
-use crate::handlers::{extract_component, update_after_install};
-use crate::paths::binaries_dir;
+use crate::cache::archive_used;
+use crate::handlers::build::{GitRef, cargo_install, prepare_build};
+use crate::handlers::download::download_file;
+use crate::handlers::release::ensure_version_prefix;
+use crate::handlers::{confirm_set_default, extract_component, update_after_install};
+use crate::paths::{binaries_dir, installed_binaries_file, release_archive_dir};
 use crate::registry::{BinaryConfig, BinaryName};
 use crate::standalone;
-use crate::types::{BinaryVersion, InstalledBinaries};
+use crate::transaction::{Transaction, fail_point};
+use crate::types::{ArchiveSource, BinaryVersion, BuildOptions, InstalledBinaries};
 use anyhow::Context;
 use anyhow::Error;
 use anyhow::anyhow;
 use anyhow::bail;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::time::Duration;
 
+/// Records an installed binary and updates the default binary, replacing the entry of a previous
+/// install of the same version (e.g. a nightly build of the same branch with the same settings).
 pub fn install_binary(
-    name: &str,
-    network: String,
-    version: &str,
-    debug: bool,
-    binary_path: PathBuf,
-    yes: bool,
+    tx: &mut Transaction,
+    binary: BinaryVersion,
+    set_default: bool,
 ) -> Result<(), Error> {
     let mut installed_binaries = InstalledBinaries::new()?;
-    installed_binaries.add_binary(BinaryVersion {
-        binary_name: name.to_string(),
-        network_release: network.clone(),
-        version: version.to_string(),
-        debug,
-        path: Some(binary_path.to_string_lossy().to_string()),
-    });
-    installed_binaries.save_to_file()?;
-    update_after_install(&vec![name.to_string()], network, version, debug, yes)?;
-    Ok(())
+    installed_binaries.remove_version(&binary);
+    installed_binaries.add_binary(binary.clone());
+    tx.write_json(&installed_binaries_file()?, &installed_binaries)?;
+    fail_point("state")?;
+    update_after_install(
+        tx,
+        &vec![binary.binary_name],
+        binary.network_release,
+        &binary.version,
+        binary.debug,
+        set_default,
+    )?;
+    fail_point("default")
 }
 
-pub async fn install_from_release(
+/// Installs a binary from a release archive that is already present in the release archives
+/// folder, registering it like any other release install. Nothing is changed if any step fails.
+///
+/// `members` are additional binaries shipped in the same archive (see `archive_members` in the
+/// binary configs), which are installed alongside with their own entries.
+pub fn install_from_archive(
     name: &str,
     network: &str,
-    version_spec: Option<String>,
+    version: &str,
+    filename: &str,
     debug: bool,
     yes: bool,
-    config: &BinaryConfig,
-    github_token: Option<String>,
+    members: &[String],
 ) -> Result<(), Error> {
-    let repo_slug = &config.repository;
-    let filename = match version_spec {
-        Some(version) => {
-            download_release_at_version(repo_slug, config, network, &version, github_token.clone())
-                .await?
-        }
-        None => download_latest_release(repo_slug, config, network, github_token.clone()).await?,
-    };
-
-    let version = extract_version_from_release(&filename)?;
     let binary_name = if debug && name == "sui" {
         format!("{}-debug", name)
     } else {
         name.to_string()
     };
 
-    if !check_if_binaries_exist(&binary_name, network.to_string(), &version)? {
-        println!("Adding binary: {name}-{version}");
-        extract_component(&binary_name, network.to_string(), &filename)?;
+    let network_dir = binaries_dir().join(network);
+    std::fs::create_dir_all(&network_dir)
+        .with_context(|| format!("Cannot create binaries directory {}", network_dir.display()))?;
+    let staging_dir = tempfile::tempdir_in(&network_dir)
+        .context("Cannot create temporary directory for extraction")?;
+    let mut new_binaries = false;
+    for binary in std::iter::once(&binary_name).chain(members) {
+        new_binaries |= !check_if_binaries_exist(binary, network.to_string(), version)?;
+    }
+    let set_default = new_binaries && confirm_set_default(yes);
+    let mut tx = Transaction::new()?;
 
-        let binary_filename = format!("{}-{}", name, version);
-        #[cfg(target_os = "windows")]
-        let binary_filename = format!("{}.exe", binary_filename);
+    if !check_if_binaries_exist(&binary_name, network.to_string(), version)? {
+        println!("Adding binary: {name}-{version}");
+        extract_component(
+            &mut tx,
+            &binary_name,
+            network.to_string(),
+            version,
+            filename,
+            staging_dir.path(),
+        )?;
 
-        let binary_path = binaries_dir().join(network).join(binary_filename);
-        install_binary(name, network.to_string(), &version, debug, binary_path, yes)?;
+        let binary_path = archive_binary_path(name, network, version);
+        install_binary(
+            &mut tx,
+            BinaryVersion {
+                binary_name: name.to_string(),
+                network_release: network.to_string(),
+                version: version.to_string(),
+                debug,
+                path: Some(binary_path.to_string_lossy().to_string()),
+                build: BuildOptions::default(),
+            },
+            set_default,
+        )?;
     } else {
         println!(
             "Binary {name}-{version} already installed. Use `suiup default set` to change the default binary."
         );
     }
+
+    let mut installed_members = vec![];
+    for member in members {
+        if check_if_binaries_exist(member, network.to_string(), version)? {
+            println!("Binary {member}-{version} already installed.");
+            continue;
+        }
+        println!("Adding binary: {member}-{version}");
+        extract_component(
+            &mut tx,
+            member,
+            network.to_string(),
+            version,
+            filename,
+            staging_dir.path(),
+        )?;
+        installed_members.push(member.clone());
+    }
+    if !installed_members.is_empty() {
+        let mut installed_binaries = InstalledBinaries::new()?;
+        for member in &installed_members {
+            installed_binaries.add_binary(BinaryVersion {
+                binary_name: member.clone(),
+                network_release: network.to_string(),
+                version: version.to_string(),
+                debug: false,
+                path: Some(
+                    archive_binary_path(member, network, version)
+                        .to_string_lossy()
+                        .to_string(),
+                ),
+                build: BuildOptions::default(),
+            });
+        }
+        tx.write_json(&installed_binaries_file()?, &installed_binaries)?;
+        update_after_install(
+            &mut tx,
+            &installed_members,
+            network.to_string(),
+            version,
+            false,
+            set_default,
+        )?;
+    }
+    tx.commit();
     Ok(())
 }
 
-/// Compile the code from the main branch or the specified branch.
-/// It checks if cargo is installed.
-pub async fn install_from_nightly(
+/// Path of a binary extracted from a release archive
+fn archive_binary_path(name: &str, network: &str, version: &str) -> PathBuf {
+    let binary_filename = format!("{}-{}", name, version);
+    #[cfg(target_os = "windows")]
+    let binary_filename = format!("{}.exe", binary_filename);
+
+    binaries_dir().join(network).join(binary_filename)
+}
+
+/// Installs a binary from a local release archive or from an archive at an arbitrary URL.
+///
+/// The archive is placed in the release archives folder and then goes through the same
+/// extraction path as a GitHub release. Network and version are derived from the archive name,
+/// unless they are explicitly provided.
+pub async fn install_from_archive_source(
     name: &BinaryName,
-    branch: &str,
+    source: &ArchiveSource,
+    network: Option<&str>,
+    version: Option<&str>,
     debug: bool,
     yes: bool,
+    github_token: Option<String>,
 ) -> Result<(), Error> {
-    let config = name.config();
-    println!("Installing {name} from {branch} branch");
-    check_command_installed("rustc")?;
-    check_command_installed("cargo")?;
-
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.green} {msg}")
-            .unwrap()
-            .tick_strings(&["-", "\\", "|", "/"]),
-    );
-    pb.enable_steady_tick(Duration::from_millis(100));
-    pb.set_message("Compiling...please wait");
-
-    let repo_url = config.repo_url();
-    let binaries_folder = binaries_dir();
-    let binaries_folder_branch = binaries_folder.join(branch);
-
-    let mut args = vec![];
+    let filename = source.file_name()?;
+    let archive_path = release_archive_dir().join(&filename);
+    std::fs::create_dir_all(release_archive_dir()).with_context(|| {
+        format!(
+            "Cannot create release archive directory {}",
+            release_archive_dir().display()
+        )
+    })?;
 
-    if let Some(toolchain) = &config.nightly_toolchain {
-        args.push(format!("+{}", toolchain));
+    match source {
+        ArchiveSource::File(path) => {
+            if !path.is_file() {
+                bail!("Archive file {} does not exist", path.display());
+            }
+            if path.canonicalize().ok() != archive_path.canonicalize().ok() {
+                std::fs::copy(path, &archive_path).with_context(|| {
+                    format!(
+                        "Cannot copy archive {} to {}",
+                        path.display(),
+                        archive_path.display()
+                    )
+                })?;
+            }
+        }
+        ArchiveSource::Url(url) => {
+            download_file(url, &archive_path, &filename, github_token).await?;
+        }
     }
+    archive_used(&archive_path);
+
+    let version = match version {
+        Some(version) => ensure_version_prefix(version),
+        None => extract_version_from_release(&filename).map_err(|_| {
+            anyhow!(
+                "Cannot determine the version from archive name {filename}. Specify it explicitly, e.g. `{name}@testnet-1.44.2`"
+            )
+        })?,
+    };
 
-    let args_static: Vec<&str> = vec![
-        "install", "--locked", "--force", "--git", &repo_url, "--branch", branch,
-    ];
-
-    if let Some(cargo_package) = &config.cargo_package {
-        args.push(cargo_package.clone());
-        args.push("--bin".to_string());
-        args.push(name.as_str().to_string());
-    } else {
-        args.push(name.as_str().to_string());
+    let network = match network {
+        Some(network) => network.to_string(),
+        None => network_from_archive_name(&filename, name.config()).ok_or_else(|| {
+            anyhow!(
+                "Cannot determine the network from archive name {filename}. Specify it explicitly, e.g. `{name}@testnet-{version}`"
+            )
+        })?,
     };
 
-    args.push("--root".to_string());
-    args.push(binaries_folder_branch.to_str().unwrap().to_string());
+    install_from_archive(
+        name.as_str(),
+        &network,
+        &version,
+        &filename,
+        debug,
+        yes,
+        &[],
+    )
+}
 
-    let all_args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-    // Merge static args with dynamic args
-    let mut final_args: Vec<&str> = Vec::new();
-    // Add toolchain first if present
-    if config.nightly_toolchain.is_some() {
-        final_args.push(all_args[0]);
-    }
-    final_args.extend(args_static.iter());
-    // Add remaining dynamic args (skip toolchain if it was present)
-    let skip = if config.nightly_toolchain.is_some() {
-        1
+/// Finds which of the binary's supported networks the archive name refers to.
+fn network_from_archive_name(filename: &str, config: &BinaryConfig) -> Option<String> {
+    let networks: Vec<&str> = if config.supported_networks.is_empty() {
+        vec!["testnet", "devnet", "mainnet"]
     } else {
-        0
+        config
+            .supported_networks
+            .iter()
+            .map(String::as_str)
+            .collect()
     };
-    for arg in all_args.iter().skip(skip) {
-        final_args.push(arg);
-    }
-
-    let mut cmd = Command::new("cargo");
-    cmd.args(&final_args);
 
-    let cmd = cmd
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::piped())
-        .spawn()?;
+    networks
+        .into_iter()
+        .find(|network| filename.contains(&format!("-{network}-")))
+        .map(str::to_string)
+}
 
-    let output = cmd.wait_with_output()?;
-    pb.finish_with_message("Done!");
+/// Compile the code from the main branch or the specified branch with the given cargo settings.
+/// It checks if cargo is installed.
+pub async fn install_from_nightly(
+    name: &BinaryName,
+    branch: &str,
+    debug: bool,
+    build: &BuildOptions,
+    yes: bool,
+) -> Result<(), Error> {
+    let config = name.config();
+    println!("Installing {name} from {branch} branch");
+    let toolchain = prepare_build(config, GitRef::Branch(branch), yes).await?;
 
-    if !output.status.success() {
-        let error_message = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!("Error during installation:\n{}", error_message));
-    }
+    let binaries_folder = binaries_dir();
+    let binaries_folder_branch = binaries_folder.join(branch);
+    cargo_install(
+        config,
+        name.as_str(),
+        GitRef::Branch(branch),
+        &binaries_folder_branch,
+        debug,
+        build,
+        toolchain.as_deref(),
+    )?;
 
     println!("Installation completed successfully!");
     // bin folder is needed because cargo installs in  /folder/bin/binary_name.
     let orig_binary_path = binaries_folder_branch.join("bin").join(name.as_str());
 
-    // rename the binary to `binary_name-nightly`, to keep things in sync across the board
-
+    // rename the binary to `binary_name-nightly`, to keep things in sync across the board.
+    // Builds with custom cargo settings get a hash of the settings, e.g. `sui-nightly-1a2b3c4d`
+    let version = build.nightly_version();
     let dst_name = if debug {
-        format!("{}-debug-nightly", name)
+        format!("{}-debug-{version}", name)
     } else {
-        format!("{}-nightly", name)
+        format!("{}-{version}", name)
     };
     let dst = binaries_folder_branch.join("bin").join(dst_name);
 
@@ -183,22 +297,94 @@ pub async fn install_from_nightly(
     #[cfg(windows)]
     let dst = PathBuf::from(format!("{}.exe", dst.display()));
 
-    std::fs::rename(&orig_binary_path, &dst).with_context(|| {
-        format!(
-            "Cannot rename nightly binary from {} to {}",
-            orig_binary_path.display(),
-            dst.display()
-        )
-    })?;
+    let set_default = confirm_set_default(yes);
+    let mut tx = Transaction::new()?;
+    tx.install_file(&orig_binary_path, &dst)
+        .context("Cannot rename nightly binary")?;
     install_binary(
+        &mut tx,
+        BinaryVersion {
+            binary_name: name.to_string(),
+            network_release: branch.to_string(),
+            version,
+            debug,
+            path: Some(dst.to_string_lossy().to_string()),
+            build: build.clone(),
+        },
+        set_default,
+    )?;
+    tx.commit();
+
+    Ok(())
+}
+
+/// Builds a release of a network-based binary from its `{network}-{version}` tag, for platforms
+/// without a prebuilt archive, and registers it like a binary installed from the release archive.
+pub async fn install_from_source(
+    name: &BinaryName,
+    network: &str,
+    version: &str,
+    debug: bool,
+    yes: bool,
+) -> Result<(), Error> {
+    let config = name.config();
+    let version = ensure_version_prefix(version);
+    let binary_name = if debug && config.supports_debug {
+        format!("{}-debug", name)
+    } else {
+        name.to_string()
+    };
+    if check_if_binaries_exist(&binary_name, network.to_string(), &version)? {
+        println!(
+            "Binary {binary_name}-{version} already installed. Use `suiup default set` to change the default binary."
+        );
+        return Ok(());
+    }
+
+    let tag = format!("{network}-{version}");
+    println!("Building {name} from the {tag} tag");
+    let toolchain = prepare_build(config, GitRef::Tag(&tag), yes).await?;
+
+    let network_dir = binaries_dir().join(network);
+    std::fs::create_dir_all(&network_dir)
+        .with_context(|| format!("Cannot create binaries directory {}", network_dir.display()))?;
+    let build_root =
+        tempfile::tempdir_in(&network_dir).context("Cannot create temporary build directory")?;
+    cargo_install(
+        config,
         name.as_str(),
-        branch.to_string(),
-        "nightly",
+        GitRef::Tag(&tag),
+        build_root.path(),
         debug,
-        dst,
-        yes,
+        &BuildOptions::default(),
+        toolchain.as_deref(),
     )?;
 
+    // cargo installs in /root/bin/binary_name
+    let built = build_root.path().join("bin").join(name.as_str());
+    #[cfg(windows)]
+    let built = PathBuf::from(format!("{}.exe", built.display()));
+    let dst = archive_binary_path(&binary_name, network, &version);
+
+    let set_default = confirm_set_default(yes);
+    let mut tx = Transaction::new()?;
+    tx.install_file(&built, &dst)
+        .context("Cannot move the built binary")?;
+    install_binary(
+        &mut tx,
+        BinaryVersion {
+            binary_name: name.to_string(),
+            network_release: network.to_string(),
+            version,
+            debug,
+            path: Some(dst.to_string_lossy().to_string()),
+            build: BuildOptions::default(),
+        },
+        set_default,
+    )?;
+    tx.commit();
+    println!("Installation completed successfully!");
+
     Ok(())
 }
 
@@ -207,7 +393,9 @@ pub async fn install_standalone(
     config: &BinaryConfig,
     binary_name_override: Option<&str>,
     yes: bool,
+    (os, arch): &(String, String),
     github_token: Option<String>,
+    include_prereleases: bool,
 ) -> Result<(), Error> {
     let network = "standalone".to_string();
     let binary_name = match binary_name_override {
@@ -220,8 +408,15 @@ pub async fn install_standalone(
         network.clone(),
         &version.clone().unwrap_or_default(),
     )? {
-        let mut installer = standalone::StandaloneInstaller::new(&config.repository, github_token);
-        let installed_version = installer.download_version(version, &binary_name).await?;
+        let mut installer = standalone::StandaloneInstaller::for_binary(config, github_token)
+            .with_prereleases(include_prereleases);
+        let downloaded = installer
+            .download_version(version, &binary_name, os, arch)
+            .await?;
+        let set_default = confirm_set_default(yes);
+        let mut tx = Transaction::new()?;
+        downloaded.install(&mut tx)?;
+        let installed_version = downloaded.version.clone();
 
         println!("Adding binary: {binary_name}-{installed_version}");
 
@@ -235,13 +430,18 @@ pub async fn install_standalone(
             .join(format!("{}-{}.exe", binary_name, installed_version));
 
         install_binary(
-            &binary_name,
-            network.clone(),
-            &installed_version,
-            false,
-            binary_path,
-            yes,
+            &mut tx,
+            BinaryVersion {
+                binary_name: binary_name.clone(),
+                network_release: network.clone(),
+                version: installed_version,
+                debug: false,
+                path: Some(binary_path.to_string_lossy().to_string()),
+                build: BuildOptions::default(),
+            },
+            set_default,
         )?;
+        tx.commit();
     } else {
         let version = version.unwrap_or_default();
         println!(
@@ -252,19 +452,45 @@ pub async fn install_standalone(
     Ok(())
 }
 
-fn check_command_installed(command: &str) -> Result<(), Error> {
-    if let Ok(output) = Command::new(command).arg("--version").output() {
-        if output.status.success() {
-            print!(
-                "{} is installed: {}",
-                command,
-                String::from_utf8_lossy(&output.stdout)
-            );
-        } else {
-            bail!("{} is not installed", command);
-        }
-    } else {
-        bail!("Failed to execute {} command", command);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::BinaryRegistry;
+
+    #[test]
+    fn network_from_archive_name_matches_supported_networks() {
+        let config = BinaryRegistry::global().get("sui").unwrap();
+        assert_eq!(
+            network_from_archive_name("sui-mainnet-v1.44.2-ubuntu-x86_64.tgz", config),
+            Some("mainnet".to_string())
+        );
+        assert_eq!(
+            network_from_archive_name("sui-v1.44.2-ubuntu-x86_64.tgz", config),
+            None
+        );
+    }
+
+    #[test]
+    fn network_from_archive_name_ignores_unsupported_networks() {
+        let config = BinaryRegistry::global().get("site-builder").unwrap();
+        assert_eq!(
+            network_from_archive_name("site-builder-testnet-v1.0.0-ubuntu-x86_64.tgz", config),
+            None
+        );
+    }
+
+    #[test]
+    fn archive_source_file_name_strips_url_query() {
+        let source = ArchiveSource::Url(
+            "https://example.com/releases/sui-testnet-v1.44.2-ubuntu-x86_64.tgz?raw=true"
+                .to_string(),
+        );
+        assert_eq!(
+            source.file_name().unwrap(),
+            "sui-testnet-v1.44.2-ubuntu-x86_64.tgz"
+        );
+
+        let source = ArchiveSource::Url("https://example.com/releases/".to_string());
+        assert!(source.file_name().is_err());
     }
-    Ok(())
 }
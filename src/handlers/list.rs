@@ -0,0 +1,268 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{Error, bail};
+use comfy_table::Table;
+use indicatif::HumanBytes;
+
+use crate::commands::TABLE_FORMAT;
+use crate::handlers::download::{find_release_asset, host_os_arch};
+use crate::registry::{BinaryConfig, BinaryName, InstallationType};
+use crate::standalone::{StandaloneInstaller, find_standalone_asset, standalone_tag_version};
+use crate::types::{Asset, BinaryVersion, DefaultBinaries, InstalledBinaries, Release};
+
+/// A version of a binary in its release list
+struct RemoteVersion<'a> {
+    release: &'a Release,
+    network: String,
+    version: String,
+    /// The asset for the platform the list is made for
+    asset: Option<&'a Asset>,
+}
+
+/// Handles the `list --remote` command: shows the versions of a binary in its release list,
+/// newest first, with the ones that are installed or the default marked
+pub async fn handle_list_remote(
+    name: &BinaryName,
+    network: Option<&str>,
+    limit: usize,
+    github_token: Option<String>,
+) -> Result<(), Error> {
+    let config = name.config();
+    let (os, arch) = host_os_arch()?;
+    let releases = if config.installation_type == InstallationType::Standalone {
+        let mut installer =
+            StandaloneInstaller::for_binary(config, github_token).with_prereleases(true);
+        installer.get_releases().await?;
+        installer.releases().to_vec()
+    } else {
+        config
+            .release_source(github_token)
+            .releases()
+            .await?
+            .into_iter()
+            .filter(|release| !release.draft)
+            .collect()
+    };
+
+    let versions = remote_versions(config, &releases, network, (os, arch))?;
+    if versions.is_empty() {
+        match network {
+            Some(network) => println!("No {network} releases of {name} found"),
+            None => println!("No releases of {name} found"),
+        }
+        return Ok(());
+    }
+
+    let installed = InstalledBinaries::read_from_file()?;
+    let defaults = DefaultBinaries::read_from_file()?;
+    let default = defaults.binaries().get(&config.name);
+    let mut table = Table::new();
+    table.load_preset(TABLE_FORMAT).set_header(vec![
+        "Version".to_string(),
+        "Network".to_string(),
+        "Published".to_string(),
+        "Size".to_string(),
+        format!("{os}-{arch}"),
+        "Status".to_string(),
+    ]);
+    for remote in versions.iter().take(limit) {
+        let is_default = default.is_some_and(|default| {
+            default.network_release == remote.network && default.version == remote.version
+        });
+        let mut status = vec![];
+        if is_default {
+            status.push("default");
+        } else if is_installed(installed.binaries(), &config.name, remote) {
+            status.push("installed");
+        }
+        if remote.release.prerelease {
+            status.push("pre-release");
+        }
+        table.add_row(vec![
+            remote.version.clone(),
+            remote.network.clone(),
+            remote.release.published_date().unwrap_or("-").to_string(),
+            remote
+                .asset
+                .and_then(|asset| asset.size)
+                .map_or("-".to_string(), |size| HumanBytes(size).to_string()),
+            if remote.asset.is_some() { "Yes" } else { "No" }.to_string(),
+            status.join(", "),
+        ]);
+    }
+    println!("{table}");
+    if versions.len() > limit {
+        println!(
+            "Showing {limit} of {} versions, use --limit to show more",
+            versions.len()
+        );
+    }
+    Ok(())
+}
+
+fn is_installed(installed: &[BinaryVersion], name: &str, remote: &RemoteVersion) -> bool {
+    installed.iter().any(|binary| {
+        binary.binary_name == name
+            && binary.network_release == remote.network
+            && binary.version == remote.version
+    })
+}
+
+/// The versions in the releases of a binary, newest first. Releases of network based binaries
+/// belong to the network in their asset names, and the others to the default network.
+fn remote_versions<'a>(
+    config: &BinaryConfig,
+    releases: &'a [Release],
+    network: Option<&str>,
+    (os, arch): (&str, &str),
+) -> Result<Vec<RemoteVersion<'a>>, Error> {
+    if !config.network_based {
+        if let Some(network) = network {
+            bail!(
+                "{} releases do not depend on the network, remove --network {network}",
+                config.name
+            );
+        }
+        return Ok(releases
+            .iter()
+            .filter_map(|release| {
+                let version =
+                    standalone_tag_version(&release.tag_name).or_else(|| release.version())?;
+                let asset = match config.installation_type {
+                    InstallationType::Standalone => {
+                        find_standalone_asset(release, &config.name, &version, os, arch)
+                    }
+                    InstallationType::Archive => find_release_asset(
+                        release,
+                        config,
+                        &config.default_network,
+                        Some(&version),
+                        os,
+                        arch,
+                    ),
+                };
+                Some(RemoteVersion {
+                    release,
+                    network: config.default_network.clone(),
+                    version,
+                    asset,
+                })
+            })
+            .collect());
+    }
+
+    let networks: Vec<&str> = if config.supported_networks.is_empty() {
+        vec!["testnet", "devnet", "mainnet"]
+    } else {
+        config
+            .supported_networks
+            .iter()
+            .map(String::as_str)
+            .collect()
+    };
+    if let Some(network) = network
+        && !networks.contains(&network)
+    {
+        bail!(
+            "{} is not released for {network}. Available networks: {}",
+            config.name,
+            networks.join(", ")
+        );
+    }
+    Ok(releases
+        .iter()
+        .filter_map(|release| {
+            let release_network = networks
+                .iter()
+                .copied()
+                .find(|n| release.assets.iter().any(|a| a.name.contains(n)))?;
+            if network.is_some_and(|network| network != release_network) {
+                return None;
+            }
+            let version = release.version()?;
+            let asset =
+                find_release_asset(release, config, release_network, Some(&version), os, arch);
+            Some(RemoteVersion {
+                release,
+                network: release_network.to_string(),
+                version,
+                asset,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::BinaryRegistry;
+
+    fn release(tag: &str, assets: &[&str]) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            assets: assets
+                .iter()
+                .map(|name| Asset {
+                    name: name.to_string(),
+                    size: Some(1024),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn lists_versions_per_network() {
+        let config = BinaryRegistry::global().get("sui").unwrap();
+        let releases = vec![
+            release("devnet-v1.45.0", &["sui-devnet-v1.45.0-macos-arm64.tgz"]),
+            release(
+                "testnet-v1.44.2",
+                &[
+                    "sui-testnet-v1.44.2-ubuntu-x86_64.tgz",
+                    "sui-testnet-v1.44.2-macos-arm64.tgz",
+                ],
+            ),
+        ];
+        let versions = remote_versions(config, &releases, None, ("ubuntu", "x86_64")).unwrap();
+        let listed: Vec<(&str, &str, bool)> = versions
+            .iter()
+            .map(|v| (v.network.as_str(), v.version.as_str(), v.asset.is_some()))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![("devnet", "v1.45.0", false), ("testnet", "v1.44.2", true)]
+        );
+
+        let versions =
+            remote_versions(config, &releases, Some("testnet"), ("ubuntu", "x86_64")).unwrap();
+        assert_eq!(versions.len(), 1);
+        assert!(
+            remote_versions(config, &releases, Some("localnet"), ("ubuntu", "x86_64")).is_err()
+        );
+    }
+
+    #[test]
+    fn lists_standalone_versions() {
+        let config = BinaryRegistry::global().get("mvr").unwrap();
+        let releases = vec![
+            release("v0.0.10", &["mvr-ubuntu-x86_64"]),
+            release("v0.0.9", &["mvr-macos-arm64"]),
+        ];
+        let versions = remote_versions(config, &releases, None, ("ubuntu", "x86_64")).unwrap();
+        let listed: Vec<(&str, &str, bool)> = versions
+            .iter()
+            .map(|v| (v.network.as_str(), v.version.as_str(), v.asset.is_some()))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("standalone", "v0.0.10", true),
+                ("standalone", "v0.0.9", false)
+            ]
+        );
+        assert!(remote_versions(config, &releases, Some("testnet"), ("ubuntu", "x86_64")).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::Path;
+
+use anyhow::{Context, Error, bail};
+use tokio::net::TcpListener;
+
+use crate::handlers::download::{find_release_asset, parse_platform};
+use crate::mirror::{read_manifest, repository_dir, serve, write_manifest};
+use crate::registry::{BinaryConfig, BinaryName};
+use crate::release_source::download_asset;
+use crate::standalone::{find_standalone_asset, standalone_tag_version};
+use crate::types::{Asset, Release};
+
+/// Handles the `mirror sync` command: downloads the `releases` most recent releases of each
+/// binary, for each network and platform, into the mirror in `dir`. Pre-releases are only
+/// mirrored with `include_prereleases`.
+pub async fn handle_mirror_sync(
+    dir: &Path,
+    binaries: &[BinaryName],
+    networks: &[String],
+    platforms: &[String],
+    releases: usize,
+    include_prereleases: bool,
+    github_token: Option<String>,
+) -> Result<(), Error> {
+    let platforms = platforms
+        .iter()
+        .map(|platform| parse_platform(platform))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut failed = 0;
+    for binary in binaries {
+        if let Err(e) = sync_binary(
+            dir,
+            binary.config(),
+            networks,
+            &platforms,
+            releases,
+            include_prereleases,
+            github_token.clone(),
+        )
+        .await
+        {
+            eprintln!("Warning: cannot mirror {binary}: {e:#}");
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        bail!(
+            "{failed} of {} binaries could not be mirrored",
+            binaries.len()
+        );
+    }
+    Ok(())
+}
+
+async fn sync_binary(
+    dir: &Path,
+    config: &BinaryConfig,
+    networks: &[String],
+    platforms: &[(String, String)],
+    releases: usize,
+    include_prereleases: bool,
+    github_token: Option<String>,
+) -> Result<(), Error> {
+    let source = config.release_source(github_token);
+    println!("Mirroring {} from {}", config.name, source.describe());
+    let all_releases: Vec<Release> = source
+        .releases()
+        .await?
+        .into_iter()
+        .filter(|release| release.is_installable(include_prereleases))
+        .collect();
+
+    let mut selected: Vec<(Option<&str>, &Release)> = vec![];
+    if config.network_based {
+        for network in networks {
+            if !config.supported_networks.is_empty() && !config.supported_networks.contains(network)
+            {
+                continue;
+            }
+            selected.extend(
+                all_releases
+                    .iter()
+                    .filter(|r| r.assets.iter().any(|a| a.name.contains(network.as_str())))
+                    .take(releases)
+                    .map(|release| (Some(network.as_str()), release)),
+            );
+        }
+    } else {
+        selected.extend(all_releases.iter().take(releases).map(|r| (None, r)));
+    }
+    if selected.is_empty() {
+        bail!("No releases found for {}", networks.join(", "));
+    }
+
+    let repository_dir = repository_dir(dir, &config.repository);
+    let mut manifest = read_manifest(&repository_dir)?;
+    let (mut downloaded, mut existing) = (0, 0);
+    for (network, release) in selected {
+        let mut mirrored = vec![];
+        for (os, arch) in platforms {
+            let Some(asset) = find_asset(config, network, release, os, arch) else {
+                println!(
+                    "No {} asset for {os}-{arch} in {}",
+                    config.name, release.tag_name
+                );
+                continue;
+            };
+            let release_dir = repository_dir.join(&release.tag_name);
+            let destination = release_dir.join(&asset.name);
+            if destination.exists() {
+                existing += 1;
+            } else {
+                std::fs::create_dir_all(&release_dir).with_context(|| {
+                    format!("Cannot create directory {}", release_dir.display())
+                })?;
+                // Downloads go through a temporary file, so an interrupted sync leaves no
+                // partial assets in the mirror
+                let partial = release_dir.join(format!("{}.part", asset.name));
+                if let Err(e) = download_asset(source.as_ref(), asset, &partial, &asset.name).await
+                {
+                    let _ = std::fs::remove_file(&partial);
+                    return Err(e);
+                }
+                std::fs::rename(&partial, &destination).with_context(|| {
+                    format!("Cannot move {} into the mirror", destination.display())
+                })?;
+                downloaded += 1;
+            }
+            mirrored.push(asset);
+        }
+        if !mirrored.is_empty() {
+            manifest.add_assets(release, mirrored);
+        }
+    }
+    write_manifest(&repository_dir, &manifest)?;
+    println!(
+        "Mirrored {} to {}: {downloaded} downloaded, {existing} already mirrored",
+        config.name,
+        repository_dir.display()
+    );
+    Ok(())
+}
+
+/// The asset of a release a client on the platform would download
+fn find_asset<'a>(
+    config: &BinaryConfig,
+    network: Option<&str>,
+    release: &'a Release,
+    os: &str,
+    arch: &str,
+) -> Option<&'a Asset> {
+    match network {
+        Some(network) => find_release_asset(release, config, network, None, os, arch),
+        None => {
+            let version = standalone_tag_version(&release.tag_name)
+                .unwrap_or_else(|| release.tag_name.clone());
+            find_standalone_asset(release, &config.name, &version, os, arch)
+        }
+    }
+}
+
+/// Handles the `mirror serve` command
+pub async fn handle_mirror_serve(dir: &Path, address: &str, port: u16) -> Result<(), Error> {
+    if !dir.is_dir() {
+        bail!(
+            "Mirror {} does not exist, run `suiup mirror sync` first",
+            dir.display()
+        );
+    }
+    let listener = TcpListener::bind((address, port))
+        .await
+        .with_context(|| format!("Cannot listen on {address}:{port}"))?;
+    let local = listener.local_addr()?;
+    println!("Serving {} on http://{local}", dir.display());
+    println!(
+        "Point clients at it with SUIUP_GITHUB_API_URL=http://<host>:{}",
+        local.port()
+    );
+    serve(dir, listener).await
+}
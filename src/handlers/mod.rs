@@ -1,29 +1,37 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::archive::ReleaseArchive;
+use crate::handlers::version::is_nightly;
+use crate::paths::default_file_path;
 use crate::paths::{binaries_dir, get_default_bin_dir, release_archive_dir};
-use crate::{paths::default_file_path, types::Version};
+use crate::transaction::{Transaction, fail_point};
 use anyhow::Context;
 use anyhow::Error;
 use anyhow::anyhow;
-use flate2::read::GzDecoder;
+use anyhow::bail;
 use std::env;
 use std::io::Write;
-use std::{fs::File, io::BufReader};
+use std::path::Path;
 
-use crate::types::{BinaryVersion, InstalledBinaries};
+use crate::types::{BinaryVersion, DefaultBinaries, DefaultBinary, InstalledBinaries};
 use std::collections::BTreeMap;
 #[cfg(not(windows))]
-use std::fs::set_permissions;
-#[cfg(not(windows))]
 use std::os::unix::fs::PermissionsExt;
-use tar::Archive;
-use version::extract_version_from_release;
 
+pub mod build;
+pub mod bundle;
+pub mod changelog;
 pub mod cleanup;
 pub mod download;
+pub mod fetch;
+pub mod history;
 pub mod install;
+pub mod list;
+pub mod mirror;
+pub mod registry;
 pub mod release;
+pub mod repair;
 pub mod self_;
 pub mod show;
 pub mod status;
@@ -41,55 +49,35 @@ pub fn available_components() -> Vec<&'static str> {
 
 /// Updates the default version file with the new installed version.
 pub fn update_default_version_file(
+    tx: &mut Transaction,
     binaries: &Vec<String>,
     network: String,
     version: &str,
     debug: bool,
 ) -> Result<(), Error> {
-    let path = default_file_path()?;
-    let file = File::open(&path)
-        .with_context(|| format!("Cannot open default version file {}", path.display()))?;
-    let reader = BufReader::new(file);
-    let mut map: BTreeMap<String, (String, Version, bool)> = serde_json::from_reader(reader)
-        .map_err(|e| {
-            anyhow!(
-                "Cannot deserialize default version file {}: {e}",
-                path.display()
-            )
-        })?;
-
+    let mut defaults = DefaultBinaries::read_from_file()?;
     for binary in binaries {
-        let b = map.get_mut(binary);
-        if let Some(b) = b {
-            b.0 = network.clone();
-            b.1 = version.to_string();
-            b.2 = debug;
-        } else {
-            map.insert(
-                binary.to_string(),
-                (network.clone(), version.to_string(), debug),
-            );
-        }
+        defaults.set(
+            binary,
+            DefaultBinary {
+                network_release: network.clone(),
+                version: version.to_string(),
+                debug,
+            },
+        );
     }
-
-    let mut file = File::create(&path)
-        .with_context(|| format!("Cannot create default version file {}", path.display()))?;
-    let payload =
-        serde_json::to_string_pretty(&map).context("Cannot serialize default version map")?;
-    file.write_all(payload.as_bytes())
-        .with_context(|| format!("Cannot write default version file {}", path.display()))?;
-
-    Ok(())
+    tx.write_json(&default_file_path()?, &defaults)
 }
 
-/// Prompts the user and asks if they want to update the default version with the one that was just
-/// installed.
+/// Makes the version that was just installed the default one when `set_default` is set, see
+/// [`confirm_set_default`].
 pub fn update_after_install(
+    tx: &mut Transaction,
     name: &Vec<String>,
     network: String,
     version: &str,
     debug: bool,
-    yes: bool,
+    set_default: bool,
 ) -> Result<(), Error> {
     // First check if the binary exists
     for binary in name {
@@ -99,7 +87,7 @@ pub fn update_after_install(
             binary.clone()
         };
 
-        let binary_path = if version == "nightly" {
+        let binary_path = if is_nightly(version) {
             // cargo install places the binary in a `bin` folder
             binaries_dir()
                 .join(&network)
@@ -130,116 +118,104 @@ pub fn update_after_install(
         }
     }
 
-    let input = if yes {
-        "y".to_string()
-    } else {
-        let prompt = "Do you want to set this new installed version as the default one? [y/N] ";
+    if !set_default {
+        println!("Keeping the current default version.");
+        return Ok(());
+    }
+    for binary in name {
+        let mut filename = if debug {
+            format!("{}-debug-{}", binary, version)
+        } else {
+            format!("{}-{}", binary, version)
+        };
 
-        print!("{prompt}");
-        std::io::stdout().flush().unwrap();
+        if version.is_empty() {
+            filename = filename.strip_suffix('-').unwrap_or_default().to_string();
+        }
 
-        // Create a mutable String to store the input
-        let mut input = String::new();
+        let binary_folder = if is_nightly(version) {
+            binaries_dir().join(&network).join("bin")
+        } else {
+            binaries_dir().join(&network)
+        };
 
-        // Read the input from the console
-        std::io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read line");
+        if !binary_folder.exists() {
+            std::fs::create_dir_all(&binary_folder)
+                .map_err(|e| anyhow!("Cannot create folder {}: {e}", binary_folder.display()))?;
+        }
 
-        // Trim the input and convert to lowercase for comparison
-        input.trim().to_lowercase()
-    };
+        #[cfg(windows)]
+        let filename = format!("{}.exe", filename);
 
-    // Check the user's response
-    match input.as_str() {
-        "y" | "yes" => {
-            for binary in name {
-                let mut filename = if debug {
-                    format!("{}-debug-{}", binary, version)
-                } else {
-                    format!("{}-{}", binary, version)
-                };
-
-                if version.is_empty() {
-                    filename = filename.strip_suffix('-').unwrap_or_default().to_string();
-                }
-
-                let binary_folder = if version == "nightly" {
-                    binaries_dir().join(&network).join("bin")
-                } else {
-                    binaries_dir().join(&network)
-                };
-
-                if !binary_folder.exists() {
-                    std::fs::create_dir_all(&binary_folder).map_err(|e| {
-                        anyhow!("Cannot create folder {}: {e}", binary_folder.display())
-                    })?;
-                }
-
-                #[cfg(windows)]
-                let filename = format!("{}.exe", filename);
+        println!(
+            "Installing binary to {}/{}",
+            binary_folder.display(),
+            filename
+        );
 
-                println!(
-                    "Installing binary to {}/{}",
-                    binary_folder.display(),
-                    filename
-                );
+        let src = binary_folder.join(&filename);
+        let dst = get_default_bin_dir().join(binary);
 
-                let src = binary_folder.join(&filename);
-                let dst = get_default_bin_dir().join(binary);
-
-                println!("Setting {} as default", binary);
-
-                #[cfg(windows)]
-                let mut dst = dst.clone();
-                #[cfg(windows)]
-                {
-                    if dst.extension() != Some("exe".as_ref()) {
-                        let new_dst = format!("{}.exe", dst.display());
-                        dst.set_file_name(new_dst);
-                    }
-                }
-
-                tracing::debug!("Copying from {} to {}", src.display(), dst.display());
-
-                std::fs::copy(&src, &dst).map_err(|e| {
-                    anyhow!(
-                        "Error copying {binary} to the default folder (src: {}, dst: {}): {e}",
-                        src.display(),
-                        dst.display()
-                    )
-                })?;
-
-                #[cfg(unix)]
-                {
-                    let mut perms = std::fs::metadata(&dst)
-                        .with_context(|| {
-                            format!("Cannot read metadata for default binary {}", dst.display())
-                        })?
-                        .permissions();
-                    perms.set_mode(0o755);
-                    std::fs::set_permissions(&dst, perms).with_context(|| {
-                        format!("Cannot set executable permissions on {}", dst.display())
-                    })?;
-                }
-
-                println!("[{network}] {binary}-{version} set as default");
+        println!("Setting {} as default", binary);
+
+        #[cfg(windows)]
+        let mut dst = dst.clone();
+        #[cfg(windows)]
+        {
+            if dst.extension() != Some("exe".as_ref()) {
+                let new_dst = format!("{}.exe", dst.display());
+                dst.set_file_name(new_dst);
             }
-            update_default_version_file(name, network, version, debug)?;
-            check_path_and_warn()?;
         }
 
-        "" | "n" | "no" => {
-            println!("Keeping the current default version.");
-        }
-        _ => {
-            println!("Invalid input. Please enter 'y' or 'n'.");
-            update_after_install(name, network, version, debug, yes)?;
+        tracing::debug!("Copying from {} to {}", src.display(), dst.display());
+
+        tx.copy_file(&src, &dst)
+            .with_context(|| format!("Error copying {binary} to the default folder"))?;
+
+        #[cfg(unix)]
+        {
+            let mut perms = std::fs::metadata(&dst)
+                .with_context(|| {
+                    format!("Cannot read metadata for default binary {}", dst.display())
+                })?
+                .permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&dst, perms).with_context(|| {
+                format!("Cannot set executable permissions on {}", dst.display())
+            })?;
         }
+
+        println!("[{network}] {binary}-{version} set as default");
     }
+    update_default_version_file(tx, name, network, version, debug)?;
+    check_path_and_warn()?;
     Ok(())
 }
 
+/// Asks whether a newly installed version should become the default one, unless `yes` is set.
+/// Installs ask before taking the state lock, so that other suiup processes do not wait on the
+/// answer.
+pub fn confirm_set_default(yes: bool) -> bool {
+    if yes {
+        return true;
+    }
+    loop {
+        print!("Do you want to set this new installed version as the default one? [y/N] ");
+        std::io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        std::io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read line");
+        match input.trim().to_lowercase().as_str() {
+            "y" | "yes" => return true,
+            "" | "n" | "no" => return false,
+            _ => println!("Invalid input. Please enter 'y' or 'n'."),
+        }
+    }
+}
+
 fn check_path_and_warn() -> Result<(), Error> {
     let local_bin = get_default_bin_dir();
 
@@ -290,91 +266,71 @@ fn check_path_and_warn() -> Result<(), Error> {
 /// Extracts a component from the release archive. The component's name is identified by the
 /// `binary` parameter.
 ///
-/// This extracts the component to the binaries folder under the network from which release comes
-/// from, and sets the correct permissions for Unix based systems.
-fn extract_component(orig_binary: &str, network: String, filename: &str) -> Result<(), Error> {
+/// The component is extracted to `staging_dir` and checked, then moved to the binaries folder
+/// under the network from which release comes from as part of `tx`. The file permissions are
+/// kept for Unix based systems.
+fn extract_component(
+    tx: &mut Transaction,
+    orig_binary: &str,
+    network: String,
+    version: &str,
+    filename: &str,
+    staging_dir: &Path,
+) -> Result<(), Error> {
     let mut archive_path = release_archive_dir();
     archive_path.push(filename);
 
-    let file = File::open(archive_path.as_path())
-        .with_context(|| format!("Cannot open archive file {}", archive_path.display()))?;
-    let tar = GzDecoder::new(file);
-    let mut archive = Archive::new(tar);
-
     #[cfg(not(windows))]
     let binary = orig_binary.to_string();
     #[cfg(windows)]
     let binary = format!("{}.exe", orig_binary);
 
-    // Check if the current entry matches the file name
-    for file in archive
-        .entries()
-        .map_err(|e| anyhow!("Cannot iterate through archive entries: {e}"))?
-    {
-        let mut f = file.map_err(|e| {
-            anyhow!(
-                "Cannot read entry from archive {}: {e}",
-                archive_path.display()
-            )
-        })?;
-        let entry_path = f.path().map_err(|e| {
-            anyhow!(
-                "Cannot read entry path from archive {}: {e}",
-                archive_path.display()
-            )
+    let mut output_path = binaries_dir();
+    output_path.push(&network);
+    if !output_path.is_dir() {
+        std::fs::create_dir_all(output_path.as_path()).with_context(|| {
+            format!("Cannot create binaries directory {}", output_path.display())
         })?;
-        if entry_path.file_name().and_then(|x| x.to_str()) == Some(&binary) {
-            println!("Extracting file: {}", &binary);
-
-            let mut output_path = binaries_dir();
-            output_path.push(&network);
-            if !output_path.is_dir() {
-                std::fs::create_dir_all(output_path.as_path()).with_context(|| {
-                    format!("Cannot create binaries directory {}", output_path.display())
-                })?;
-            }
-            let version = extract_version_from_release(filename)?;
-            let binary_version = format!("{}-{}", orig_binary, version);
-            #[cfg(not(windows))]
-            output_path.push(&binary_version);
-            #[cfg(windows)]
-            output_path.push(&format!("{}.exe", binary_version));
-
-            let mut output_file = File::create(&output_path).map_err(|e| {
-                anyhow!(
-                    "Cannot create output path ({}) for extracting this file {binary_version}: {e}",
-                    output_path.display()
-                )
-            })?;
-
-            std::io::copy(&mut f, &mut output_file).map_err(|e| {
-                anyhow!(
-                    "Cannot copy file {} into output path {}: {e}",
-                    orig_binary,
-                    output_path.display()
-                )
-            })?;
-            println!(" '{}' extracted successfully!", &binary);
-            #[cfg(not(target_os = "windows"))]
-            {
-                // Retrieve and apply the original file permissions on Unix-like systems
-                if let Ok(permissions) = f.header().mode() {
-                    set_permissions(output_path.clone(), PermissionsExt::from_mode(permissions))
-                        .with_context(|| {
-                            format!(
-                                "Cannot apply original file permissions to {}",
-                                output_path.display()
-                            )
-                        })?;
-                }
-            }
-            break;
-        }
     }
+    let binary_version = format!("{}-{}", orig_binary, version);
+    #[cfg(not(windows))]
+    output_path.push(&binary_version);
+    #[cfg(windows)]
+    output_path.push(&format!("{}.exe", binary_version));
+
+    let staged_path = staging_dir.join(&binary);
+    extract_binary(&archive_path, &binary, &staged_path)?;
+    fail_point("extract")?;
+    verify_extracted_binary(&staged_path)?;
+    tx.install_file(&staged_path, &output_path)?;
+    fail_point("install")
+}
 
+/// Checks that an extracted binary is a non-empty regular file
+fn verify_extracted_binary(path: &Path) -> Result<(), Error> {
+    let metadata = std::fs::metadata(path)
+        .with_context(|| format!("Cannot read metadata for {}", path.display()))?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        bail!("Extracted binary {} is empty or invalid", path.display());
+    }
     Ok(())
 }
 
+/// Extracts the archive entry with the given file name to `output_path`, keeping the original
+/// file permissions on Unix based systems.
+fn extract_binary(archive_path: &Path, binary: &str, output_path: &Path) -> Result<(), Error> {
+    println!("Extracting file: {}", binary);
+    if ReleaseArchive::open(archive_path)?.extract_file(binary, output_path)? {
+        println!(" '{}' extracted successfully!", binary);
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Cannot find {binary} in archive {}",
+            archive_path.display()
+        ))
+    }
+}
+
 /// Checks if the binaries exist in the binaries folder
 pub fn check_if_binaries_exist(
     binary: &str,
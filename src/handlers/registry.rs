@@ -0,0 +1,144 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{Error, bail};
+use comfy_table::Table;
+
+use crate::commands::TABLE_FORMAT;
+use crate::paths::user_binaries_dir;
+use crate::registry::BinaryRegistry;
+use crate::remote_registry::{
+    FetchedIndex, Subscription, decode_public_key, definition_name, fetch_index,
+    read_subscriptions, remove_cached_index, save_index, save_subscriptions, verified_definitions,
+};
+
+/// Handles the `registry list` command
+pub fn handle_registry_list() -> Result<(), Error> {
+    let mut table = Table::new();
+    table
+        .load_preset(TABLE_FORMAT)
+        .set_header(vec!["Binary", "Description", "Source"])
+        .add_rows(BinaryRegistry::global().all().iter().map(|config| {
+            vec![
+                config.name.clone(),
+                config.description.clone(),
+                config.source.to_string(),
+            ]
+        }));
+    println!("{table}");
+    println!(
+        "Add binaries by placing their definitions in {}",
+        user_binaries_dir().display()
+    );
+    let subscriptions = read_subscriptions()?;
+    if !subscriptions.is_empty() {
+        println!("Subscribed registries:");
+        for subscription in subscriptions {
+            println!("  {}", subscription.url);
+        }
+    }
+    Ok(())
+}
+
+/// Handles the `registry add` command: fetches the index, checks that its entries are signed
+/// with `public_key` and subscribes to it
+pub async fn handle_registry_add(url: &str, public_key: &str) -> Result<(), Error> {
+    let url = url.trim();
+    let key = decode_public_key(public_key)?;
+    let mut subscriptions = read_subscriptions()?;
+    if subscriptions.iter().any(|s| s.url == url) {
+        bail!("Already subscribed to {url}, run `suiup registry update` to refresh it");
+    }
+    // Load the current definitions before the new registry is saved, to report conflicts
+    let registry = BinaryRegistry::global();
+
+    let FetchedIndex::Updated { index, body, etag } = fetch_index(url, false).await? else {
+        bail!("Unexpected response from {url}");
+    };
+    let (definitions, warnings) = verified_definitions(url, &key, &index);
+    for warning in &warnings {
+        eprintln!("Warning: {warning}");
+    }
+    if definitions.is_empty() && !index.binaries.is_empty() {
+        bail!("No entry of the registry at {url} is signed with the given public key");
+    }
+
+    save_index(url, &body, etag.as_deref())?;
+    subscriptions.push(Subscription {
+        url: url.to_string(),
+        public_key: public_key.trim().to_string(),
+    });
+    save_subscriptions(&subscriptions)?;
+
+    println!(
+        "Subscribed to {url} with {} binary definitions",
+        definitions.len()
+    );
+    for name in definitions.iter().filter_map(|d| definition_name(d)) {
+        match registry.get(&name) {
+            Some(existing) => {
+                println!("  {name} (ignored, already defined by {})", existing.source)
+            }
+            None => println!("  {name}"),
+        }
+    }
+    Ok(())
+}
+
+/// Handles the `registry update` command
+pub async fn handle_registry_update() -> Result<(), Error> {
+    let subscriptions = read_subscriptions()?;
+    if subscriptions.is_empty() {
+        println!("No remote registries, subscribe to one with `suiup registry add`");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for subscription in &subscriptions {
+        if let Err(e) = update_registry(subscription).await {
+            eprintln!(
+                "Warning: cannot update {}: {e:#}, keeping the cached index",
+                subscription.url
+            );
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        bail!(
+            "{failed} of {} registries could not be updated",
+            subscriptions.len()
+        );
+    }
+    Ok(())
+}
+
+async fn update_registry(subscription: &Subscription) -> Result<(), Error> {
+    let url = &subscription.url;
+    let key = decode_public_key(&subscription.public_key)?;
+    match fetch_index(url, true).await? {
+        FetchedIndex::NotModified => println!("{url} is up to date"),
+        FetchedIndex::Updated { index, body, etag } => {
+            let (definitions, warnings) = verified_definitions(url, &key, &index);
+            for warning in &warnings {
+                eprintln!("Warning: {warning}");
+            }
+            save_index(url, &body, etag.as_deref())?;
+            println!("Updated {url}: {} binary definitions", definitions.len());
+        }
+    }
+    Ok(())
+}
+
+/// Handles the `registry remove` command
+pub fn handle_registry_remove(url: &str) -> Result<(), Error> {
+    let url = url.trim();
+    let mut subscriptions = read_subscriptions()?;
+    let Some(i) = subscriptions.iter().position(|s| s.url == url) else {
+        bail!("Not subscribed to {url}");
+    };
+    subscriptions.remove(i);
+    save_subscriptions(&subscriptions)?;
+    remove_cached_index(url)?;
+    println!("Removed registry {url}");
+    Ok(())
+}
@@ -7,27 +7,63 @@ use anyhow::{Context, Error};
 use reqwest::header::ETAG;
 use reqwest::header::IF_NONE_MATCH;
 use serde::de::DeserializeOwned;
+use std::path::PathBuf;
 
-use crate::handlers::version::extract_version_from_release;
 use crate::paths::get_suiup_cache_dir;
 use crate::types::Release;
 
+/// Environment variable with the base URL of the GitHub API, e.g. to use a `suiup mirror serve`
+/// mirror instead of GitHub
+pub const GITHUB_API_URL_ENV: &str = "SUIUP_GITHUB_API_URL";
+
+/// The base URL of the GitHub API, without a trailing slash
+pub fn github_api_url() -> String {
+    std::env::var(GITHUB_API_URL_ENV)
+        .ok()
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| "https://api.github.com".to_string())
+}
+
+/// Hosts the GitHub token is sent to. Other hosts, like a mirror set with
+/// `SUIUP_GITHUB_API_URL`, never see it.
+const GITHUB_HOSTS: [&str; 3] = [
+    "api.github.com",
+    "github.com",
+    "objects.githubusercontent.com",
+];
+
+/// Whether the GitHub token can be sent with a request to `url`
+pub fn sends_github_token(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| {
+        url.scheme() == "https"
+            && url
+                .host_str()
+                .is_some_and(|host| GITHUB_HOSTS.contains(&host))
+    })
+}
+
 /// Fetches the list of releases from the GitHub repository
 pub async fn release_list(
     repo_slug: &str,
     github_token: Option<String>,
 ) -> Result<(Vec<Release>, Option<String>), anyhow::Error> {
-    let release_url = format!("https://api.github.com/repos/{}/releases", repo_slug);
+    let release_url = format!("{}/repos/{}/releases", github_api_url(), repo_slug);
     let client = reqwest::Client::new();
     let mut request = client.get(&release_url).header("User-Agent", "suiup");
 
     // Add authorization header if token is provided
-    if let Some(token) = github_token {
+    if let Some(token) = github_token
+        && sends_github_token(&release_url)
+    {
         request = request.header("Authorization", format!("token {}", token));
     }
 
-    // Add ETag for caching
-    if let Ok(etag) = read_etag_file(repo_slug) {
+    // Add ETag for caching. Caches written before release metadata was stored only have the
+    // assets of each release, so they are fetched again rather than revalidated.
+    if let Ok(etag) = read_etag_file(repo_slug)
+        && cache_has_release_metadata(repo_slug)
+    {
         request = request.header(IF_NONE_MATCH, etag);
     }
 
@@ -80,10 +116,18 @@ pub async fn release_list(
     Ok((releases, etag))
 }
 
-fn read_etag_file(repo_slug: &str) -> Result<String, anyhow::Error> {
+/// Returns the paths of the cached release list and of its ETag for the given repository
+pub fn release_list_cache_files(repo_slug: &str) -> (PathBuf, PathBuf) {
     let repo_name = repo_slug.replace("/", "_");
-    let filename = format!("etag_{}.txt", repo_name);
-    let etag_file = get_suiup_cache_dir().join(filename);
+    let cache_dir = get_suiup_cache_dir();
+    (
+        cache_dir.join(format!("releases_{}.txt", repo_name)),
+        cache_dir.join(format!("etag_{}.txt", repo_name)),
+    )
+}
+
+fn read_etag_file(repo_slug: &str) -> Result<String, anyhow::Error> {
+    let (_, etag_file) = release_list_cache_files(repo_slug);
     if etag_file.exists() {
         std::fs::read_to_string(&etag_file)
             .with_context(|| format!("Cannot read ETag file {}", etag_file.display()))
@@ -92,6 +136,13 @@ fn read_etag_file(repo_slug: &str) -> Result<String, anyhow::Error> {
     }
 }
 
+/// Whether every release in the cached release list has its tag, and so the release metadata
+fn cache_has_release_metadata(repo_slug: &str) -> bool {
+    load_cached_release_list(repo_slug).is_ok_and(|cached| {
+        cached.is_some_and(|(releases, _)| releases.iter().all(|r| !r.tag_name.is_empty()))
+    })
+}
+
 /// Finds the last release for a given network
 pub async fn find_last_release_by_network(
     releases: Vec<Release>,
@@ -108,15 +159,11 @@ fn save_release_list(
     etag: Option<String>,
 ) -> Result<(), anyhow::Error> {
     tracing::debug!("Saving releases list to cache");
-    let repo_name = repo_slug.replace("/", "_");
-    let etag_filename = format!("etag_{}.txt", repo_name);
-    let releases_filename = format!("releases_{}.txt", repo_name);
     let cache_dir = get_suiup_cache_dir();
     std::fs::create_dir_all(&cache_dir)
         .with_context(|| format!("Could not create cache directory {}", cache_dir.display()))?;
 
-    let cache_file = cache_dir.join(releases_filename);
-    let etag_file = cache_dir.join(etag_filename);
+    let (cache_file, etag_file) = release_list_cache_files(repo_slug);
 
     let cache_content = serde_json::to_string_pretty(releases)
         .context("Could not serialize GitHub releases for cache file")?;
@@ -137,11 +184,7 @@ fn save_release_list(
 fn load_cached_release_list(
     repo_slug: &str,
 ) -> Result<Option<(Vec<Release>, String)>, anyhow::Error> {
-    let repo_name = repo_slug.replace("/", "_");
-    let etag_filename = format!("etag_{}.txt", repo_name);
-    let releases_filename = format!("releases_{}.txt", repo_name);
-    let cache_file = get_suiup_cache_dir().join(releases_filename);
-    let etag_file = get_suiup_cache_dir().join(etag_filename);
+    let (cache_file, etag_file) = release_list_cache_files(repo_slug);
 
     if cache_file.exists() && etag_file.exists() {
         let raw_cache_content = std::fs::read_to_string(&cache_file)
@@ -192,7 +235,9 @@ pub async fn last_release_for_network<'a>(
     {
         Ok((
             network,
-            extract_version_from_release(release.assets[0].name.as_str())?,
+            release
+                .version()
+                .ok_or_else(|| anyhow!("Could not extract version from release"))?,
         ))
     } else {
         bail!("No release found for {network}")
@@ -234,6 +279,29 @@ mod tests {
     use super::*;
     use crate::types::{Asset, Release};
 
+    #[test]
+    fn github_token_is_only_sent_to_github() {
+        assert!(sends_github_token(
+            "https://api.github.com/repos/MystenLabs/sui/releases"
+        ));
+        assert!(sends_github_token(
+            "https://github.com/MystenLabs/sui/releases/download/testnet-v1.44.2/sui.tgz"
+        ));
+        assert!(sends_github_token(
+            "https://objects.githubusercontent.com/github-production-release-asset/1"
+        ));
+        assert!(!sends_github_token(
+            "http://mirror:8080/repos/MystenLabs/sui/releases"
+        ));
+        assert!(!sends_github_token("http://api.github.com/repos"));
+        assert!(!sends_github_token(
+            "https://github.com.example.com/sui.tgz"
+        ));
+        assert!(!sends_github_token(
+            "https://example.com/github.com/sui.tgz"
+        ));
+    }
+
     fn create_test_release(asset_names: Vec<&str>) -> Release {
         Release {
             assets: asset_names
@@ -241,8 +309,10 @@ mod tests {
                 .map(|name| Asset {
                     name: name.to_string(),
                     browser_download_url: format!("https://example.com/{}", name),
+                    ..Default::default()
                 })
                 .collect(),
+            ..Default::default()
         }
     }
 
@@ -284,4 +354,36 @@ mod tests {
         assert_eq!(ensure_version_prefix("0.1.2"), "v0.1.2");
         assert_eq!(ensure_version_prefix("v2.0.0"), "v2.0.0");
     }
+
+    #[test]
+    fn test_release_metadata_from_github() {
+        let release: Release = serde_json::from_str(
+            r#"{"tag_name": "testnet-v1.44.2", "published_at": "2025-03-04T17:21:08Z",
+                "prerelease": true, "draft": false, "body": "Notes",
+                "assets": [{"name": "sui-testnet-v1.44.2-ubuntu-x86_64.tgz",
+                    "browser_download_url": "https://example.com/sui.tgz",
+                    "size": 1024, "digest": "sha256:abc"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(release.version().as_deref(), Some("v1.44.2"));
+        assert_eq!(release.published_date(), Some("2025-03-04"));
+        assert_eq!(release.assets[0].size, Some(1024));
+        assert!(!release.is_installable(false));
+        assert!(release.is_installable(true));
+
+        // Release lists cached by older versions only have the assets
+        let cached: Release = serde_json::from_str(
+            r#"{"assets": [{"name": "sui-mainnet-v1.40.0-macos-arm64.tgz",
+                "browser_download_url": "https://example.com/sui.tgz"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(cached.version().as_deref(), Some("v1.40.0"));
+        assert!(cached.is_installable(false));
+
+        let draft = Release {
+            draft: true,
+            ..cached
+        };
+        assert!(!draft.is_installable(true));
+    }
 }
@@ -0,0 +1,408 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reconciles the recorded state with the binaries that are actually on disk, e.g. after files
+//! were deleted by hand or a data directory was copied from another machine.
+
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, bail};
+
+use crate::fs_utils::sha256_file;
+use crate::handlers::version::{extract_version_from_release, is_nightly};
+use crate::paths::{binaries_dir, default_file_path, get_default_bin_dir, installed_binaries_file};
+use crate::transaction::Transaction;
+use crate::types::{
+    BinaryVersion, BuildOptions, DefaultBinaries, DefaultBinary, InstalledBinaries,
+};
+
+/// The changes needed to bring the recorded state in line with the binaries folder
+#[derive(Debug, Default, PartialEq)]
+pub struct RepairPlan {
+    /// Binaries on disk without an entry in the installed binaries file
+    pub add: Vec<BinaryVersion>,
+    /// Entries in the installed binaries file whose binary is gone
+    pub remove: Vec<BinaryVersion>,
+    /// Default binaries whose copy in the default bin folder is missing or outdated
+    pub restore_defaults: Vec<DefaultCopy>,
+    /// Default binaries whose installed binary is gone
+    pub drop_defaults: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DefaultCopy {
+    pub name: String,
+    pub src: PathBuf,
+    pub dst: PathBuf,
+}
+
+impl RepairPlan {
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty()
+            && self.remove.is_empty()
+            && self.restore_defaults.is_empty()
+            && self.drop_defaults.is_empty()
+    }
+}
+
+impl Display for RepairPlan {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for binary in &self.add {
+            writeln!(
+                f,
+                "+ {binary} [{}] found on disk, adding it to the installed binaries",
+                binary.network_release
+            )?;
+        }
+        for binary in &self.remove {
+            writeln!(
+                f,
+                "- {binary} [{}] is missing, removing it from the installed binaries",
+                binary.network_release
+            )?;
+        }
+        for copy in &self.restore_defaults {
+            writeln!(
+                f,
+                "~ default {} is missing or outdated, copying it again from {}",
+                copy.name,
+                copy.src.display()
+            )?;
+        }
+        for name in &self.drop_defaults {
+            writeln!(
+                f,
+                "- default {name} refers to a missing binary, removing it from the defaults"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Handles the `repair` command
+pub fn handle_repair(yes: bool, dry_run: bool) -> Result<(), Error> {
+    // The plan is made without the state lock, so other suiup commands are not blocked while
+    // the confirmation prompt waits
+    let plan = plan_repair(
+        &InstalledBinaries::read_from_file()?,
+        &DefaultBinaries::read_from_file()?,
+    )?;
+
+    if plan.is_empty() {
+        println!("The installed binaries match the binaries folder, nothing to repair.");
+        return Ok(());
+    }
+    println!("Changes needed to match {}:", binaries_dir().display());
+    print!("{plan}");
+    if dry_run {
+        println!("Dry run, no changes were made.");
+        return Ok(());
+    }
+    if !yes && !confirm("Apply these changes? [y/N] ")? {
+        println!("No changes were made.");
+        return Ok(());
+    }
+
+    let mut tx = Transaction::new()?;
+    let mut installed = InstalledBinaries::read_from_file()?;
+    let mut defaults = DefaultBinaries::read_from_file()?;
+    if plan_repair(&installed, &defaults)? != plan {
+        bail!("The installed binaries changed in the meantime, run `suiup repair` again");
+    }
+
+    for binary in &plan.remove {
+        installed.remove_version(binary);
+    }
+    for binary in plan.add {
+        installed.add_binary(binary);
+    }
+    tx.write_json(&installed_binaries_file()?, &installed)?;
+
+    for copy in &plan.restore_defaults {
+        tx.copy_file(&copy.src, &copy.dst)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&copy.dst, std::fs::Permissions::from_mode(0o755))
+                .with_context(|| {
+                    format!(
+                        "Cannot set executable permissions on {}",
+                        copy.dst.display()
+                    )
+                })?;
+        }
+    }
+    for name in &plan.drop_defaults {
+        defaults.remove(name);
+        let dst = get_default_bin_dir().join(executable_name(name));
+        if dst.is_file() {
+            tx.remove_file(&dst)?;
+        }
+    }
+    tx.write_json(&default_file_path()?, &defaults)?;
+    tx.commit();
+
+    println!("Repair completed.");
+    Ok(())
+}
+
+/// Compares the recorded state with the binaries folder and the default bin folder.
+pub fn plan_repair(
+    installed: &InstalledBinaries,
+    defaults: &DefaultBinaries,
+) -> Result<RepairPlan, Error> {
+    let on_disk = scan_binaries_dir(&binaries_dir())?;
+    let mut plan = RepairPlan::default();
+
+    for binary in installed.binaries() {
+        let recorded_exists = binary
+            .path
+            .as_ref()
+            .is_some_and(|path| Path::new(path).is_file());
+        if !recorded_exists && !on_disk.iter().any(|b| same_binary(b, binary)) {
+            plan.remove.push(binary.clone());
+        }
+    }
+    for binary in &on_disk {
+        if !installed.binaries().iter().any(|b| same_binary(b, binary)) {
+            plan.add.push(binary.clone());
+        }
+    }
+
+    for (name, default) in defaults.binaries() {
+        let src = default_source_path(name, default);
+        if !src.is_file() {
+            plan.drop_defaults.push(name.clone());
+            continue;
+        }
+
+        let dst = get_default_bin_dir().join(executable_name(name));
+        if !dst.is_file() || sha256_file(&dst)? != sha256_file(&src)? {
+            plan.restore_defaults.push(DefaultCopy {
+                name: name.clone(),
+                src,
+                dst,
+            });
+        }
+    }
+    Ok(plan)
+}
+
+/// Finds all installed binaries in `binaries_dir/<network>/`, and nightly builds in
+/// `binaries_dir/<branch>/bin/`.
+fn scan_binaries_dir(binaries_dir: &Path) -> Result<Vec<BinaryVersion>, Error> {
+    let mut binaries = vec![];
+    for network_dir in read_dir_sorted(binaries_dir)? {
+        if !network_dir.is_dir() || is_hidden(&network_dir) {
+            continue;
+        }
+        let network = file_name(&network_dir);
+        let nightly_dir = network_dir.join("bin");
+        for path in read_dir_sorted(&network_dir)?
+            .into_iter()
+            .chain(read_dir_sorted(&nightly_dir)?)
+        {
+            if !path.is_file() || is_hidden(&path) {
+                continue;
+            }
+            let Some((name, version, debug)) = parse_binary_file_name(&file_name(&path)) else {
+                continue;
+            };
+            // Nightly builds only live in the `bin` folder of their branch
+            if is_nightly(&version) != (path.parent() == Some(nightly_dir.as_path())) {
+                continue;
+            }
+            binaries.push(BinaryVersion {
+                binary_name: name,
+                network_release: network.clone(),
+                version,
+                debug,
+                path: Some(path.to_string_lossy().to_string()),
+                build: BuildOptions::default(),
+            });
+        }
+    }
+    Ok(binaries)
+}
+
+/// Splits a file name like `sui-v1.44.2`, `sui-debug-v1.44.2`, `mvr-nightly` or
+/// `mvr-nightly-1a2b3c4d` into the binary name, version and debug flag.
+fn parse_binary_file_name(file_name: &str) -> Option<(String, String, bool)> {
+    let file_name = if cfg!(windows) {
+        file_name.strip_suffix(".exe")?
+    } else {
+        file_name
+    };
+
+    let version = if file_name.ends_with("-nightly") {
+        "nightly".to_string()
+    } else if let Some((_, hash)) = file_name.rsplit_once("-nightly-")
+        && hash.len() == 8
+        && hash.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        format!("nightly-{hash}")
+    } else {
+        extract_version_from_release(file_name).ok()?
+    };
+    let name = file_name.strip_suffix(&format!("-{version}"))?;
+    let (name, debug) = match name.strip_suffix("-debug") {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), version, debug))
+}
+
+fn same_binary(a: &BinaryVersion, b: &BinaryVersion) -> bool {
+    a.binary_name == b.binary_name
+        && a.network_release == b.network_release
+        && a.version == b.version
+        && a.debug == b.debug
+}
+
+/// Path of the installed binary that is copied to the default bin folder for a default entry.
+/// Debug builds set as default with `default set --debug` use the `-debug` suffixed name.
+pub(crate) fn default_source_path(name: &str, default: &DefaultBinary) -> PathBuf {
+    let base_name = match name.strip_suffix("-debug") {
+        Some(base_name) if default.debug => base_name,
+        _ => name,
+    };
+    binary_file_path(
+        &binaries_dir(),
+        base_name,
+        &default.network_release,
+        &default.version,
+        default.debug,
+    )
+}
+
+/// Path of an installed binary, following the layout used by the install commands
+pub(crate) fn binary_file_path(
+    binaries_dir: &Path,
+    name: &str,
+    network: &str,
+    version: &str,
+    debug: bool,
+) -> PathBuf {
+    let mut path = binaries_dir.join(network);
+    if is_nightly(version) {
+        path.push("bin");
+    }
+    let file_name = if debug {
+        format!("{name}-debug-{version}")
+    } else {
+        format!("{name}-{version}")
+    };
+    path.join(executable_name(&file_name))
+}
+
+pub(crate) fn executable_name(name: &str) -> String {
+    if cfg!(windows) {
+        format!("{name}.exe")
+    } else {
+        name.to_string()
+    }
+}
+
+fn read_dir_sorted(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("Cannot read directory {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Cannot read directory {}", dir.display()))?;
+    paths.sort();
+    Ok(paths)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Temporary files, backups and cargo metadata start with a dot
+fn is_hidden(path: &Path) -> bool {
+    file_name(path).starts_with('.')
+}
+
+pub(crate) fn confirm(prompt: &str) -> Result<bool, Error> {
+    print!("{prompt}");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin()
+        .read_line(&mut input)
+        .context("Cannot read the answer")?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn parse_binary_file_name_handles_all_layouts() {
+        assert_eq!(
+            parse_binary_file_name("sui-v1.44.2"),
+            Some(("sui".to_string(), "v1.44.2".to_string(), false))
+        );
+        assert_eq!(
+            parse_binary_file_name("sui-debug-v1.44.2"),
+            Some(("sui".to_string(), "v1.44.2".to_string(), true))
+        );
+        assert_eq!(
+            parse_binary_file_name("sui-graphql-rpc-v1.44.2"),
+            Some(("sui-graphql-rpc".to_string(), "v1.44.2".to_string(), false))
+        );
+        assert_eq!(
+            parse_binary_file_name("mvr-nightly"),
+            Some(("mvr".to_string(), "nightly".to_string(), false))
+        );
+        assert_eq!(
+            parse_binary_file_name("sui-debug-nightly-1a2b3c4d"),
+            Some(("sui".to_string(), "nightly-1a2b3c4d".to_string(), true))
+        );
+        assert_eq!(parse_binary_file_name("sui"), None);
+        assert_eq!(parse_binary_file_name("sui-v1.44.2.bak"), None);
+        assert_eq!(parse_binary_file_name("-v1.44.2"), None);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn scan_binaries_dir_finds_release_and_nightly_binaries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let testnet = dir.path().join("testnet");
+        let nightly = dir.path().join("main").join("bin");
+        std::fs::create_dir_all(testnet.join(".tmp123")).unwrap();
+        std::fs::create_dir_all(&nightly).unwrap();
+        for path in [
+            testnet.join("sui-v1.44.2"),
+            testnet.join("notes.txt"),
+            nightly.join("mvr-nightly"),
+            nightly.join("mvr"),
+            dir.path().join("main").join(".crates.toml"),
+        ] {
+            std::fs::write(path, b"binary").unwrap();
+        }
+
+        let binaries = scan_binaries_dir(dir.path()).unwrap();
+        let found: Vec<_> = binaries
+            .iter()
+            .map(|b| (b.network_release.as_str(), b.to_string()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("main", "mvr-nightly".to_string()),
+                ("testnet", "sui-v1.44.2".to_string())
+            ]
+        );
+    }
+}
@@ -3,24 +3,18 @@
 
 use super::download::detect_os_arch;
 
-use crate::handlers::download::download_file;
+use crate::release_source::{GitHubSource, ReleaseSource, download_asset};
+use crate::types::Release;
 use anyhow::{Context, Result, anyhow};
 use std::{fmt::Display, path::Path, process::Command};
 #[cfg(windows)]
 use std::{path::PathBuf, time::Duration};
 use tokio::task;
 
-use flate2::read::GzDecoder;
-use serde::Deserialize;
-use serde::de::DeserializeOwned;
-use std::fs::File;
-use tar::Archive;
-use zip::ZipArchive;
+use crate::archive::ReleaseArchive;
 
-#[derive(Debug, Deserialize)]
-struct GitHubRelease {
-    tag_name: String,
-}
+/// The repository suiup is released from
+const SUIUP_REPOSITORY: &str = "MystenLabs/suiup";
 
 pub fn check_for_updates() {
     task::spawn(check_for_updates_impl());
@@ -49,32 +43,16 @@ async fn check_for_updates_impl() -> Option<()> {
     Some(())
 }
 
-async fn get_latest_version() -> Result<Ver> {
-    let client = reqwest::Client::new();
-    let url = "https://api.github.com/repos/MystenLabs/suiup/releases/latest";
-    let response = client
-        .get(url)
-        .header("User-Agent", "suiup")
-        .send()
-        .await
-        .with_context(|| format!("Failed to send request to {url}"))?;
-
-    let status = response.status();
-    if !status.is_success() {
-        let body = response
-            .text()
-            .await
-            .unwrap_or_else(|e| format!("Unable to read response body: {e}"));
-        return Err(anyhow!(
-            "Failed to fetch latest version from GitHub (status {}) for {}: {}",
-            status,
-            url,
-            body
-        ));
-    }
+/// The latest release of suiup
+async fn latest_release(source: &GitHubSource) -> Result<Release> {
+    source
+        .latest_release()
+        .await?
+        .ok_or_else(|| anyhow!("No release of suiup found in {SUIUP_REPOSITORY}"))
+}
 
-    let release: GitHubRelease =
-        parse_json_response(response, url, "GitHub latest release").await?;
+async fn get_latest_version() -> Result<Ver> {
+    let release = latest_release(&GitHubSource::new(SUIUP_REPOSITORY, None)).await?;
     Ver::from_str(&release.tag_name)
 }
 
@@ -125,8 +103,9 @@ pub async fn handle_update() -> Result<()> {
             )
         })
         .and_then(Ver::from_str)?;
-    let latest_version = get_latest_version().await?;
-    let tag = format!("v{latest_version}");
+    let source = GitHubSource::new(SUIUP_REPOSITORY, None);
+    let release = latest_release(&source).await?;
+    let latest_version = Ver::from_str(&release.tag_name)?;
 
     if current_version == latest_version {
         println!("suiup is already up to date");
@@ -134,70 +113,26 @@ pub async fn handle_update() -> Result<()> {
     }
     println!("Updating to latest version: {}", latest_version);
 
-    // download the latest version from github
-    // https://github.com/MystenLabs/suiup/releases/download/v0.0.1/suiup-Linux-musl-x86_64.tar.gz
-
     let archive_name = find_archive_name()?;
-    let url =
-        format!("https://github.com/MystenLabs/suiup/releases/download/{tag}/{archive_name}",);
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == archive_name)
+        .ok_or_else(|| anyhow!("Release {} has no {archive_name}", release.tag_name))?;
 
     let temp_dir = tempfile::tempdir()?;
     let archive_path = temp_dir.path().join(&archive_name);
-    download_file(&url, &temp_dir.path().join(&archive_name), "suiup", None).await?;
-
-    // extract the archive based on file extension
-    if archive_name.ends_with(".zip") {
-        // Handle ZIP extraction
-        let file = File::open(archive_path.as_path())
-            .with_context(|| format!("Cannot open archive file {}", archive_path.display()))?;
-        let mut archive = ZipArchive::new(file)
-            .with_context(|| format!("Cannot read zip archive {}", archive_path.display()))?;
-
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i).with_context(|| {
-                format!(
-                    "Cannot read entry at index {} from zip archive {}",
-                    i,
-                    archive_path.display()
-                )
-            })?;
-            let outpath = temp_dir.path().join(file.name());
-
-            if file.is_dir() {
-                std::fs::create_dir_all(&outpath).with_context(|| {
-                    format!("Cannot create extraction directory {}", outpath.display())
-                })?;
-            } else {
-                if let Some(parent) = outpath.parent() {
-                    std::fs::create_dir_all(parent).with_context(|| {
-                        format!(
-                            "Cannot create parent directory for extraction {}",
-                            parent.display()
-                        )
-                    })?;
-                }
-                let mut outfile = File::create(&outpath).with_context(|| {
-                    format!("Cannot create extracted file {}", outpath.display())
-                })?;
-                std::io::copy(&mut file, &mut outfile).with_context(|| {
-                    format!("Cannot write extracted file {}", outpath.display())
-                })?;
-            }
-        }
-    } else {
-        // Handle tar.gz extraction
-        let file = File::open(archive_path.as_path())
-            .with_context(|| format!("Cannot open archive file {}", archive_path.display()))?;
-        let tar = GzDecoder::new(file);
-        let mut archive = Archive::new(tar);
-        archive.unpack(temp_dir.path()).with_context(|| {
+    download_asset(&source, asset, &archive_path, "suiup").await?;
+
+    ReleaseArchive::open(&archive_path)?
+        .unpack(temp_dir.path())
+        .with_context(|| {
             format!(
                 "Cannot unpack archive file {} into {}",
                 archive_path.display(),
                 temp_dir.path().display()
             )
         })?;
-    }
 
     #[cfg(not(windows))]
     let binary = "suiup";
@@ -241,26 +176,6 @@ pub fn handle_uninstall() -> Result<()> {
     Ok(())
 }
 
-async fn parse_json_response<T>(
-    response: reqwest::Response,
-    request_url: &str,
-    response_name: &str,
-) -> Result<T>
-where
-    T: DeserializeOwned,
-{
-    let response_body = response
-        .text()
-        .await
-        .with_context(|| format!("Cannot read {response_name} response body from {request_url}"))?;
-
-    serde_json::from_str(&response_body).map_err(|e| {
-        anyhow!(
-            "Failed to deserialize {response_name} response from {request_url}: {e}\nResponse body:\n{response_body}"
-        )
-    })
-}
-
 pub fn handle_complete_update(
     target: &Path,
     source: &Path,
@@ -3,35 +3,36 @@
 
 use crate::{
     handlers::installed_binaries_grouped_by_network,
-    paths::default_file_path,
-    types::{Binaries, BinaryVersion, Version},
+    types::{Binaries, BinaryVersion, DefaultBinaries, InstalledBinaries},
 };
-use anyhow::{Context, Error, anyhow};
-use std::collections::BTreeMap;
+use anyhow::Error;
 
 use crate::commands::print_table;
 
-/// Load default binaries from configuration file
+/// Load default binaries from configuration file, with the cargo settings of the installed
+/// binary they point to
 fn load_default_binaries() -> Result<Binaries, Error> {
-    let default_path = default_file_path()?;
-    let default = std::fs::read_to_string(&default_path)
-        .with_context(|| format!("Cannot read default file {}", default_path.display()))?;
-    let default: BTreeMap<String, (String, Version, bool)> = serde_json::from_str(&default)
-        .map_err(|e| {
-            anyhow!(
-                "Cannot deserialize default file {}: {e}",
-                default_path.display()
-            )
-        })?;
-    Ok(Binaries::from(default))
+    let mut defaults = Binaries::from(&DefaultBinaries::read_from_file()?);
+    let installed = InstalledBinaries::read_from_file()?;
+    for default in &mut defaults.binaries {
+        if let Some(binary) = installed.binaries().iter().find(|b| {
+            b.binary_name == default.binary_name
+                && b.network_release == default.network_release
+                && b.version == default.version
+                && b.debug == default.debug
+        }) {
+            default.build = binary.build.clone();
+        }
+    }
+    Ok(defaults)
 }
 
 /// Load installed binaries grouped by network
 fn load_installed_binaries() -> Result<Vec<BinaryVersion>, Error> {
     let installed_binaries = installed_binaries_grouped_by_network(None)?;
     let binaries = installed_binaries
-        .into_iter()
-        .flat_map(|(_, binaries)| binaries.to_owned())
+        .into_values()
+        .flat_map(|binaries| binaries.to_owned())
         .collect();
     Ok(binaries)
 }
@@ -6,9 +6,11 @@ use std::collections::{BTreeMap, HashMap};
 use anyhow::{Error, Result};
 use colored::Colorize;
 
+use crate::handlers::changelog::{releases_between, render_changelog};
 use crate::handlers::installed_binaries_grouped_by_network;
-use crate::handlers::release::{last_release_for_network, release_list};
-use crate::registry::{BinaryRegistry, InstallationType};
+use crate::handlers::release::last_release_for_network;
+use crate::handlers::version::{compare_versions, is_nightly};
+use crate::registry::{BinaryConfig, BinaryRegistry, InstallationType};
 use crate::standalone::StandaloneInstaller;
 use crate::types::{BinaryVersion, InstalledBinaries};
 
@@ -24,10 +26,13 @@ struct StatusEntry {
     installed_version: String,
     latest_version: Option<String>,
     status: UpdateStatus,
+    /// Release notes of the update, with `--changelog`
+    changelog: Option<String>,
 }
 
 /// Handles the `status` command -- checks for available updates for all installed binaries.
-pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
+/// With `changelog`, the release notes of each available update are shown too.
+pub async fn handle_status(changelog: bool, github_token: Option<String>) -> Result<(), Error> {
     let installed_binaries = InstalledBinaries::new()?;
     let binaries = installed_binaries.binaries().to_vec();
 
@@ -41,15 +46,17 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
     // Separate nightly binaries -- they are installed from branches and we don't
     // track commit SHAs, so we cannot check for updates.
     let nightly_binaries: Vec<&BinaryVersion> =
-        binaries.iter().filter(|b| b.version == "nightly").collect();
+        binaries.iter().filter(|b| is_nightly(&b.version)).collect();
     let release_binaries: Vec<BinaryVersion> = binaries
         .iter()
-        .filter(|b| b.version != "nightly")
+        .filter(|b| !is_nightly(&b.version))
         .cloned()
         .collect();
 
     let binaries_by_network = installed_binaries_grouped_by_network(Some(installed_binaries))?;
     let registry = BinaryRegistry::global();
+    // Binaries installed with `--with` follow the releases of the binary they ship with
+    let config_of = |name: &str| registry.get(name).or_else(|| registry.member_of(name));
 
     // Collect unique installed binary names from release (non-nightly) binaries only
     let mut installed_names: Vec<String> = release_binaries
@@ -60,44 +67,48 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
         .collect();
     installed_names.sort();
 
-    // Group installed binaries by repo for deduplication of API calls
-    let mut repo_to_names: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    // Group installed binaries by release source for deduplication of API calls
+    let mut source_to_names: BTreeMap<String, Vec<&str>> = BTreeMap::new();
     for name in &installed_names {
-        if let Some(config) = registry.get(name) {
-            repo_to_names
-                .entry(&config.repository)
+        if let Some(config) = config_of(name) {
+            source_to_names
+                .entry(release_source_key(config))
                 .or_default()
                 .push(name);
         }
     }
 
-    // Fetch releases per repo (deduplicated)
+    // Fetch releases per source (deduplicated)
     let mut network_releases: HashMap<String, Result<Vec<crate::types::Release>, String>> =
         HashMap::new();
     let mut standalone_installers: HashMap<String, StandaloneInstaller> = HashMap::new();
 
-    for repo_slug in repo_to_names.keys() {
-        // Determine if this repo is standalone or network-based by checking the first binary's config
-        let first_name = repo_to_names[repo_slug].first().unwrap();
-        let config = registry.get(first_name).unwrap();
+    for (source_key, names) in &source_to_names {
+        // Determine if this source is standalone or network-based by checking the first binary's config
+        let first_name = names.first().unwrap();
+        let config = config_of(first_name).unwrap();
 
         if config.installation_type == InstallationType::Standalone {
-            let mut installer = StandaloneInstaller::new(repo_slug, github_token.clone());
+            let mut installer = StandaloneInstaller::for_binary(config, github_token.clone());
             match installer.get_releases().await {
                 Ok(()) => {
-                    standalone_installers.insert(repo_slug.to_string(), installer);
+                    standalone_installers.insert(source_key.clone(), installer);
                 }
                 Err(e) => {
-                    network_releases.insert(repo_slug.to_string(), Err(e.to_string()));
+                    network_releases.insert(source_key.clone(), Err(e.to_string()));
                 }
             }
         } else {
-            match release_list(repo_slug, github_token.clone()).await {
-                Ok((releases, _)) => {
-                    network_releases.insert(repo_slug.to_string(), Ok(releases));
+            match config.release_source(github_token.clone()).releases().await {
+                Ok(releases) => {
+                    let releases = releases
+                        .into_iter()
+                        .filter(|release| release.is_installable(false))
+                        .collect();
+                    network_releases.insert(source_key.clone(), Ok(releases));
                 }
                 Err(e) => {
-                    network_releases.insert(repo_slug.to_string(), Err(e.to_string()));
+                    network_releases.insert(source_key.clone(), Err(e.to_string()));
                 }
             }
         }
@@ -107,7 +118,7 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
     let mut all_entries: BTreeMap<String, Vec<StatusEntry>> = BTreeMap::new();
 
     for name in &installed_names {
-        let config = match registry.get(name) {
+        let config = match config_of(name) {
             Some(c) => c,
             None => continue,
         };
@@ -122,7 +133,7 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
                 None => continue,
             };
 
-            if let Some(installer) = standalone_installers.get(&config.repository as &str) {
+            if let Some(installer) = standalone_installers.get(&release_source_key(config)) {
                 match installer.latest_version() {
                     Ok(latest) => {
                         let status = if installed_version == latest {
@@ -130,11 +141,22 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
                         } else {
                             UpdateStatus::UpdateAvailable
                         };
+                        let changelog = (changelog
+                            && matches!(status, UpdateStatus::UpdateAvailable))
+                        .then(|| {
+                            render_changelog(&releases_between(
+                                installer.releases(),
+                                None,
+                                &installed_version,
+                                &latest,
+                            ))
+                        });
                         entries.push(StatusEntry {
                             network: None,
                             installed_version,
                             latest_version: Some(latest),
                             status,
+                            changelog,
                         });
                     }
                     Err(e) => {
@@ -143,20 +165,22 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
                             installed_version,
                             latest_version: None,
                             status: UpdateStatus::FetchError(e.to_string()),
+                            changelog: None,
                         });
                     }
                 }
-            } else if let Some(Err(e)) = network_releases.get(&config.repository as &str) {
+            } else if let Some(Err(e)) = network_releases.get(&release_source_key(config)) {
                 entries.push(StatusEntry {
                     network: None,
                     installed_version,
                     latest_version: None,
                     status: UpdateStatus::FetchError(e.clone()),
+                    changelog: None,
                 });
             }
         } else {
             // Network-based binary: check each network
-            let releases = match network_releases.get(&config.repository as &str) {
+            let releases = match network_releases.get(&release_source_key(config)) {
                 Some(Ok(r)) => r,
                 Some(Err(e)) => {
                     // Show error for each network this binary is installed under
@@ -170,6 +194,7 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
                                     .unwrap_or_else(|| "unknown".to_string()),
                                 latest_version: None,
                                 status: UpdateStatus::FetchError(e.clone()),
+                                changelog: None,
                             });
                         }
                     }
@@ -182,7 +207,7 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
                 // Filter out nightly entries from this network group
                 let release_only: Vec<_> = network_binaries
                     .iter()
-                    .filter(|b| b.version != "nightly")
+                    .filter(|b| !is_nightly(&b.version))
                     .cloned()
                     .collect();
                 let installed_version = match find_max_version_in_network(&release_only, name) {
@@ -197,11 +222,22 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
                         } else {
                             UpdateStatus::UpdateAvailable
                         };
+                        let changelog = (changelog
+                            && matches!(status, UpdateStatus::UpdateAvailable))
+                        .then(|| {
+                            render_changelog(&releases_between(
+                                releases,
+                                Some(network),
+                                &installed_version,
+                                &latest_version,
+                            ))
+                        });
                         entries.push(StatusEntry {
                             network: Some(network.clone()),
                             installed_version,
                             latest_version: Some(latest_version),
                             status,
+                            changelog,
                         });
                     }
                     Err(_) => {
@@ -224,6 +260,7 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
                 installed_version: b.version.clone(),
                 latest_version: None,
                 status: UpdateStatus::Nightly,
+                changelog: None,
             });
     }
 
@@ -252,7 +289,7 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
             continue;
         }
 
-        let config = registry.get(name);
+        let config = config_of(name);
         let repo = config.map(|c| c.repository.as_str()).unwrap_or("unknown");
         println!("\n{} ({})", name.bold(), repo.dimmed());
 
@@ -285,12 +322,25 @@ pub async fn handle_status(github_token: Option<String>) -> Result<(), Error> {
                 max_version_w,
                 &mut update_count,
             );
+            if let Some(changelog) = &entry.changelog {
+                for line in changelog.lines() {
+                    if line.is_empty() {
+                        println!();
+                    } else {
+                        println!("    {line}");
+                    }
+                }
+            }
         }
 
         // Print nightly entries as simple lines
         for entry in nightly {
             let branch = entry.network.as_deref().unwrap_or("unknown");
-            println!("  {} {}", branch, "(nightly)".dimmed());
+            println!(
+                "  {} {}",
+                branch,
+                format!("({})", entry.installed_version).dimmed()
+            );
         }
     }
 
@@ -336,11 +386,19 @@ fn print_release_entry(
         UpdateStatus::UpdateAvailable => {
             *update_count += 1;
             let latest = entry.latest_version.as_deref().unwrap_or("?");
+            // Binaries installed with `--with` are installed from the archive of their binary
+            let (install_name, with) = match BinaryRegistry::global().member_of(binary_name) {
+                Some(parent) => (parent.name.as_str(), format!(" --with {binary_name}")),
+                None => (binary_name, String::new()),
+            };
             let update_cmd = match &entry.network {
                 Some(network) => {
-                    format!("suiup install {}@{}-{}", binary_name, network, latest)
+                    format!(
+                        "suiup install {}@{}-{}{}",
+                        install_name, network, latest, with
+                    )
                 }
-                None => format!("suiup install {}@{}", binary_name, latest),
+                None => format!("suiup install {}@{}{}", install_name, latest, with),
             };
             println!(
                 "  {}{:<width$} {} {}   {}",
@@ -368,17 +426,10 @@ fn print_release_entry(
     }
 }
 
-/// Parse a version string (with optional leading 'v') into a semver::Version.
-fn parse_semver(v: &str) -> Option<semver::Version> {
-    semver::Version::parse(v.strip_prefix('v').unwrap_or(v)).ok()
-}
-
-/// Compare two version strings using semver, falling back to lexicographic comparison.
-fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
-    match (parse_semver(a), parse_semver(b)) {
-        (Some(va), Some(vb)) => va.cmp(&vb),
-        _ => a.cmp(b),
-    }
+/// Identifies where the releases of a binary come from, so that binaries published together
+/// share one release list
+fn release_source_key(config: &BinaryConfig) -> String {
+    config.release_source(None).describe()
 }
 
 /// Find the max version of a binary across all networks/entries.
@@ -402,7 +453,7 @@ fn find_max_version_in_network(network_binaries: &[BinaryVersion], name: &str) -
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::BinaryVersion;
+    use crate::types::{BinaryVersion, BuildOptions};
 
     fn make_binary(name: &str, network: &str, version: &str) -> BinaryVersion {
         BinaryVersion {
@@ -411,6 +462,7 @@ mod tests {
             version: version.to_string(),
             debug: false,
             path: None,
+            build: BuildOptions::default(),
         }
     }
 
@@ -505,7 +557,7 @@ mod tests {
 
     #[test]
     fn nightly_binaries_are_separated() {
-        let binaries = vec![
+        let binaries = [
             make_binary("sui", "testnet", "v1.39.0"),
             make_binary("sui", "main", "nightly"),
             make_binary("mvr", "standalone", "v0.6.4"),
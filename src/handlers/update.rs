@@ -2,14 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::handlers::{
-    available_components, installed_binaries_grouped_by_network,
-    release::{last_release_for_network, release_list},
+    available_components, installed_binaries_grouped_by_network, release::last_release_for_network,
 };
 use crate::{
-    commands::{CommandMetadata, ComponentCommands, parse_component_with_version},
+    commands::{CommandMetadata, ComponentCommands, parse_member_with_version},
     handle_commands::handle_cmd,
     registry::InstallationType,
-    types::InstalledBinaries,
+    types::{InstalledBinaries, Release},
 };
 use anyhow::{Error, bail};
 
@@ -17,13 +16,21 @@ use anyhow::{Error, bail};
 pub async fn handle_update(
     binary_name: String,
     yes: bool,
+    include_prereleases: bool,
     github_token: Option<String>,
 ) -> Result<(), Error> {
     if binary_name.is_empty() {
         bail!("Invalid number of arguments for `update` command");
     }
 
-    let CommandMetadata { name, version, .. } = parse_component_with_version(&binary_name)?;
+    // Binaries installed with `--with` are updated from the archive of the binary they ship with
+    let (installed_name, CommandMetadata { name, version, .. }) =
+        parse_member_with_version(&binary_name)?;
+    let (component, with) = if installed_name == name.as_str() {
+        (binary_name, vec![])
+    } else {
+        (name.to_string(), vec![installed_name.clone()])
+    };
 
     if version.is_some() {
         bail!("Update should be done without a version. Use `suiup install` to specify a version");
@@ -36,9 +43,9 @@ pub async fn handle_update(
     let config = name.config();
     let installed_binaries = InstalledBinaries::new()?;
     let binaries = installed_binaries.binaries();
-    if !binaries.iter().any(|x| x.binary_name == name.as_str()) {
+    if !binaries.iter().any(|x| x.binary_name == installed_name) {
         bail!(
-            "Binary {name} not found in installed binaries. Use `suiup show` to see installed binaries and `suiup install` to install the binary."
+            "Binary {installed_name} not found in installed binaries. Use `suiup show` to see installed binaries and `suiup install` to install the binary."
         )
     }
     let binaries_by_network = installed_binaries_grouped_by_network(Some(installed_binaries))?;
@@ -48,7 +55,7 @@ pub async fn handle_update(
     for (network, binaries) in &binaries_by_network {
         let last_version = binaries
             .iter()
-            .filter(|x| x.binary_name == name.as_str())
+            .filter(|x| x.binary_name == installed_name)
             .collect::<Vec<_>>();
         if last_version.is_empty() {
             continue;
@@ -68,10 +75,16 @@ pub async fn handle_update(
     if !config.network_based || config.installation_type == InstallationType::Standalone {
         handle_cmd(
             ComponentCommands::Add {
-                component: binary_name,
+                component,
                 debug: false,
                 nightly: None,
                 yes,
+                from_file: None,
+                from_url: None,
+                with,
+                build: Box::default(),
+                build_from_source_fallback: false,
+                pre: include_prereleases,
             },
             github_token.as_deref(),
         )
@@ -79,29 +92,41 @@ pub async fn handle_update(
         return Ok(());
     }
 
-    let releases = release_list(&config.repository, github_token.clone())
+    let releases: Vec<Release> = config
+        .release_source(github_token.clone())
+        .releases()
         .await?
-        .0;
+        .into_iter()
+        .filter(|release| release.is_installable(include_prereleases))
+        .collect();
     let mut to_update = vec![];
     for (n, v) in &network_local_last_version {
         let last_release = last_release_for_network(&releases, n).await?;
         let last_version = last_release.1;
         if v == &last_version {
-            println!("[{n} release] {name} is up to date");
+            println!("[{n} release] {installed_name} is up to date");
         } else {
-            println!("[{n} release] {name} is outdated. Local: {v}, Latest: {last_version}");
+            println!(
+                "[{n} release] {installed_name} is outdated. Local: {v}, Latest: {last_version}"
+            );
             to_update.push((n, last_version));
         }
     }
 
     for (n, v) in to_update.iter() {
-        println!("Updating {name} to {v} from {n} release");
+        println!("Updating {installed_name} to {v} from {n} release");
         handle_cmd(
             ComponentCommands::Add {
-                component: binary_name.clone(),
+                component: component.clone(),
                 debug: false,
                 nightly: None,
                 yes,
+                from_file: None,
+                from_url: None,
+                with: with.clone(),
+                build: Box::default(),
+                build_from_source_fallback: false,
+                pre: include_prereleases,
             },
             github_token.as_deref(),
         )
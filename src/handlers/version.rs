@@ -16,3 +16,22 @@ pub fn extract_version_from_release(release: &str) -> Result<String, Error> {
 
     Ok(captures.get(0).unwrap().as_str().to_string())
 }
+
+/// Whether a version is the one of a build from a branch, `nightly` or `nightly-<hash>` for a
+/// build with custom cargo settings
+pub fn is_nightly(version: &str) -> bool {
+    version == "nightly" || version.starts_with("nightly-")
+}
+
+/// Parse a version string (with optional leading 'v') into a semver::Version.
+pub fn parse_semver(v: &str) -> Option<semver::Version> {
+    semver::Version::parse(v.strip_prefix('v').unwrap_or(v)).ok()
+}
+
+/// Compare two version strings using semver, falling back to lexicographic comparison.
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    match (parse_semver(a), parse_semver(b)) {
+        (Some(va), Some(vb)) => va.cmp(&vb),
+        _ => a.cmp(b),
+    }
+}
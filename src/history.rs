@@ -0,0 +1,363 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The history journal: every committed change to the installed or default binaries is
+//! appended to `history.jsonl` in the suiup data directory, together with the state before and
+//! after the change. Changes made by the same suiup invocation form one operation, which is what
+//! `suiup history` lists and `suiup undo` reverts.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Error, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::paths::get_suiup_data_dir;
+use crate::types::{BinaryVersion, DefaultBinaries, DefaultBinary, InstalledBinaries};
+
+static RECORDING_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// The installed and default binaries at one point in time
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StateSnapshot {
+    pub installed: Vec<BinaryVersion>,
+    pub defaults: BTreeMap<String, DefaultBinary>,
+}
+
+/// A committed change, as stored in the journal
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Identifies the suiup invocation that made the change
+    pub session: String,
+    /// The command line of the invocation
+    pub command: String,
+    pub before: StateSnapshot,
+    pub after: StateSnapshot,
+}
+
+/// All changes made by one suiup invocation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    /// 1-based position in the history, the oldest operation is 1
+    pub id: usize,
+    pub timestamp: u64,
+    pub command: String,
+    pub before: StateSnapshot,
+    pub after: StateSnapshot,
+}
+
+impl StateSnapshot {
+    /// Reads the current state from the state files
+    pub fn read() -> Result<Self, Error> {
+        Ok(Self {
+            installed: InstalledBinaries::read_from_file()?.binaries().to_vec(),
+            defaults: DefaultBinaries::read_from_file()?.binaries().clone(),
+        })
+    }
+
+    /// Human readable lines describing how to get from `self` to `other`
+    pub fn diff(&self, other: &StateSnapshot) -> Vec<String> {
+        let mut lines = vec![];
+        for binary in &other.installed {
+            if !self.installed.contains(binary) {
+                lines.push(format!("+ {binary} [{}]", binary.network_release));
+            }
+        }
+        for binary in &self.installed {
+            if !other.installed.contains(binary) {
+                lines.push(format!("- {binary} [{}]", binary.network_release));
+            }
+        }
+
+        let names: std::collections::BTreeSet<_> =
+            self.defaults.keys().chain(other.defaults.keys()).collect();
+        for name in names {
+            let from = self.defaults.get(name);
+            let to = other.defaults.get(name);
+            if from != to {
+                lines.push(format!(
+                    "~ default {name}: {} -> {}",
+                    describe_default(from),
+                    describe_default(to)
+                ));
+            }
+        }
+        lines
+    }
+}
+
+fn describe_default(default: Option<&DefaultBinary>) -> String {
+    match default {
+        Some(default) if default.debug => format!(
+            "{}@{} (debug build)",
+            default.version, default.network_release
+        ),
+        Some(default) => format!("{}@{}", default.version, default.network_release),
+        None => "none".to_string(),
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "#{} {} {}",
+            self.id,
+            format_timestamp(self.timestamp),
+            self.command
+        )?;
+        for line in self.before.diff(&self.after) {
+            writeln!(f, "    {line}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn history_file() -> PathBuf {
+    get_suiup_data_dir().join("history.jsonl")
+}
+
+/// Appends a change to the journal, unless nothing changed or recording is suspended.
+pub fn record(before: StateSnapshot, after: StateSnapshot) -> Result<(), Error> {
+    if before == after || RECORDING_SUSPENDED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let entry = JournalEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        session: session().to_string(),
+        command: command_line(),
+        before,
+        after,
+    };
+
+    let path = history_file();
+    let mut line = serde_json::to_string(&entry).context("Cannot serialize history entry")?;
+    line.push('\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Cannot write history file {}", path.display()))
+}
+
+/// Stops recording changes until the returned guard is dropped. Used by `undo`, which records
+/// its changes as a single operation.
+pub fn suspend_recording() -> RecordingGuard {
+    RECORDING_SUSPENDED.store(true, Ordering::SeqCst);
+    RecordingGuard
+}
+
+pub struct RecordingGuard;
+
+impl Drop for RecordingGuard {
+    fn drop(&mut self) {
+        RECORDING_SUSPENDED.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Appends a change to the journal regardless of whether recording is suspended.
+pub fn record_operation(before: StateSnapshot, after: StateSnapshot) -> Result<(), Error> {
+    let suspended = RECORDING_SUSPENDED.swap(false, Ordering::SeqCst);
+    let result = record(before, after);
+    RECORDING_SUSPENDED.store(suspended, Ordering::SeqCst);
+    result
+}
+
+/// Reads the journal and groups its entries into operations, oldest first
+pub fn read_operations() -> Result<Vec<Operation>, Error> {
+    let path = history_file();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Cannot read history file {}", path.display()))?;
+    let entries = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_str::<JournalEntry>(line).map_err(|e| {
+                anyhow!(
+                    "Cannot parse line {} of history file {}: {e}",
+                    i + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(group_entries(entries))
+}
+
+fn group_entries(entries: Vec<JournalEntry>) -> Vec<Operation> {
+    let mut operations: Vec<(String, Operation)> = vec![];
+    for entry in entries {
+        match operations.last_mut() {
+            Some((session, operation)) if *session == entry.session => {
+                operation.after = entry.after;
+            }
+            _ => operations.push((
+                entry.session,
+                Operation {
+                    id: operations.len() + 1,
+                    timestamp: entry.timestamp,
+                    command: entry.command,
+                    before: entry.before,
+                    after: entry.after,
+                },
+            )),
+        }
+    }
+    operations.into_iter().map(|(_, op)| op).collect()
+}
+
+fn session() -> &'static str {
+    static SESSION: OnceLock<String> = OnceLock::new();
+    SESSION.get_or_init(|| {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        format!("{}-{nanos}", std::process::id())
+    })
+}
+
+/// The command line of this invocation, without secrets
+fn command_line() -> String {
+    let mut args = vec!["suiup".to_string()];
+    let mut redact_next = false;
+    for arg in std::env::args().skip(1) {
+        if redact_next {
+            args.push("***".to_string());
+            redact_next = false;
+        } else if arg == "--github-token" {
+            args.push(arg);
+            redact_next = true;
+        } else if arg.starts_with("--github-token=") {
+            args.push("--github-token=***".to_string());
+        } else {
+            args.push(arg);
+        }
+    }
+    args.join(" ")
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+    // Converts days since the epoch to a civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3_600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::BuildOptions;
+
+    fn binary(version: &str) -> BinaryVersion {
+        BinaryVersion {
+            binary_name: "sui".to_string(),
+            network_release: "testnet".to_string(),
+            version: version.to_string(),
+            debug: false,
+            path: None,
+            build: BuildOptions::default(),
+        }
+    }
+
+    fn snapshot(versions: &[&str], default: Option<&str>) -> StateSnapshot {
+        StateSnapshot {
+            installed: versions.iter().map(|v| binary(v)).collect(),
+            defaults: default
+                .map(|version| {
+                    (
+                        "sui".to_string(),
+                        DefaultBinary {
+                            network_release: "testnet".to_string(),
+                            version: version.to_string(),
+                            debug: false,
+                        },
+                    )
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn entry(session: &str, before: StateSnapshot, after: StateSnapshot) -> JournalEntry {
+        JournalEntry {
+            timestamp: 0,
+            session: session.to_string(),
+            command: format!("suiup {session}"),
+            before,
+            after,
+        }
+    }
+
+    #[test]
+    fn diff_lists_binaries_and_defaults() {
+        let before = snapshot(&["v1.44.2"], Some("v1.44.2"));
+        let after = snapshot(&["v1.45.0"], Some("v1.45.0"));
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                "+ sui-v1.45.0 [testnet]",
+                "- sui-v1.44.2 [testnet]",
+                "~ default sui: v1.44.2@testnet -> v1.45.0@testnet",
+            ]
+        );
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn entries_of_one_session_form_one_operation() {
+        let s0 = snapshot(&[], None);
+        let s1 = snapshot(&["v1.44.2"], Some("v1.44.2"));
+        let s2 = snapshot(&["v1.44.2", "v1.45.0"], Some("v1.44.2"));
+        let s3 = snapshot(&["v1.44.2", "v1.45.0"], Some("v1.45.0"));
+
+        let operations = group_entries(vec![
+            entry("a", s0.clone(), s1.clone()),
+            entry("b", s1.clone(), s2.clone()),
+            entry("b", s2, s3.clone()),
+        ]);
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0].id, 1);
+        assert_eq!(operations[1].id, 2);
+        assert_eq!(operations[1].command, "suiup b");
+        assert_eq!(operations[1].before, s1);
+        assert_eq!(operations[1].after, s3);
+    }
+
+    #[test]
+    fn format_timestamp_as_utc() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_709_210_096), "2024-02-29 12:34:56 UTC");
+    }
+}
@@ -1,14 +1,26 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod archive;
+pub mod asset_pattern;
+pub mod binary_info;
+pub mod cache;
 pub mod commands;
 pub mod component;
 pub mod fs_utils;
 pub mod handle_commands;
 pub mod handlers;
+pub mod history;
+pub mod lock;
+pub mod mirror;
 pub mod paths;
 pub mod registry;
+pub mod release_source;
+pub mod remote_registry;
 pub mod standalone;
+pub mod state;
+pub mod toolchain;
+pub mod transaction;
 pub mod types;
 
 /// Macro to safely wrap `std::env::set_var` calls in an unsafe block.
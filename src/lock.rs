@@ -0,0 +1,165 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Advisory file locks that keep concurrent suiup processes, e.g. parallel CI jobs on a shared
+//! runner, from modifying the same state files or downloading the same archive at once.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Error, bail};
+
+use crate::paths::{cache_lock_file, state_lock_file};
+
+/// Environment variable with the number of seconds to wait for a lock before giving up
+pub const LOCK_TIMEOUT_ENV: &str = "SUIUP_LOCK_TIMEOUT";
+
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An exclusive lock on a lock file, released when dropped or when the process exits.
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Acquires the lock on `path`, waiting for at most the configured timeout if another
+    /// process holds it. `purpose` describes what the lock protects in the waiting message.
+    pub fn acquire(path: &Path, purpose: &str) -> Result<Self, Error> {
+        Self::acquire_with_timeout(path, purpose, lock_timeout()?)
+    }
+
+    /// Acquires the lock on `path` if no other process holds it
+    pub fn try_acquire(path: &Path) -> Result<Option<Self>, Error> {
+        let file = open_lock_file(path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => {
+                Err(e).with_context(|| format!("Cannot lock file {}", path.display()))
+            }
+        }
+    }
+
+    fn acquire_with_timeout(path: &Path, purpose: &str, timeout: Duration) -> Result<Self, Error> {
+        let file = open_lock_file(path)?;
+
+        let start = Instant::now();
+        let mut waiting = false;
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { _file: file }),
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("Cannot lock file {}", path.display()));
+                }
+            }
+
+            if !waiting {
+                eprintln!("Waiting for another suiup process that is {purpose}...");
+                waiting = true;
+            }
+            if start.elapsed() >= timeout {
+                bail!(
+                    "Timed out after {}s waiting for another suiup process that is {purpose} (lock file: {}). Set {LOCK_TIMEOUT_ENV} to wait longer",
+                    timeout.as_secs(),
+                    path.display()
+                );
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+fn open_lock_file(path: &Path) -> Result<File, Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Cannot create directory {}", parent.display()))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Cannot open lock file {}", path.display()))
+}
+
+/// Locks the suiup state: installed binaries, default binaries and their metadata files.
+pub fn lock_state() -> Result<FileLock, Error> {
+    FileLock::acquire(&state_lock_file(), "modifying the installed binaries")
+}
+
+/// Locks a download destination, so that only one process writes the file at a time.
+pub fn lock_download(download_to: &Path) -> Result<FileLock, Error> {
+    let file_name = download_to
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    FileLock::acquire(
+        &download_lock_file(download_to),
+        &format!("downloading {file_name}"),
+    )
+}
+
+/// Locks a download destination, unless another process is downloading it
+pub fn try_lock_download(download_to: &Path) -> Result<Option<FileLock>, Error> {
+    FileLock::try_acquire(&download_lock_file(download_to))
+}
+
+/// The lock file of a download destination, `<file>.lock`
+pub fn download_lock_file(download_to: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.lock", download_to.display()))
+}
+
+/// Locks the shared target directory of source builds
+pub fn lock_build(target_dir: &Path) -> Result<FileLock, Error> {
+    FileLock::acquire(
+        &build_lock_file(target_dir),
+        &format!("building in {}", target_dir.display()),
+    )
+}
+
+/// The lock file of a build target directory, next to the directory
+pub fn build_lock_file(target_dir: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.lock", target_dir.display()))
+}
+
+/// Locks the index of the release archive cache
+pub fn lock_cache() -> Result<FileLock, Error> {
+    FileLock::acquire(&cache_lock_file(), "updating the download cache")
+}
+
+fn lock_timeout() -> Result<Duration, Error> {
+    match std::env::var(LOCK_TIMEOUT_ENV) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Duration::from_secs)
+            .with_context(|| {
+                format!("Invalid {LOCK_TIMEOUT_ENV} value `{value}`, expected seconds")
+            }),
+        Err(_) => Ok(DEFAULT_LOCK_TIMEOUT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("state").join("suiup.lock");
+
+        let lock = FileLock::acquire_with_timeout(&path, "testing", Duration::ZERO).unwrap();
+        let err = FileLock::acquire_with_timeout(&path, "testing", Duration::ZERO).unwrap_err();
+        assert!(err.to_string().contains("Timed out"));
+
+        assert!(FileLock::try_acquire(&path).unwrap().is_none());
+
+        drop(lock);
+        assert!(FileLock::acquire_with_timeout(&path, "testing", Duration::ZERO).is_ok());
+    }
+}
@@ -0,0 +1,476 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A mirror of binary releases in a local folder, and the HTTP server that shares it. Each
+//! repository gets a folder with the layout of the manifest release source:
+//!
+//! ```text
+//! <mirror>/MystenLabs/sui/releases.json
+//! <mirror>/MystenLabs/sui/testnet-v1.44.2/sui-testnet-v1.44.2-ubuntu-x86_64.tgz
+//! ```
+//!
+//! The server answers the release endpoints of the GitHub API from the manifests, so clients
+//! use it by setting `SUIUP_GITHUB_API_URL`, and serves the files of the mirror as they are.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Error, bail};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::fs_utils::{read_json_file, write_json_file};
+use crate::release_source::{MANIFEST_FILE, Manifest};
+
+/// The folder of a repository in the mirror
+pub fn repository_dir(mirror: &Path, repository: &str) -> PathBuf {
+    repository
+        .split('/')
+        .fold(mirror.to_path_buf(), |dir, part| dir.join(part))
+}
+
+/// Reads the manifest of a repository folder, which is empty when nothing was mirrored yet
+pub fn read_manifest(repository_dir: &Path) -> Result<Manifest, Error> {
+    let path = repository_dir.join(MANIFEST_FILE);
+    if path.exists() {
+        read_json_file(&path)
+    } else {
+        Ok(Manifest::default())
+    }
+}
+
+pub fn write_manifest(repository_dir: &Path, manifest: &Manifest) -> Result<(), Error> {
+    write_json_file(&repository_dir.join(MANIFEST_FILE), manifest)
+}
+
+/// Serves the mirror in `dir` until the process is stopped
+pub async fn serve(dir: &Path, listener: TcpListener) -> Result<(), Error> {
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .context("Cannot accept a connection")?;
+        let dir = dir.to_path_buf();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(&dir, stream).await {
+                eprintln!("Warning: {e:#}");
+            }
+        });
+    }
+}
+
+/// The parts of an HTTP request the mirror looks at
+#[derive(Debug, Default)]
+struct Request {
+    method: String,
+    path: String,
+    host: Option<String>,
+    if_none_match: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Response {
+    Json { body: String, etag: String },
+    File(PathBuf),
+    NotModified,
+    NotFound,
+    MethodNotAllowed,
+}
+
+/// Largest request line and headers the mirror reads
+const MAX_HEAD_SIZE: u64 = 8192;
+/// Most headers the mirror reads
+const MAX_HEADERS: usize = 64;
+/// How long a client has to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn handle_connection(dir: &Path, stream: TcpStream) -> Result<(), Error> {
+    let peer = stream
+        .peer_addr()
+        .map(|addr| addr.to_string())
+        .unwrap_or_default();
+    let local = stream
+        .local_addr()
+        .context("Cannot read the server address")?;
+    let mut reader = BufReader::new(stream);
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut reader))
+        .await
+        .with_context(|| format!("{peer} did not send a request in time"))??;
+
+    let base_url = format!(
+        "http://{}",
+        request.host.clone().unwrap_or_else(|| local.to_string())
+    );
+    let response = route(dir, &base_url, &request)?;
+    let stream = reader.get_mut();
+    let status = match response {
+        Response::Json { body, etag } => {
+            write_head(
+                stream,
+                "200 OK",
+                "application/json",
+                body.len() as u64,
+                Some(&etag),
+            )
+            .await?;
+            stream.write_all(body.as_bytes()).await?;
+            "200"
+        }
+        Response::File(path) => {
+            let mut file = tokio::fs::File::open(&path)
+                .await
+                .with_context(|| format!("Cannot open {}", path.display()))?;
+            let length = file.metadata().await?.len();
+            write_head(stream, "200 OK", "application/octet-stream", length, None).await?;
+            tokio::io::copy(&mut file, stream).await?;
+            "200"
+        }
+        Response::NotModified => {
+            write_head(stream, "304 Not Modified", "application/json", 0, None).await?;
+            "304"
+        }
+        Response::NotFound => {
+            let body = r#"{"message":"Not Found"}"#;
+            write_head(
+                stream,
+                "404 Not Found",
+                "application/json",
+                body.len() as u64,
+                None,
+            )
+            .await?;
+            stream.write_all(body.as_bytes()).await?;
+            "404"
+        }
+        Response::MethodNotAllowed => {
+            write_head(stream, "405 Method Not Allowed", "text/plain", 0, None).await?;
+            "405"
+        }
+    };
+    stream.flush().await?;
+    println!("{peer} {} {} {status}", request.method, request.path);
+    Ok(())
+}
+
+async fn write_head(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    length: u64,
+    etag: Option<&str>,
+) -> Result<(), Error> {
+    let mut head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {length}\r\nConnection: close\r\n"
+    );
+    if let Some(etag) = etag {
+        head.push_str(&format!("ETag: {etag}\r\n"));
+    }
+    head.push_str("\r\n");
+    stream
+        .write_all(head.as_bytes())
+        .await
+        .context("Cannot write the response")
+}
+
+/// Reads the request line and the headers, at most `MAX_HEAD_SIZE` bytes and `MAX_HEADERS`
+/// headers
+async fn read_request(reader: &mut BufReader<TcpStream>) -> Result<Request, Error> {
+    let mut head = reader.take(MAX_HEAD_SIZE);
+    let mut request = Request::default();
+    let mut line = String::new();
+    head.read_line(&mut line)
+        .await
+        .context("Cannot read the request")?;
+    let mut parts = line.split_whitespace();
+    request.method = parts.next().unwrap_or_default().to_string();
+    request.path = parts.next().unwrap_or_default().to_string();
+    let mut headers = 0;
+    loop {
+        line.clear();
+        if head
+            .read_line(&mut line)
+            .await
+            .context("Cannot read the request headers")?
+            == 0
+        {
+            if head.limit() == 0 {
+                bail!("The request headers are larger than {MAX_HEAD_SIZE} bytes");
+            }
+            break;
+        }
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            break;
+        };
+        headers += 1;
+        if headers > MAX_HEADERS {
+            bail!("The request has more than {MAX_HEADERS} headers");
+        }
+        match name.trim().to_ascii_lowercase().as_str() {
+            "host" => request.host = Some(value.trim().to_string()),
+            "if-none-match" => request.if_none_match = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+    Ok(request)
+}
+
+/// Answers a request. The release endpoints of the GitHub API are answered from the manifest of
+/// the repository, with asset URLs under `base_url`; any other path is a file of the mirror.
+fn route(dir: &Path, base_url: &str, request: &Request) -> Result<Response, Error> {
+    if request.method != "GET" {
+        return Ok(Response::MethodNotAllowed);
+    }
+    let path = request.path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path
+        .trim_start_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if segments
+        .iter()
+        .any(|segment| *segment == "." || *segment == ".." || segment.contains('\\'))
+    {
+        return Ok(Response::NotFound);
+    }
+
+    match segments.as_slice() {
+        [] => json_response(
+            &serde_json::json!({
+                "releases_url": format!("{base_url}/repos/{{owner}}/{{repo}}/releases")
+            }),
+            request,
+        ),
+        ["repos", owner, repo, "releases", rest @ ..] => {
+            let repository_dir = dir.join(owner).join(repo);
+            if !repository_dir.join(MANIFEST_FILE).exists() {
+                return Ok(Response::NotFound);
+            }
+            let releases = read_manifest(&repository_dir)?
+                .releases(|path| format!("{base_url}/{owner}/{repo}/{path}"));
+            let release = match rest {
+                [] => return json_response(&releases, request),
+                ["latest"] => releases
+                    .iter()
+                    .find(|release| release.is_installable(false)),
+                ["tags", tag] => releases.iter().find(|release| release.tag_name == *tag),
+                _ => None,
+            };
+            match release {
+                Some(release) => json_response(release, request),
+                None => Ok(Response::NotFound),
+            }
+        }
+        _ => {
+            // Partial downloads and download locks of a running sync are not mirror files
+            if segments
+                .last()
+                .is_some_and(|name| name.ends_with(".part") || name.ends_with(".lock"))
+            {
+                return Ok(Response::NotFound);
+            }
+            let file = segments
+                .iter()
+                .fold(dir.to_path_buf(), |path, segment| path.join(segment));
+            // Symbolic links are only followed to files inside the mirror
+            let (Ok(file), Ok(dir)) = (file.canonicalize(), dir.canonicalize()) else {
+                return Ok(Response::NotFound);
+            };
+            Ok(if file.starts_with(&dir) && file.is_file() {
+                Response::File(file)
+            } else {
+                Response::NotFound
+            })
+        }
+    }
+}
+
+fn json_response<T: serde::Serialize>(value: &T, request: &Request) -> Result<Response, Error> {
+    let body = serde_json::to_string(value)?;
+    let etag = etag(&body);
+    if request.if_none_match.as_deref() == Some(etag.as_str()) {
+        return Ok(Response::NotModified);
+    }
+    Ok(Response::Json { body, etag })
+}
+
+fn etag(body: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(body.as_bytes()));
+    format!("\"{}\"", &digest[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Asset, Release};
+
+    fn get(path: &str) -> Request {
+        Request {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn mirror() -> tempfile::TempDir {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repository = repository_dir(dir.path(), "MystenLabs/mvr");
+        let mut manifest = Manifest::default();
+        for tag in ["v0.0.9", "v0.0.10"] {
+            std::fs::create_dir_all(repository.join(tag)).unwrap();
+            std::fs::write(repository.join(tag).join("mvr-ubuntu-x86_64"), tag).unwrap();
+            let release = Release {
+                tag_name: tag.to_string(),
+                published_at: Some("2025-03-04T17:21:08Z".to_string()),
+                assets: vec![Asset {
+                    name: "mvr-ubuntu-x86_64".to_string(),
+                    size: Some(tag.len() as u64),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            manifest.add_assets(&release, &release.assets);
+        }
+        write_manifest(&repository, &manifest).unwrap();
+        dir
+    }
+
+    #[test]
+    fn answers_the_github_release_endpoints() {
+        let dir = mirror();
+        let base = "http://mirror:8080";
+        let Response::Json { body, etag } = route(
+            dir.path(),
+            base,
+            &get("/repos/MystenLabs/mvr/releases?per_page=100"),
+        )
+        .unwrap() else {
+            panic!("expected a release list");
+        };
+        let releases: Vec<Release> = serde_json::from_str(&body).unwrap();
+        assert_eq!(releases[0].tag_name, "v0.0.10");
+        assert_eq!(
+            releases[0].assets[0].browser_download_url,
+            "http://mirror:8080/MystenLabs/mvr/v0.0.10/mvr-ubuntu-x86_64"
+        );
+        assert_eq!(releases[0].published_date(), Some("2025-03-04"));
+        assert_eq!(releases[0].assets[0].size, Some(7));
+
+        let cached = Request {
+            if_none_match: Some(etag),
+            ..get("/repos/MystenLabs/mvr/releases")
+        };
+        assert_eq!(
+            route(dir.path(), base, &cached).unwrap(),
+            Response::NotModified
+        );
+
+        let Response::Json { body, .. } = route(
+            dir.path(),
+            base,
+            &get("/repos/MystenLabs/mvr/releases/tags/v0.0.9"),
+        )
+        .unwrap() else {
+            panic!("expected a release");
+        };
+        let release: Release = serde_json::from_str(&body).unwrap();
+        assert_eq!(release.tag_name, "v0.0.9");
+
+        for path in [
+            "/repos/MystenLabs/mvr/releases/tags/v9.9.9",
+            "/repos/MystenLabs/sui/releases",
+        ] {
+            assert_eq!(
+                route(dir.path(), base, &get(path)).unwrap(),
+                Response::NotFound
+            );
+        }
+    }
+
+    /// Sends `request` to a connection and reads it with `read_request`
+    async fn read_sent(request: Vec<u8>) -> Result<Request, Error> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            // The server stops reading early, which can reset the connection
+            let _ = stream.write_all(&request).await;
+            stream
+        });
+        let (stream, _) = listener.accept().await.unwrap();
+        let result = read_request(&mut BufReader::new(stream)).await;
+        drop(client.await.unwrap());
+        result
+    }
+
+    #[tokio::test]
+    async fn limits_the_request_head() {
+        let request = read_sent(
+            b"GET /repos/MystenLabs/mvr/releases HTTP/1.1\r\nHost: mirror:8080\r\n\r\n".to_vec(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(request.path, "/repos/MystenLabs/mvr/releases");
+        assert_eq!(request.host.as_deref(), Some("mirror:8080"));
+
+        let mut long_line = b"GET /".to_vec();
+        long_line.resize(MAX_HEAD_SIZE as usize * 2, b'a');
+        assert!(read_sent(long_line).await.is_err());
+
+        let mut many_headers = b"GET / HTTP/1.1\r\n".to_vec();
+        for i in 0..=MAX_HEADERS {
+            many_headers.extend(format!("X-Header-{i}: 1\r\n").as_bytes());
+        }
+        many_headers.extend(b"\r\n");
+        let error = read_sent(many_headers).await.unwrap_err();
+        assert!(error.to_string().contains("headers"));
+    }
+
+    #[test]
+    fn serves_mirror_files_only() {
+        let dir = mirror();
+        let root = dir.path().canonicalize().unwrap();
+        let base = "http://mirror:8080";
+        assert_eq!(
+            route(
+                dir.path(),
+                base,
+                &get("/MystenLabs/mvr/v0.0.9/mvr-ubuntu-x86_64")
+            )
+            .unwrap(),
+            Response::File(root.join("MystenLabs/mvr/v0.0.9/mvr-ubuntu-x86_64"))
+        );
+        assert_eq!(
+            route(dir.path(), base, &get("/MystenLabs/mvr/releases.json")).unwrap(),
+            Response::File(root.join("MystenLabs/mvr/releases.json"))
+        );
+
+        let release_dir = dir.path().join("MystenLabs/mvr/v0.0.9");
+        std::fs::write(release_dir.join("mvr-macos-arm64.part"), "partial").unwrap();
+        std::fs::write(release_dir.join("mvr-macos-arm64.lock"), "").unwrap();
+        let outside = tempfile::NamedTempFile::new().unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(outside.path(), release_dir.join("outside")).unwrap();
+        for path in [
+            "/MystenLabs/../../etc/passwd",
+            "/MystenLabs/mvr",
+            "/missing",
+            "/MystenLabs/mvr/v0.0.9/mvr-macos-arm64.part",
+            "/MystenLabs/mvr/v0.0.9/mvr-macos-arm64.lock",
+            "/MystenLabs/mvr/v0.0.9/outside",
+        ] {
+            assert_eq!(
+                route(dir.path(), base, &get(path)).unwrap(),
+                Response::NotFound
+            );
+        }
+        let post = Request {
+            method: "POST".to_string(),
+            ..get("/")
+        };
+        assert_eq!(
+            route(dir.path(), base, &post).unwrap(),
+            Response::MethodNotAllowed
+        );
+    }
+}
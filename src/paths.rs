@@ -2,14 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{Context, Error};
-use std::collections::BTreeMap;
 use std::env;
-use std::fs::{File, create_dir_all};
-use std::io::Write;
+use std::fs::create_dir_all;
 use std::path::PathBuf;
 
 use crate::handlers::RELEASES_ARCHIVES_FOLDER;
-use crate::types::InstalledBinaries;
+use crate::state::migrate_state_files;
+use crate::types::{DefaultBinaries, InstalledBinaries};
 
 #[cfg(not(windows))]
 const XDG_DATA_HOME: &str = "XDG_DATA_HOME";
@@ -109,6 +108,51 @@ pub fn get_suiup_cache_dir() -> PathBuf {
     get_cache_home().join("suiup")
 }
 
+/// Binary definitions added by the user, one TOML file per binary
+pub fn user_binaries_dir() -> PathBuf {
+    get_suiup_config_dir().join("binaries")
+}
+
+/// Remote registry indexes the user subscribed to with `suiup registry add`
+pub fn remote_registries_file() -> PathBuf {
+    get_suiup_config_dir().join("registries.json")
+}
+
+/// Cached remote registry indexes and their ETags
+pub fn remote_registry_cache_dir() -> PathBuf {
+    get_suiup_cache_dir().join("registries")
+}
+
+/// Default folder of `suiup mirror`
+pub fn default_mirror_dir() -> PathBuf {
+    get_suiup_data_dir().join("mirror")
+}
+
+/// Lock file that serializes changes to the suiup state across processes
+pub fn state_lock_file() -> PathBuf {
+    get_suiup_data_dir().join("suiup.lock")
+}
+
+/// Lock file that serializes changes to the release archive cache index
+pub fn cache_lock_file() -> PathBuf {
+    get_suiup_cache_dir().join("cache.lock")
+}
+
+/// Target directories of source builds, one per repository
+pub fn build_cache_dir() -> PathBuf {
+    get_suiup_cache_dir().join("builds")
+}
+
+/// Logs of source builds
+pub fn build_logs_dir() -> PathBuf {
+    get_suiup_cache_dir().join("build_logs")
+}
+
+/// Records when each release archive in the cache was last used
+pub fn cache_index_file() -> PathBuf {
+    get_suiup_cache_dir().join("archive_index.json")
+}
+
 pub fn get_default_bin_dir() -> PathBuf {
     #[cfg(windows)]
     {
@@ -155,13 +199,7 @@ pub fn default_file_path() -> Result<PathBuf, Error> {
             })?;
         }
 
-        let mut file = File::create(&path)
-            .with_context(|| format!("Cannot create default version file {}", path.display()))?;
-        let default = BTreeMap::<String, (String, String)>::new();
-        let default_str = serde_json::to_string_pretty(&default)
-            .context("Cannot serialize default version file content")?;
-        file.write_all(default_str.as_bytes())
-            .with_context(|| format!("Cannot write default version file {}", path.display()))?;
+        DefaultBinaries::create_file(&path)?;
     }
     Ok(path)
 }
@@ -226,5 +264,10 @@ pub fn initialize() -> Result<(), Error> {
     })?;
     default_file_path()?;
     installed_binaries_file()?;
+    // Commands that read a state file that cannot be upgraded report the error themselves, this
+    // keeps commands like `doctor` working
+    if let Err(e) = migrate_state_files() {
+        eprintln!("Warning: {e:#}");
+    }
     Ok(())
 }
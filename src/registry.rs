@@ -1,10 +1,18 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+use crate::asset_pattern::{ARCH_NAMES, AssetPattern, AssetValues, OS_NAMES, validate_aliases};
+use crate::paths::user_binaries_dir;
+use crate::release_source::{ReleaseSource, ReleaseSourceConfig};
+use crate::remote_registry::{RemoteDefinitions, cached_definitions};
+
 include!(concat!(env!("OUT_DIR"), "/binary_configs.rs"));
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -34,6 +42,51 @@ pub struct BinaryConfig {
     pub nightly_toolchain: Option<String>,
     #[serde(default)]
     pub shared_repo_binary: bool,
+    /// Template of the release asset names, e.g. `{name}-{network}-{version}-{os}-{arch}.tgz`.
+    /// When empty, the asset is picked by looking for the OS and architecture in its name
+    pub asset_pattern: Option<String>,
+    /// Other binaries shipped in the same release archive, which can be installed together with
+    /// this one via `--with`
+    #[serde(default)]
+    pub archive_members: Vec<String>,
+    /// Names used in asset names for each OS, when they differ from the detected one
+    #[serde(default)]
+    pub os_aliases: BTreeMap<String, Vec<String>>,
+    /// Names used in asset names for each architecture, when they differ from the detected one
+    #[serde(default)]
+    pub arch_aliases: BTreeMap<String, Vec<String>>,
+    /// System tools and libraries needed to build the binary from source, e.g. `clang`, `cmake`
+    /// or `libpq`
+    #[serde(default)]
+    pub build_dependencies: Vec<String>,
+    /// Where the releases are published, GitHub releases of `repository` by default
+    #[serde(default)]
+    pub release_source: ReleaseSourceConfig,
+    /// Where the definition was loaded from
+    #[serde(skip)]
+    pub source: ConfigSource,
+}
+
+/// Where a binary definition comes from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Embedded in suiup at build time
+    #[default]
+    BuiltIn,
+    /// A TOML file in the user binaries folder
+    User(PathBuf),
+    /// A signed entry of a subscribed remote registry index
+    Remote(String),
+}
+
+/// A binary definition in the user binaries folder. It has the same fields as the built-in
+/// definitions, and must set `override_builtin` to replace a built-in binary of the same name.
+#[derive(Deserialize)]
+struct UserBinaryConfig {
+    #[serde(flatten)]
+    config: BinaryConfig,
+    #[serde(default)]
+    override_builtin: bool,
 }
 
 fn default_main_branch() -> String {
@@ -54,6 +107,113 @@ impl BinaryConfig {
     pub fn repo_url(&self) -> String {
         format!("https://github.com/{}", self.repository)
     }
+
+    /// The source the releases of the binary are downloaded from
+    pub fn release_source(&self, github_token: Option<String>) -> Box<dyn ReleaseSource> {
+        self.release_source.source(&self.repository, github_token)
+    }
+
+    /// Returns the network to install from. Binaries that are only released for specific
+    /// networks fall back to their default network.
+    pub fn effective_network(&self, network: &str) -> String {
+        if self.network_based
+            && !self.supported_networks.is_empty()
+            && !self.supported_networks.iter().any(|n| n == network)
+        {
+            self.default_network.clone()
+        } else {
+            network.to_string()
+        }
+    }
+
+    /// Parses a binary definition and normalizes its optional fields
+    fn parse(toml_str: &str) -> Result<Self> {
+        let mut config: BinaryConfig = toml::from_str(toml_str)?;
+        config.normalize();
+        Ok(config)
+    }
+
+    fn normalize(&mut self) {
+        normalize_optional_string(&mut self.cargo_package);
+        normalize_optional_string(&mut self.nightly_toolchain);
+        normalize_optional_string(&mut self.asset_pattern);
+    }
+
+    /// Checks that the asset pattern and the alias tables are well formed
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!(
+                "Invalid binary name `{}`, only letters, digits, `-` and `_` are allowed",
+                self.name
+            );
+        }
+        if let Some(pattern) = &self.asset_pattern {
+            AssetPattern::parse(pattern).map_err(|e| anyhow!("{}: {e}", self.name))?;
+        }
+        validate_aliases(
+            "os_aliases",
+            OS_NAMES,
+            self.os_aliases
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_slice())),
+        )
+        .map_err(|e| anyhow!("{}: {e}", self.name))?;
+        validate_aliases(
+            "arch_aliases",
+            ARCH_NAMES,
+            self.arch_aliases
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_slice())),
+        )
+        .map_err(|e| anyhow!("{}: {e}", self.name))?;
+        Ok(())
+    }
+
+    /// Returns the parsed asset pattern, if the binary has one
+    pub fn asset_pattern(&self) -> Option<AssetPattern> {
+        self.asset_pattern
+            .as_deref()
+            .and_then(|pattern| AssetPattern::parse(pattern).ok())
+    }
+
+    /// Returns the values to match the asset pattern placeholders against. The version is
+    /// matched loosely when it is not known, e.g. when looking for the latest release
+    pub fn asset_values(
+        &self,
+        network: Option<&str>,
+        version: Option<&str>,
+        os: &str,
+        arch: &str,
+    ) -> AssetValues {
+        let aliases = |table: &BTreeMap<String, Vec<String>>, key: &str| {
+            table
+                .get(key)
+                .cloned()
+                .unwrap_or_else(|| vec![key.to_string()])
+        };
+        AssetValues {
+            name: vec![self.name.clone()],
+            network: network.map(str::to_string).into_iter().collect(),
+            version: version.map(str::to_string).into_iter().collect(),
+            os: aliases(&self.os_aliases, os),
+            arch: aliases(&self.arch_aliases, arch),
+        }
+    }
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ConfigSource::BuiltIn => write!(f, "built-in"),
+            ConfigSource::User(path) => write!(f, "{}", path.display()),
+            ConfigSource::Remote(url) => write!(f, "{url}"),
+        }
+    }
 }
 
 pub struct BinaryRegistry {
@@ -63,21 +223,105 @@ pub struct BinaryRegistry {
 static REGISTRY: OnceLock<BinaryRegistry> = OnceLock::new();
 
 impl BinaryRegistry {
+    /// The built-in binaries, the ones defined in the user binaries folder and the verified
+    /// entries of the cached remote registry indexes. Problems with user and remote definitions
+    /// are printed as warnings and the definition is skipped.
     pub fn global() -> &'static BinaryRegistry {
         REGISTRY.get_or_init(|| {
-            let mut configs = Vec::new();
-            for toml_str in BINARY_CONFIGS {
-                let mut config: BinaryConfig =
-                    toml::from_str(toml_str).expect("Failed to parse embedded binary TOML config");
-                normalize_optional_string(&mut config.cargo_package);
-                normalize_optional_string(&mut config.nightly_toolchain);
-                configs.push(config);
+            let (remote, mut warnings) = cached_definitions();
+            let (registry, load_warnings) =
+                Self::load(BINARY_CONFIGS, &user_binaries_dir(), &remote);
+            warnings.extend(load_warnings);
+            for warning in warnings {
+                eprintln!("Warning: {warning}");
             }
-            configs.sort_by(|a, b| a.name.cmp(&b.name));
-            BinaryRegistry { configs }
+            registry
         })
     }
 
+    /// Loads the built-in definitions and the `*.toml` files in `user_dir`, in file name order.
+    ///
+    /// A user definition with the name of a built-in binary is skipped, unless it sets
+    /// `override_builtin = true` to replace the built-in one. When several user files define
+    /// the same binary, the first one is used. Remote definitions come last and never replace
+    /// a built-in, user or earlier remote definition. Returns the warnings for the skipped
+    /// definitions.
+    pub fn load(
+        builtin: &[&str],
+        user_dir: &Path,
+        remote: &[RemoteDefinitions],
+    ) -> (Self, Vec<String>) {
+        let mut configs: Vec<BinaryConfig> = builtin
+            .iter()
+            .map(|toml_str| {
+                BinaryConfig::parse(toml_str).expect("Failed to parse embedded binary TOML config")
+            })
+            .collect();
+        let mut warnings = vec![];
+
+        let mut files: Vec<PathBuf> = std::fs::read_dir(user_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+
+        for path in files {
+            let user = match read_user_config(&path) {
+                Ok(user) => user,
+                Err(e) => {
+                    warnings.push(format!("Skipping {}: {e:#}", path.display()));
+                    continue;
+                }
+            };
+            let name = user.config.name.clone();
+            match configs.iter().position(|c| c.name == name) {
+                Some(i) => match &configs[i].source {
+                    ConfigSource::BuiltIn if user.override_builtin => configs[i] = user.config,
+                    ConfigSource::BuiltIn => warnings.push(format!(
+                        "Skipping {}: `{name}` is a built-in binary. Set `override_builtin = true` to replace it",
+                        path.display()
+                    )),
+                    ConfigSource::User(first) => warnings.push(format!(
+                        "Skipping {}: `{name}` is already defined in {}",
+                        path.display(),
+                        first.display()
+                    )),
+                    ConfigSource::Remote(_) => unreachable!("remote definitions are loaded last"),
+                },
+                None => configs.push(user.config),
+            }
+        }
+
+        for registry in remote {
+            for definition in &registry.definitions {
+                let config = match read_remote_config(definition, &registry.url) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        warnings.push(format!(
+                            "Skipping a definition from {}: {e:#}",
+                            registry.url
+                        ));
+                        continue;
+                    }
+                };
+                match configs.iter().find(|c| c.name == config.name) {
+                    Some(existing) => warnings.push(format!(
+                        "Skipping `{}` from {}: it is already defined by {}",
+                        config.name, registry.url, existing.source
+                    )),
+                    None => configs.push(config),
+                }
+            }
+        }
+
+        configs.sort_by(|a, b| a.name.cmp(&b.name));
+        (BinaryRegistry { configs }, warnings)
+    }
+
     pub fn get(&self, name: &str) -> Option<&BinaryConfig> {
         self.configs.iter().find(|c| c.name == name)
     }
@@ -93,6 +337,36 @@ impl BinaryRegistry {
     pub fn contains(&self, name: &str) -> bool {
         self.configs.iter().any(|c| c.name == name)
     }
+
+    /// The binary whose release archive ships `name` as one of its `archive_members`, when
+    /// `name` is not a binary of its own
+    pub fn member_of(&self, name: &str) -> Option<&BinaryConfig> {
+        if self.contains(name) {
+            return None;
+        }
+        self.configs
+            .iter()
+            .find(|c| c.archive_members.iter().any(|member| member == name))
+    }
+}
+
+/// Reads and validates a user binary definition
+fn read_user_config(path: &Path) -> Result<UserBinaryConfig> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read file {}", path.display()))?;
+    let mut user: UserBinaryConfig = toml::from_str(&content)?;
+    user.config.normalize();
+    user.config.validate()?;
+    user.config.source = ConfigSource::User(path.to_path_buf());
+    Ok(user)
+}
+
+/// Parses and validates a verified definition of a remote registry
+fn read_remote_config(definition: &str, url: &str) -> Result<BinaryConfig> {
+    let mut config = BinaryConfig::parse(definition)?;
+    config.validate()?;
+    config.source = ConfigSource::Remote(url.to_string());
+    Ok(config)
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -164,6 +438,10 @@ mod tests {
             "cargo_package",
             "nightly_toolchain",
             "shared_repo_binary",
+            "asset_pattern",
+            "archive_members",
+            "build_dependencies",
+            "release_source",
         ];
 
         for toml_str in BINARY_CONFIGS {
@@ -250,6 +528,59 @@ mod tests {
         assert_eq!(config.default_network, "mainnet");
     }
 
+    #[test]
+    fn effective_network_falls_back_to_default() {
+        let config = BinaryRegistry::global().get("site-builder").unwrap();
+        assert_eq!(config.effective_network("testnet"), "mainnet");
+        assert_eq!(config.effective_network("mainnet"), "mainnet");
+
+        let config = BinaryRegistry::global().get("sui").unwrap();
+        assert_eq!(config.effective_network("devnet"), "devnet");
+    }
+
+    #[test]
+    fn all_configs_are_valid() {
+        for config in BinaryRegistry::global().all() {
+            config.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn asset_patterns_match_release_assets() {
+        let sui_node = BinaryRegistry::global().get("sui-node").unwrap();
+        let pattern = sui_node.asset_pattern().unwrap();
+        let values = sui_node.asset_values(Some("testnet"), None, "macos", "arm64");
+        assert!(pattern.matches("sui-testnet-v1.44.2-macos-arm64.tgz", &values));
+        assert!(!pattern.matches("sui-testnet-v1.44.2-macos-x86_64.tgz", &values));
+
+        let mvr = BinaryRegistry::global().get("mvr").unwrap();
+        let pattern = mvr.asset_pattern().unwrap();
+        let values = mvr.asset_values(None, None, "ubuntu", "aarch64");
+        assert!(pattern.matches("mvr-linux-arm64", &values));
+        assert!(!pattern.matches("mvr-macos-arm64", &values));
+    }
+
+    #[test]
+    fn invalid_alias_table_rejected() {
+        let mut config = BinaryRegistry::global().get("mvr").unwrap().clone();
+        config
+            .os_aliases
+            .insert("linux".to_string(), vec!["linux".to_string()]);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn sui_archive_members() {
+        let config = BinaryRegistry::global().get("sui").unwrap();
+        assert!(config.archive_members.contains(&"sui-tool".to_string()));
+        assert!(config.archive_members.contains(&"sui-faucet".to_string()));
+
+        let registry = BinaryRegistry::global();
+        assert_eq!(registry.member_of("sui-tool").unwrap().name, "sui");
+        assert!(registry.member_of("sui").is_none());
+        assert!(registry.member_of("nonexistent").is_none());
+    }
+
     #[test]
     fn invalid_binary_name_rejected() {
         assert!(BinaryName::new("nonexistent").is_err());
@@ -274,4 +605,98 @@ mod tests {
         sorted.sort();
         assert_eq!(names, sorted);
     }
+
+    fn user_config(name: &str, description: &str) -> String {
+        format!(
+            r#"name = "{name}"
+description = "{description}"
+repository = "example/{name}"
+installation_type = "standalone"
+asset_pattern = "{{name}}-{{os}}-{{arch}}*"
+"#
+        )
+    }
+
+    #[test]
+    fn loads_user_definitions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.toml"), user_config("my-tool", "First")).unwrap();
+        std::fs::write(dir.path().join("b.toml"), user_config("my-tool", "Second")).unwrap();
+        std::fs::write(dir.path().join("c.toml"), user_config("sui", "Not sui")).unwrap();
+        std::fs::write(dir.path().join("d.toml"), "name = \"broken\"").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a definition").unwrap();
+
+        let (registry, warnings) = BinaryRegistry::load(BINARY_CONFIGS, dir.path(), &[]);
+
+        let tool = registry.get("my-tool").unwrap();
+        assert_eq!(tool.description, "First");
+        assert_eq!(tool.main_branch, "main");
+        assert_eq!(tool.source, ConfigSource::User(dir.path().join("a.toml")));
+        assert_eq!(registry.get("sui").unwrap().source, ConfigSource::BuiltIn);
+        assert!(!registry.contains("broken"));
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+        assert!(warnings[0].contains("b.toml") && warnings[0].contains("already defined"));
+        assert!(warnings[1].contains("c.toml") && warnings[1].contains("override_builtin"));
+        assert!(warnings[2].contains("d.toml"));
+    }
+
+    #[test]
+    fn user_definitions_can_override_builtin() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = format!(
+            "{}override_builtin = true\n",
+            user_config("sui", "Forked sui")
+        );
+        std::fs::write(dir.path().join("sui.toml"), config).unwrap();
+        std::fs::write(
+            dir.path().join("bad-name.toml"),
+            user_config("bad@name", "Bad"),
+        )
+        .unwrap();
+
+        let (registry, warnings) = BinaryRegistry::load(BINARY_CONFIGS, dir.path(), &[]);
+
+        let sui = registry.get("sui").unwrap();
+        assert_eq!(sui.description, "Forked sui");
+        assert_eq!(sui.repository, "example/sui");
+        assert!(matches!(sui.source, ConfigSource::User(_)));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("Invalid binary name"));
+    }
+
+    #[test]
+    fn remote_definitions_never_replace_local_ones() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("mine.toml"), user_config("mine", "Local")).unwrap();
+        let url = "https://example.com/index.json".to_string();
+        let remote = [
+            RemoteDefinitions {
+                url: url.clone(),
+                definitions: vec![
+                    user_config("new-tool", "Remote"),
+                    user_config("sui", "Remote sui"),
+                    user_config("mine", "Remote mine"),
+                    "name = \"incomplete\"".to_string(),
+                ],
+            },
+            RemoteDefinitions {
+                url: "https://example.org/index.json".to_string(),
+                definitions: vec![user_config("new-tool", "Second remote")],
+            },
+        ];
+
+        let (registry, warnings) = BinaryRegistry::load(BINARY_CONFIGS, dir.path(), &remote);
+
+        let tool = registry.get("new-tool").unwrap();
+        assert_eq!(tool.description, "Remote");
+        assert_eq!(tool.source, ConfigSource::Remote(url.clone()));
+        assert_eq!(registry.get("sui").unwrap().source, ConfigSource::BuiltIn);
+        assert_eq!(registry.get("mine").unwrap().description, "Local");
+        assert!(!registry.contains("incomplete"));
+        assert_eq!(warnings.len(), 4, "{warnings:?}");
+        assert!(warnings[0].contains("`sui`") && warnings[0].contains("built-in"));
+        assert!(warnings[1].contains("`mine`") && warnings[1].contains("mine.toml"));
+        assert!(warnings[2].contains("Skipping a definition"));
+        assert!(warnings[3].contains("`new-tool`") && warnings[3].contains(&url));
+    }
 }
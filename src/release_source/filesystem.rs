@@ -0,0 +1,168 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, bail};
+use futures_util::future::BoxFuture;
+
+use super::{MANIFEST_FILE, Manifest, ReleaseSource, releases_from_paths};
+use crate::fs_utils::read_json_file;
+use crate::handlers::download::partial_download_file;
+use crate::lock::lock_download;
+use crate::types::{Asset, Release};
+
+/// A local folder with a folder of assets per release tag, e.g. a mirror on a shared drive.
+/// The releases are listed in its `releases.json` manifest when it has one, otherwise the tag
+/// folders are listed from the newest to the oldest version.
+pub struct FilesystemSource {
+    root: PathBuf,
+}
+
+impl FilesystemSource {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    fn list_releases(&self) -> Result<Vec<Release>, Error> {
+        if !self.root.is_dir() {
+            bail!("Release folder {} does not exist", self.root.display());
+        }
+        let resolve = |path: &str| self.root.join(path).to_string_lossy().to_string();
+        let manifest = self.root.join(MANIFEST_FILE);
+        if manifest.exists() {
+            return Ok(read_json_file::<Manifest>(&manifest)?.releases(resolve));
+        }
+
+        let mut paths = vec![];
+        for tag in read_dir(&self.root)? {
+            if !tag.is_dir() {
+                continue;
+            }
+            for asset in read_dir(&tag)? {
+                if !asset.is_file() {
+                    continue;
+                }
+                if let (Some(tag), Some(name)) = (tag.file_name(), asset.file_name()) {
+                    paths.push(format!(
+                        "{}/{}",
+                        tag.to_string_lossy(),
+                        name.to_string_lossy()
+                    ));
+                }
+            }
+        }
+        Ok(releases_from_paths(
+            paths.iter().map(String::as_str),
+            resolve,
+        ))
+    }
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    std::fs::read_dir(dir)
+        .with_context(|| format!("Cannot read directory {}", dir.display()))?
+        .map(|entry| {
+            entry
+                .map(|entry| entry.path())
+                .with_context(|| format!("Cannot read an entry in directory {}", dir.display()))
+        })
+        .collect()
+}
+
+impl ReleaseSource for FilesystemSource {
+    fn describe(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn releases(&self) -> BoxFuture<'_, Result<Vec<Release>, Error>> {
+        Box::pin(async move { self.list_releases() })
+    }
+
+    fn download<'a>(
+        &'a self,
+        asset: &'a Asset,
+        destination: &'a Path,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let source = Path::new(&asset.browser_download_url);
+            let _lock = lock_download(destination)?;
+            // Copied through a temporary file, so the destination is only ever complete
+            let partial = partial_download_file(destination);
+            if let Err(e) = std::fs::copy(source, &partial) {
+                let _ = std::fs::remove_file(&partial);
+                return Err(e).with_context(|| {
+                    format!(
+                        "Cannot copy {} to {}",
+                        source.display(),
+                        destination.display()
+                    )
+                });
+            }
+            std::fs::rename(&partial, destination)
+                .with_context(|| format!("Cannot move copy into {}", destination.display()))?;
+            println!("Copied {name} from {}", source.display());
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn lists_tag_folders_and_copies_assets() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for (tag, asset) in [
+            ("v0.9.0", "tool-linux-x86_64"),
+            ("v0.10.0", "tool-linux-x86_64"),
+            ("v0.10.0", "tool-macos-arm64"),
+        ] {
+            std::fs::create_dir_all(dir.path().join(tag)).unwrap();
+            std::fs::write(dir.path().join(tag).join(asset), tag).unwrap();
+        }
+        std::fs::write(dir.path().join("README"), "not a release").unwrap();
+
+        let source = FilesystemSource::new(dir.path());
+        let releases = source.releases().await.unwrap();
+        let tags: Vec<&str> = releases.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v0.10.0", "v0.9.0"]);
+        assert_eq!(releases[0].assets.len(), 2);
+
+        let destination = dir.path().join("downloaded");
+        source
+            .download(&releases[1].assets[0], &destination, "tool")
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&destination).unwrap(), "v0.9.0");
+        assert!(!partial_download_file(&destination).exists());
+    }
+
+    #[tokio::test]
+    async fn uses_the_manifest_when_there_is_one() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("v0.1.0")).unwrap();
+        std::fs::write(
+            dir.path().join(MANIFEST_FILE),
+            r#"{"releases": [{"tag_name": "v0.1.0", "assets": [{"name": "tool"}]}]}"#,
+        )
+        .unwrap();
+
+        let releases = FilesystemSource::new(dir.path()).releases().await.unwrap();
+        assert_eq!(releases.len(), 1);
+        assert_eq!(
+            Path::new(&releases[0].assets[0].browser_download_url),
+            dir.path().join("v0.1.0/tool")
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_folder_is_an_error() {
+        let source = FilesystemSource::new(Path::new("/nonexistent/suiup/mirror"));
+        assert!(source.releases().await.is_err());
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{Context, Error, anyhow, bail};
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+
+use super::{ReleaseSource, releases_from_paths};
+use crate::types::Release;
+
+/// A public Google Cloud Storage bucket with the assets of each release in a `<prefix><tag>/`
+/// folder, like the bucket walrus assets are served from
+pub struct GcsSource {
+    bucket: String,
+    prefix: String,
+}
+
+/// A page of the objects listing of the GCS JSON API
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectList {
+    #[serde(default)]
+    items: Vec<Object>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Object {
+    name: String,
+}
+
+impl GcsSource {
+    pub fn new(bucket: &str, prefix: &str) -> Self {
+        let prefix = prefix.trim_matches('/');
+        Self {
+            bucket: bucket.to_string(),
+            prefix: if prefix.is_empty() {
+                String::new()
+            } else {
+                format!("{prefix}/")
+            },
+        }
+    }
+
+    fn object_url(&self, object: &str) -> String {
+        format!("https://storage.googleapis.com/{}/{object}", self.bucket)
+    }
+
+    /// Groups the object names under the prefix into releases
+    fn releases_from_objects(&self, objects: &[String]) -> Vec<Release> {
+        releases_from_paths(
+            objects
+                .iter()
+                .filter_map(|name| name.strip_prefix(&self.prefix)),
+            |path| self.object_url(&format!("{}{path}", self.prefix)),
+        )
+    }
+}
+
+impl ReleaseSource for GcsSource {
+    fn describe(&self) -> String {
+        format!("gs://{}/{}", self.bucket, self.prefix)
+    }
+
+    fn releases(&self) -> BoxFuture<'_, Result<Vec<Release>, Error>> {
+        Box::pin(async move {
+            let client = reqwest::Client::new();
+            let url = format!(
+                "https://storage.googleapis.com/storage/v1/b/{}/o",
+                self.bucket
+            );
+            let mut objects = vec![];
+            let mut page_token: Option<String> = None;
+            loop {
+                let mut query = vec![
+                    ("prefix", self.prefix.as_str()),
+                    ("fields", "items(name),nextPageToken"),
+                ];
+                if let Some(token) = &page_token {
+                    query.push(("pageToken", token));
+                }
+                let request_url = reqwest::Url::parse_with_params(&url, &query)
+                    .with_context(|| format!("Invalid GCS URL {url}"))?;
+                let request = client.get(request_url).header("User-Agent", "suiup");
+                let response = request
+                    .send()
+                    .await
+                    .with_context(|| format!("Failed to send request to {url}"))?;
+                let status = response.status();
+                if !status.is_success() {
+                    bail!("GCS request failed with status {status} for {url}");
+                }
+                let body = response
+                    .text()
+                    .await
+                    .with_context(|| format!("Cannot read GCS objects from {url}"))?;
+                let page: ObjectList = serde_json::from_str(&body)
+                    .map_err(|e| anyhow!("Failed to deserialize GCS objects from {url}: {e}"))?;
+                objects.extend(page.items.into_iter().map(|object| object.name));
+                match page.next_page_token {
+                    Some(token) => page_token = Some(token),
+                    None => break,
+                }
+            }
+            Ok(self.releases_from_objects(&objects))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_objects_under_the_prefix() {
+        let source = GcsSource::new("mysten-walrus-binaries", "/walrus/");
+        let releases = source.releases_from_objects(&[
+            "walrus/testnet-v1.20.0/walrus-testnet-v1.20.0-ubuntu-x86_64.tgz".to_string(),
+            "walrus/testnet-v1.21.0/walrus-testnet-v1.21.0-ubuntu-x86_64.tgz".to_string(),
+            "other/testnet-v9.0.0/walrus.tgz".to_string(),
+        ]);
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].tag_name, "testnet-v1.21.0");
+        assert_eq!(
+            releases[0].assets[0].browser_download_url,
+            "https://storage.googleapis.com/mysten-walrus-binaries/walrus/testnet-v1.21.0/walrus-testnet-v1.21.0-ubuntu-x86_64.tgz"
+        );
+        assert_eq!(source.describe(), "gs://mysten-walrus-binaries/walrus/");
+    }
+}
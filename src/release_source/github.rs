@@ -0,0 +1,111 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::Path;
+
+use anyhow::{Context, Error, anyhow, bail};
+use futures_util::future::BoxFuture;
+use reqwest::StatusCode;
+
+use super::ReleaseSource;
+use crate::handlers::download::download_file;
+use crate::handlers::release::{github_api_url, release_list, sends_github_token};
+use crate::types::{Asset, Release};
+
+/// GitHub releases of a repository. The release list is cached with its ETag.
+pub struct GitHubSource {
+    repository: String,
+    github_token: Option<String>,
+}
+
+impl GitHubSource {
+    pub fn new(repository: &str, github_token: Option<String>) -> Self {
+        Self {
+            repository: repository.to_string(),
+            github_token,
+        }
+    }
+
+    /// Fetches a single release from the GitHub API, e.g. `releases/latest`
+    async fn get_release(&self, path: &str) -> Result<Option<Release>, Error> {
+        let url = format!(
+            "{}/repos/{}/releases/{path}",
+            github_api_url(),
+            self.repository
+        );
+        let mut request = reqwest::Client::new()
+            .get(&url)
+            .header("User-Agent", "suiup");
+        if let Some(token) = &self.github_token
+            && sends_github_token(&url)
+        {
+            request = request.header("Authorization", format!("token {token}"));
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to {url}"))?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let body = response
+                .text()
+                .await
+                .unwrap_or_else(|e| format!("Unable to read response body: {e}"));
+            bail!("GitHub API request failed with status {status} for {url}: {body}");
+        }
+        let body = response
+            .text()
+            .await
+            .with_context(|| format!("Cannot read GitHub release response body from {url}"))?;
+        serde_json::from_str(&body).map(Some).map_err(|e| {
+            anyhow!(
+                "Failed to deserialize GitHub release response from {url}: {e}\nResponse body:\n{body}"
+            )
+        })
+    }
+}
+
+impl ReleaseSource for GitHubSource {
+    fn describe(&self) -> String {
+        format!("GitHub {}", self.repository)
+    }
+
+    fn releases(&self) -> BoxFuture<'_, Result<Vec<Release>, Error>> {
+        Box::pin(async move {
+            Ok(release_list(&self.repository, self.github_token.clone())
+                .await?
+                .0)
+        })
+    }
+
+    /// The release list only has the most recent releases, so older tags are looked up directly
+    fn release_by_tag<'a>(&'a self, tag: &'a str) -> BoxFuture<'a, Result<Option<Release>, Error>> {
+        Box::pin(async move { self.get_release(&format!("tags/{tag}")).await })
+    }
+
+    fn latest_release(&self) -> BoxFuture<'_, Result<Option<Release>, Error>> {
+        Box::pin(async move { self.get_release("latest").await })
+    }
+
+    fn download<'a>(
+        &'a self,
+        asset: &'a Asset,
+        destination: &'a Path,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            download_file(
+                &asset.browser_download_url,
+                destination,
+                name,
+                self.github_token.clone(),
+            )
+            .await?;
+            Ok(())
+        })
+    }
+}
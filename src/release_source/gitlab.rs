@@ -0,0 +1,148 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{Context, Error, anyhow, bail};
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+
+use super::ReleaseSource;
+use crate::types::{Asset, Release};
+
+/// Releases of a GitLab project, with their assets attached as release links
+pub struct GitLabSource {
+    url: String,
+    project: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabRelease {
+    tag_name: String,
+    released_at: Option<String>,
+    /// Set for releases with a release date in the future
+    #[serde(default)]
+    upcoming_release: bool,
+    description: Option<String>,
+    assets: GitLabAssets,
+}
+
+#[derive(Deserialize)]
+struct GitLabAssets {
+    #[serde(default)]
+    links: Vec<GitLabLink>,
+}
+
+#[derive(Deserialize)]
+struct GitLabLink {
+    name: String,
+    url: String,
+    direct_asset_url: Option<String>,
+}
+
+impl GitLabSource {
+    pub fn new(url: &str, project: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            project: project.to_string(),
+        }
+    }
+
+    fn releases_url(&self) -> String {
+        format!(
+            "{}/api/v4/projects/{}/releases",
+            self.url,
+            self.project.replace('/', "%2F")
+        )
+    }
+}
+
+/// Converts the releases of the GitLab API, which are listed newest first
+fn parse_releases(body: &str) -> Result<Vec<Release>, Error> {
+    let releases: Vec<GitLabRelease> = serde_json::from_str(body)?;
+    Ok(releases
+        .into_iter()
+        .map(|release| Release {
+            tag_name: release.tag_name,
+            published_at: release.released_at,
+            prerelease: release.upcoming_release,
+            draft: false,
+            body: release.description,
+            assets: release
+                .assets
+                .links
+                .into_iter()
+                .map(|link| Asset {
+                    name: link.name,
+                    browser_download_url: link.direct_asset_url.unwrap_or(link.url),
+                    ..Default::default()
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+impl ReleaseSource for GitLabSource {
+    fn describe(&self) -> String {
+        format!("GitLab {} at {}", self.project, self.url)
+    }
+
+    fn releases(&self) -> BoxFuture<'_, Result<Vec<Release>, Error>> {
+        Box::pin(async move {
+            let url = self.releases_url();
+            let response = reqwest::Client::new()
+                .get(&url)
+                .header("User-Agent", "suiup")
+                .send()
+                .await
+                .with_context(|| format!("Failed to send request to {url}"))?;
+            let status = response.status();
+            if !status.is_success() {
+                bail!("GitLab API request failed with status {status} for {url}");
+            }
+            let body = response
+                .text()
+                .await
+                .with_context(|| format!("Cannot read GitLab releases from {url}"))?;
+            parse_releases(&body)
+                .map_err(|e| anyhow!("Failed to deserialize GitLab releases from {url}: {e}"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_the_project_path() {
+        let source = GitLabSource::new("https://gitlab.example.com/", "group/sub/tool");
+        assert_eq!(
+            source.releases_url(),
+            "https://gitlab.example.com/api/v4/projects/group%2Fsub%2Ftool/releases"
+        );
+    }
+
+    #[test]
+    fn converts_release_links_to_assets() {
+        let releases = parse_releases(
+            r#"[{"tag_name": "v1.0.0", "released_at": "2025-03-04T17:21:08.000Z",
+                "upcoming_release": true, "description": "Notes", "assets": {"links": [
+                {"name": "tool-linux-x86_64", "url": "https://example.com/l",
+                 "direct_asset_url": "https://gitlab.com/d"},
+                {"name": "tool-macos-arm64", "url": "https://example.com/m"}
+            ]}}]"#,
+        )
+        .unwrap();
+        assert_eq!(releases[0].tag_name, "v1.0.0");
+        assert_eq!(releases[0].published_date(), Some("2025-03-04"));
+        assert!(releases[0].prerelease);
+        assert_eq!(releases[0].body.as_deref(), Some("Notes"));
+        assert_eq!(
+            releases[0].assets[0].browser_download_url,
+            "https://gitlab.com/d"
+        );
+        assert_eq!(
+            releases[0].assets[1].browser_download_url,
+            "https://example.com/m"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{Context, Error, anyhow, bail};
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use super::{ReleaseSource, compare_tags};
+use crate::types::{Asset, Release};
+
+/// Name of the manifest file at the root of a manifest or filesystem source
+pub const MANIFEST_FILE: &str = "releases.json";
+
+/// The list of releases of a manifest or filesystem source, newest first
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Manifest {
+    pub releases: Vec<ManifestRelease>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestRelease {
+    pub tag_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub prerelease: bool,
+    /// The release notes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    pub assets: Vec<ManifestAsset>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestAsset {
+    pub name: String,
+    /// Where to download the asset. By default it is `<tag_name>/<name>` next to the manifest;
+    /// relative URLs are resolved against the manifest location.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Digest of the asset, e.g. `sha256:<hex>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+impl Manifest {
+    /// The releases, with asset locations resolved with `resolve`
+    pub fn releases(&self, resolve: impl Fn(&str) -> String) -> Vec<Release> {
+        self.releases
+            .iter()
+            .map(|release| Release {
+                tag_name: release.tag_name.clone(),
+                published_at: release.published_at.clone(),
+                prerelease: release.prerelease,
+                draft: false,
+                body: release.body.clone(),
+                assets: release
+                    .assets
+                    .iter()
+                    .map(|asset| Asset {
+                        name: asset.name.clone(),
+                        browser_download_url: match &asset.url {
+                            Some(url) if is_absolute_url(url) => url.clone(),
+                            Some(url) => resolve(url),
+                            None => resolve(&format!("{}/{}", release.tag_name, asset.name)),
+                        },
+                        size: asset.size,
+                        digest: asset.digest.clone(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Adds assets of a release, which is added with its metadata when it is not listed yet,
+    /// and keeps the releases sorted from the newest to the oldest version
+    pub fn add_assets<'a>(
+        &mut self,
+        release: &Release,
+        assets: impl IntoIterator<Item = &'a Asset>,
+    ) {
+        let index = match self
+            .releases
+            .iter()
+            .position(|listed| listed.tag_name == release.tag_name)
+        {
+            Some(index) => index,
+            None => {
+                self.releases.push(ManifestRelease {
+                    tag_name: release.tag_name.clone(),
+                    published_at: release.published_at.clone(),
+                    prerelease: release.prerelease,
+                    body: release.body.clone(),
+                    assets: vec![],
+                });
+                self.releases.len() - 1
+            }
+        };
+        let listed = &mut self.releases[index];
+        for asset in assets {
+            if !listed.assets.iter().any(|a| a.name == asset.name) {
+                listed.assets.push(ManifestAsset {
+                    name: asset.name.clone(),
+                    url: None,
+                    size: asset.size,
+                    digest: asset.digest.clone(),
+                });
+            }
+        }
+        listed.assets.sort_by(|a, b| a.name.cmp(&b.name));
+        self.releases
+            .sort_by(|a, b| compare_tags(&b.tag_name, &a.tag_name));
+    }
+}
+
+fn is_absolute_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// A `releases.json` manifest served over HTTP, e.g. by `suiup mirror serve`
+pub struct ManifestSource {
+    base_url: String,
+}
+
+impl ManifestSource {
+    /// `url` is the folder that holds the manifest, or the manifest itself
+    pub fn new(url: &str) -> Self {
+        let url = url.trim_end_matches('/');
+        let base_url = url
+            .strip_suffix(&format!("/{MANIFEST_FILE}"))
+            .unwrap_or(url);
+        Self {
+            base_url: base_url.to_string(),
+        }
+    }
+
+    fn resolve(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+}
+
+impl ReleaseSource for ManifestSource {
+    fn describe(&self) -> String {
+        format!("manifest at {}", self.base_url)
+    }
+
+    fn releases(&self) -> BoxFuture<'_, Result<Vec<Release>, Error>> {
+        Box::pin(async move {
+            let url = self.resolve(MANIFEST_FILE);
+            let response = reqwest::Client::new()
+                .get(&url)
+                .header("User-Agent", "suiup")
+                .send()
+                .await
+                .with_context(|| format!("Failed to send request to {url}"))?;
+            let status = response.status();
+            if !status.is_success() {
+                bail!("Release manifest request failed with status {status} for {url}");
+            }
+            let body = response
+                .text()
+                .await
+                .with_context(|| format!("Cannot read release manifest from {url}"))?;
+            let manifest: Manifest = serde_json::from_str(&body)
+                .map_err(|e| anyhow!("Invalid release manifest at {url}: {e}"))?;
+            Ok(manifest.releases(|path| self.resolve(path)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_asset_urls_against_the_manifest() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"releases": [{"tag_name": "v1.0.0", "assets": [
+                {"name": "tool-linux"},
+                {"name": "tool-macos", "url": "other/tool-macos"},
+                {"name": "tool-windows", "url": "https://cdn.example.com/tool.exe"}
+            ]}]}"#,
+        )
+        .unwrap();
+        let source = ManifestSource::new("https://example.com/tool/releases.json");
+        let releases = manifest.releases(|path| source.resolve(path));
+        let urls: Vec<&str> = releases[0]
+            .assets
+            .iter()
+            .map(|a| a.browser_download_url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/tool/v1.0.0/tool-linux",
+                "https://example.com/tool/other/tool-macos",
+                "https://cdn.example.com/tool.exe",
+            ]
+        );
+    }
+
+    #[test]
+    fn adds_assets_to_new_and_existing_releases() {
+        let release = |tag: &str, names: &[&str]| Release {
+            tag_name: tag.to_string(),
+            prerelease: tag.contains("rc"),
+            assets: names
+                .iter()
+                .map(|name| Asset {
+                    name: name.to_string(),
+                    size: Some(42),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let mut manifest = Manifest::default();
+        for release in [
+            release("v1.9.0", &["tool-linux"]),
+            release("v1.10.0-rc", &["tool-macos"]),
+            release("v1.10.0-rc", &["tool-linux", "tool-macos"]),
+        ] {
+            manifest.add_assets(&release, &release.assets);
+        }
+        let releases = manifest.releases(|path| path.to_string());
+        let tags: Vec<&str> = releases.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v1.10.0-rc", "v1.9.0"]);
+        assert!(releases[0].prerelease);
+        let names: Vec<&str> = releases[0].assets.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["tool-linux", "tool-macos"]);
+        assert_eq!(releases[0].assets[0].size, Some(42));
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Where the releases of a binary are published. Each binary definition picks a source in its
+//! `release_source` table, and GitHub releases of its `repository` are used when it has none:
+//!
+//! ```toml
+//! release_source = { type = "github" }
+//! release_source = { type = "gitlab", project = "group/project", url = "https://gitlab.com" }
+//! release_source = { type = "manifest", url = "https://example.com/releases" }
+//! release_source = { type = "filesystem", path = "/mnt/mirror/sui" }
+//! release_source = { type = "gcs", bucket = "my-bucket", prefix = "sui/" }
+//! ```
+//!
+//! The manifest and filesystem sources share one layout: a `releases.json` manifest next to a
+//! folder per release tag that holds its assets.
+
+mod filesystem;
+mod gcs;
+mod github;
+mod gitlab;
+mod manifest;
+
+pub use filesystem::FilesystemSource;
+pub use gcs::GcsSource;
+pub use github::GitHubSource;
+pub use gitlab::GitLabSource;
+pub use manifest::{MANIFEST_FILE, Manifest, ManifestAsset, ManifestRelease, ManifestSource};
+
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+
+use crate::handlers::download::download_file;
+use crate::types::{Asset, Release};
+
+/// A place binaries are released to
+pub trait ReleaseSource: Send + Sync {
+    /// Where the releases come from, e.g. `GitHub MystenLabs/sui`
+    fn describe(&self) -> String;
+
+    /// The releases, newest first
+    fn releases(&self) -> BoxFuture<'_, Result<Vec<Release>, Error>>;
+
+    /// The release with the given tag, which might not be in the list of releases
+    fn release_by_tag<'a>(&'a self, tag: &'a str) -> BoxFuture<'a, Result<Option<Release>, Error>> {
+        Box::pin(async move {
+            Ok(self
+                .releases()
+                .await?
+                .into_iter()
+                .find(|release| release.tag_name == tag))
+        })
+    }
+
+    /// The most recent release that is neither a draft nor a pre-release
+    fn latest_release(&self) -> BoxFuture<'_, Result<Option<Release>, Error>> {
+        Box::pin(async move {
+            Ok(self
+                .releases()
+                .await?
+                .into_iter()
+                .find(|release| release.is_installable(false)))
+        })
+    }
+
+    /// Downloads an asset of a release to `destination`. `name` is used in progress messages.
+    fn download<'a>(
+        &'a self,
+        asset: &'a Asset,
+        destination: &'a Path,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            download_file(&asset.browser_download_url, destination, name, None).await?;
+            Ok(())
+        })
+    }
+}
+
+/// Downloads an asset with [`ReleaseSource::download`] and checks it against the size and digest
+/// reported by the source. A download that does not match is removed.
+pub async fn download_asset(
+    source: &dyn ReleaseSource,
+    asset: &Asset,
+    destination: &Path,
+    name: &str,
+) -> Result<(), Error> {
+    source.download(asset, destination, name).await?;
+    if let Err(e) = asset.verify(destination) {
+        let _ = std::fs::remove_file(destination);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// The `release_source` setting of a binary definition
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ReleaseSourceConfig {
+    /// GitHub releases of the binary repository
+    #[default]
+    #[serde(rename = "github")]
+    GitHub,
+    /// Releases of a GitLab project
+    #[serde(rename = "gitlab")]
+    GitLab {
+        /// GitLab instance
+        #[serde(default = "default_gitlab_url")]
+        url: String,
+        /// Project path, the binary repository by default
+        project: Option<String>,
+    },
+    /// A `releases.json` manifest served over HTTP, with the assets of each release in a
+    /// folder named after its tag
+    Manifest { url: String },
+    /// A local folder with the layout of the manifest source
+    Filesystem { path: PathBuf },
+    /// A Google Cloud Storage bucket with a folder per release tag under `prefix`
+    Gcs {
+        bucket: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+fn default_gitlab_url() -> String {
+    "https://gitlab.com".to_string()
+}
+
+impl ReleaseSourceConfig {
+    /// The source for a binary published from `repository`
+    pub fn source(&self, repository: &str, github_token: Option<String>) -> Box<dyn ReleaseSource> {
+        match self {
+            ReleaseSourceConfig::GitHub => Box::new(GitHubSource::new(repository, github_token)),
+            ReleaseSourceConfig::GitLab { url, project } => Box::new(GitLabSource::new(
+                url,
+                project.as_deref().unwrap_or(repository),
+            )),
+            ReleaseSourceConfig::Manifest { url } => Box::new(ManifestSource::new(url)),
+            ReleaseSourceConfig::Filesystem { path } => Box::new(FilesystemSource::new(path)),
+            ReleaseSourceConfig::Gcs { bucket, prefix } => Box::new(GcsSource::new(bucket, prefix)),
+        }
+    }
+}
+
+/// Sorts releases from the newest to the oldest version in their tag. Tags without a version
+/// come last, in reverse name order.
+pub fn sort_newest_first(releases: &mut [Release]) {
+    releases.sort_by(|a, b| compare_tags(&b.tag_name, &a.tag_name));
+}
+
+fn compare_tags(a: &str, b: &str) -> Ordering {
+    let version = |tag: &str| {
+        tag.rsplit(['-', '_', '/'])
+            .find_map(|part| semver::Version::parse(part.strip_prefix('v').unwrap_or(part)).ok())
+    };
+    match (version(a), version(b)) {
+        (Some(va), Some(vb)) => va.cmp(&vb).then_with(|| a.cmp(b)),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => a.cmp(b),
+    }
+}
+
+/// Groups `<tag>/<asset>` paths into releases, newest first. `url` gives the download URL of
+/// each path; paths outside of a tag folder are ignored.
+fn releases_from_paths<'a>(
+    paths: impl IntoIterator<Item = &'a str>,
+    url: impl Fn(&str) -> String,
+) -> Vec<Release> {
+    let mut releases: Vec<Release> = vec![];
+    for path in paths {
+        let Some((tag, name)) = path.split_once('/') else {
+            continue;
+        };
+        if tag.is_empty() || name.is_empty() || name.contains('/') {
+            continue;
+        }
+        let asset = Asset {
+            name: name.to_string(),
+            browser_download_url: url(path),
+            ..Default::default()
+        };
+        match releases.iter_mut().find(|release| release.tag_name == tag) {
+            Some(release) => release.assets.push(asset),
+            None => releases.push(Release {
+                tag_name: tag.to_string(),
+                assets: vec![asset],
+                ..Default::default()
+            }),
+        }
+    }
+    for release in &mut releases {
+        release.assets.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    sort_newest_first(&mut releases);
+    releases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_source_settings() {
+        let parse = |toml_str: &str| -> ReleaseSourceConfig {
+            #[derive(Deserialize)]
+            struct Config {
+                #[serde(default)]
+                release_source: ReleaseSourceConfig,
+            }
+            toml::from_str::<Config>(toml_str).unwrap().release_source
+        };
+        assert_eq!(parse(""), ReleaseSourceConfig::GitHub);
+        assert_eq!(
+            parse("release_source = { type = \"gitlab\", project = \"a/b\" }"),
+            ReleaseSourceConfig::GitLab {
+                url: "https://gitlab.com".to_string(),
+                project: Some("a/b".to_string())
+            }
+        );
+        assert_eq!(
+            parse("[release_source]\ntype = \"gcs\"\nbucket = \"b\""),
+            ReleaseSourceConfig::Gcs {
+                bucket: "b".to_string(),
+                prefix: String::new()
+            }
+        );
+        assert!(
+            toml::from_str::<ReleaseSourceConfig>("type = \"filesystem\"\nurl = \"x\"").is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn download_asset_checks_size_and_digest() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("tool");
+        std::fs::write(&file, "tool").unwrap();
+        let source = FilesystemSource::new(dir.path());
+        let destination = dir.path().join("downloaded");
+        let asset = Asset {
+            browser_download_url: file.display().to_string(),
+            name: "tool".to_string(),
+            size: Some(4),
+            digest: Some(format!(
+                "sha256:{}",
+                crate::fs_utils::sha256_file(&file).unwrap()
+            )),
+        };
+        download_asset(&source, &asset, &destination, "tool")
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&destination).unwrap(), "tool");
+
+        let wrong_digest = Asset {
+            digest: Some(format!("sha256:{}", "0".repeat(64))),
+            ..asset.clone()
+        };
+        let err = download_asset(&source, &wrong_digest, &destination, "tool")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch for tool"));
+        assert!(!destination.exists());
+
+        let wrong_size = Asset {
+            size: Some(5),
+            ..asset
+        };
+        let err = download_asset(&source, &wrong_size, &destination, "tool")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("has 4 bytes"));
+        assert!(!destination.exists());
+    }
+
+    #[test]
+    fn groups_paths_into_releases_newest_first() {
+        let releases = releases_from_paths(
+            [
+                "testnet-v1.9.0/sui-testnet-v1.9.0-ubuntu-x86_64.tgz",
+                "testnet-v1.10.0/sui-testnet-v1.10.0-ubuntu-x86_64.tgz",
+                "testnet-v1.10.0/sui-testnet-v1.10.0-macos-arm64.tgz",
+                "releases.json",
+                "nested/too/deep.tgz",
+            ],
+            |path| format!("https://example.com/{path}"),
+        );
+        let tags: Vec<&str> = releases.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["testnet-v1.10.0", "testnet-v1.9.0"]);
+        assert_eq!(releases[0].assets.len(), 2);
+        assert_eq!(
+            releases[0].assets[0].browser_download_url,
+            "https://example.com/testnet-v1.10.0/sui-testnet-v1.10.0-macos-arm64.tgz"
+        );
+    }
+
+    #[test]
+    fn tags_without_versions_sort_last() {
+        assert_eq!(compare_tags("v0.2.0", "nightly"), Ordering::Greater);
+        assert_eq!(compare_tags("mvr-v0.0.9", "mvr-v0.0.10"), Ordering::Less);
+    }
+}
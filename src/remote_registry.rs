@@ -0,0 +1,370 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Remote registry indexes: binary definitions published at a URL that users subscribe to with
+//! `suiup registry add`, so that new tools can be installed without a new suiup release.
+//!
+//! An index is a JSON document with one entry per binary:
+//!
+//! ```json
+//! { "binaries": [ { "definition": "<TOML definition>", "signature": "<base64>" } ] }
+//! ```
+//!
+//! Each signature is an Ed25519 signature of the definition bytes. The public key is pinned
+//! when subscribing, and entries whose signature does not match it are never loaded. Indexes
+//! are cached with their ETag like release lists, and refreshed with `suiup registry update`.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Error, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use ring::signature::{ED25519, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::fs_utils::{read_json_file, write_file_atomic, write_json_file};
+use crate::paths::{remote_registries_file, remote_registry_cache_dir};
+
+/// Time to wait for a registry index
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Length of an Ed25519 public key in bytes
+const PUBLIC_KEY_LEN: usize = 32;
+
+/// A remote registry the user subscribed to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub url: String,
+    /// Base64 encoded Ed25519 public key that signs the index entries
+    pub public_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Subscriptions {
+    registries: Vec<Subscription>,
+}
+
+/// The content of a registry index
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RegistryIndex {
+    pub binaries: Vec<SignedDefinition>,
+}
+
+/// A binary definition in TOML, signed with the registry key
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedDefinition {
+    pub definition: String,
+    /// Base64 encoded Ed25519 signature of `definition`
+    pub signature: String,
+}
+
+/// The verified definitions of a registry
+#[derive(Debug, Clone, Default)]
+pub struct RemoteDefinitions {
+    pub url: String,
+    pub definitions: Vec<String>,
+}
+
+/// The result of fetching a registry index
+pub enum FetchedIndex {
+    /// The index did not change since it was cached
+    NotModified,
+    Updated {
+        index: RegistryIndex,
+        body: String,
+        etag: Option<String>,
+    },
+}
+
+/// The subscribed registries, in the order they were added
+pub fn read_subscriptions() -> Result<Vec<Subscription>, Error> {
+    let path = remote_registries_file();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(read_json_file::<Subscriptions>(&path)?.registries)
+}
+
+pub fn save_subscriptions(registries: &[Subscription]) -> Result<(), Error> {
+    let path = remote_registries_file();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Cannot create directory {}", parent.display()))?;
+    }
+    write_json_file(
+        &path,
+        &Subscriptions {
+            registries: registries.to_vec(),
+        },
+    )
+}
+
+/// Decodes a base64 encoded Ed25519 public key
+pub fn decode_public_key(public_key: &str) -> Result<Vec<u8>, Error> {
+    let key = BASE64
+        .decode(public_key.trim())
+        .map_err(|e| anyhow!("Invalid public key, expected base64: {e}"))?;
+    if key.len() != PUBLIC_KEY_LEN {
+        bail!(
+            "Invalid public key, expected {PUBLIC_KEY_LEN} bytes of Ed25519 key but got {}",
+            key.len()
+        );
+    }
+    Ok(key)
+}
+
+/// Checks the signature of an index entry against the registry public key
+pub fn verify_definition(public_key: &[u8], entry: &SignedDefinition) -> Result<(), Error> {
+    let signature = BASE64
+        .decode(entry.signature.trim())
+        .map_err(|e| anyhow!("invalid signature encoding: {e}"))?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(entry.definition.as_bytes(), &signature)
+        .map_err(|_| anyhow!("signature does not match the registry public key"))
+}
+
+/// The definitions of the index with a valid signature, and a warning for each other entry
+pub fn verified_definitions(
+    url: &str,
+    public_key: &[u8],
+    index: &RegistryIndex,
+) -> (Vec<String>, Vec<String>) {
+    let mut definitions = vec![];
+    let mut warnings = vec![];
+    for (i, entry) in index.binaries.iter().enumerate() {
+        match verify_definition(public_key, entry) {
+            Ok(()) => definitions.push(entry.definition.clone()),
+            Err(e) => warnings.push(format!(
+                "Skipping {} from {url}: {e}",
+                entry_label(i, &entry.definition)
+            )),
+        }
+    }
+    (definitions, warnings)
+}
+
+/// The binary name of a definition, if it has one
+pub fn definition_name(definition: &str) -> Option<String> {
+    toml::from_str::<toml::Table>(definition)
+        .ok()?
+        .get("name")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Names an index entry in messages by its binary name when it has one
+fn entry_label(i: usize, definition: &str) -> String {
+    definition_name(definition)
+        .map(|name| format!("`{name}`"))
+        .unwrap_or_else(|| format!("entry {}", i + 1))
+}
+
+/// Returns the paths of the cached index and of its ETag for the given registry
+pub fn index_cache_files(url: &str) -> (PathBuf, PathBuf) {
+    let digest = format!("{:x}", Sha256::digest(url.as_bytes()));
+    let dir = remote_registry_cache_dir();
+    (
+        dir.join(format!("{}.json", &digest[..16])),
+        dir.join(format!("{}.etag", &digest[..16])),
+    )
+}
+
+/// The cached index of a registry, if it was fetched before
+pub fn cached_index(url: &str) -> Result<Option<RegistryIndex>, Error> {
+    let (index_file, _) = index_cache_files(url);
+    if !index_file.exists() {
+        return Ok(None);
+    }
+    read_json_file(&index_file).map(Some)
+}
+
+/// The ETag of the cached index of a registry
+fn cached_etag(url: &str) -> Option<String> {
+    let (_, etag_file) = index_cache_files(url);
+    std::fs::read_to_string(etag_file)
+        .ok()
+        .filter(|etag| !etag.is_empty())
+}
+
+/// Fetches a registry index. When `use_cache` is set and the index is cached, the request
+/// carries its ETag and the server can answer that it did not change.
+pub async fn fetch_index(url: &str, use_cache: bool) -> Result<FetchedIndex, Error> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .context("Cannot create HTTP client")?;
+    let mut request = client.get(url).header("User-Agent", "suiup");
+    if use_cache
+        && index_cache_files(url).0.exists()
+        && let Some(etag) = cached_etag(url)
+    {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to send request to {url}"))?;
+    let status = response.status();
+    if status == StatusCode::NOT_MODIFIED && use_cache {
+        return Ok(FetchedIndex::NotModified);
+    }
+    if !status.is_success() {
+        bail!("Registry index request to {url} failed with status {status}");
+    }
+
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let body = response
+        .text()
+        .await
+        .with_context(|| format!("Cannot read the registry index from {url}"))?;
+    let index =
+        serde_json::from_str(&body).map_err(|e| anyhow!("Invalid registry index at {url}: {e}"))?;
+    Ok(FetchedIndex::Updated { index, body, etag })
+}
+
+/// Caches a fetched index and its ETag
+pub fn save_index(url: &str, body: &str, etag: Option<&str>) -> Result<(), Error> {
+    let dir = remote_registry_cache_dir();
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Cannot create directory {}", dir.display()))?;
+    let (index_file, etag_file) = index_cache_files(url);
+    write_file_atomic(&index_file, body.as_bytes())?;
+    match etag {
+        Some(etag) => write_file_atomic(&etag_file, etag.as_bytes())?,
+        None if etag_file.exists() => std::fs::remove_file(&etag_file)
+            .with_context(|| format!("Cannot remove file {}", etag_file.display()))?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// Removes the cached index of a registry
+pub fn remove_cached_index(url: &str) -> Result<(), Error> {
+    let (index_file, etag_file) = index_cache_files(url);
+    for file in [index_file, etag_file] {
+        if file.exists() {
+            std::fs::remove_file(&file)
+                .with_context(|| format!("Cannot remove file {}", file.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// The verified definitions of every subscribed registry, read from the cached indexes.
+/// Returns warnings for the registries and entries that were skipped.
+pub fn cached_definitions() -> (Vec<RemoteDefinitions>, Vec<String>) {
+    let mut warnings = vec![];
+    let subscriptions = read_subscriptions().unwrap_or_else(|e| {
+        warnings.push(format!("Ignoring remote registries: {e:#}"));
+        vec![]
+    });
+
+    let mut registries = vec![];
+    for subscription in subscriptions {
+        let url = subscription.url;
+        let public_key = match decode_public_key(&subscription.public_key) {
+            Ok(key) => key,
+            Err(e) => {
+                warnings.push(format!("Skipping registry {url}: {e:#}"));
+                continue;
+            }
+        };
+        let index = match cached_index(&url) {
+            Ok(Some(index)) => index,
+            Ok(None) => {
+                warnings.push(format!(
+                    "The index of registry {url} is not cached, run `suiup registry update`"
+                ));
+                continue;
+            }
+            Err(e) => {
+                warnings.push(format!("Skipping registry {url}: {e:#}"));
+                continue;
+            }
+        };
+        let (definitions, skipped) = verified_definitions(&url, &public_key, &index);
+        warnings.extend(skipped);
+        registries.push(RemoteDefinitions { url, definitions });
+    }
+    (registries, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    fn key_pair() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn sign(key_pair: &Ed25519KeyPair, definition: &str) -> SignedDefinition {
+        SignedDefinition {
+            definition: definition.to_string(),
+            signature: BASE64.encode(key_pair.sign(definition.as_bytes())),
+        }
+    }
+
+    #[test]
+    fn decodes_public_keys() {
+        let key_pair = key_pair();
+        let encoded = BASE64.encode(key_pair.public_key());
+        assert_eq!(
+            decode_public_key(&encoded).unwrap(),
+            key_pair.public_key().as_ref()
+        );
+        assert!(decode_public_key("not base64!").is_err());
+        assert!(decode_public_key(&BASE64.encode([1u8; 16])).is_err());
+    }
+
+    #[test]
+    fn only_entries_signed_with_the_registry_key_are_verified() {
+        let registry_key = key_pair();
+        let other_key = key_pair();
+        let mut tampered = sign(&registry_key, "name = \"tool\"\n");
+        tampered.definition = "name = \"evil\"\n".to_string();
+        let index = RegistryIndex {
+            binaries: vec![
+                sign(&registry_key, "name = \"good\"\n"),
+                sign(&other_key, "name = \"forged\"\n"),
+                tampered,
+                SignedDefinition {
+                    definition: "not toml".to_string(),
+                    signature: "%%%".to_string(),
+                },
+            ],
+        };
+
+        let (definitions, warnings) = verified_definitions(
+            "https://example.com/index.json",
+            registry_key.public_key().as_ref(),
+            &index,
+        );
+        assert_eq!(definitions, vec!["name = \"good\"\n"]);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("`forged`"));
+        assert!(warnings[1].contains("`evil`"));
+        assert!(warnings[2].contains("entry 4"));
+    }
+
+    #[test]
+    fn cache_files_depend_on_the_url() {
+        let (index, etag) = index_cache_files("https://example.com/a.json");
+        let (other, _) = index_cache_files("https://example.com/b.json");
+        assert_ne!(index, other);
+        assert_eq!(index.file_stem(), etag.file_stem());
+        assert_eq!(index.extension().unwrap(), "json");
+        assert_eq!(etag.extension().unwrap(), "etag");
+    }
+}
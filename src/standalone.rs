@@ -2,128 +2,125 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    handlers::{
-        download::{detect_os_arch, download_file},
-        release::ensure_version_prefix,
-    },
-    paths::{binaries_dir, get_suiup_cache_dir},
+    handlers::download::ensure_installable,
+    handlers::release::ensure_version_prefix,
+    paths::binaries_dir,
+    registry::{BinaryConfig, BinaryRegistry},
+    release_source::{ReleaseSource, ReleaseSourceConfig, download_asset},
+    transaction::{Transaction, fail_point},
+    types::{Asset, Release},
 };
 use anyhow::{Context, Error, anyhow};
-use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
-
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct StandaloneRelease {
-    pub tag_name: String,
-    pub assets: Vec<StandaloneAsset>,
+use std::path::{Path, PathBuf};
+
+/// A version of a standalone binary that was downloaded by `StandaloneInstaller::download_version`
+pub struct DownloadedVersion {
+    pub version: String,
+    /// The staging folder and the binary in it, unless the version was already downloaded
+    staged: Option<(tempfile::TempDir, PathBuf)>,
+    destination: PathBuf,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct StandaloneAsset {
-    pub name: String,
-    pub browser_download_url: String,
+impl DownloadedVersion {
+    /// Moves the downloaded binary into the binary folder as part of `tx`
+    pub fn install(&self, tx: &mut Transaction) -> Result<(), Error> {
+        if let Some((_, staged_path)) = &self.staged {
+            tx.install_file(staged_path, &self.destination)?;
+            fail_point("install")?;
+        }
+        Ok(())
+    }
 }
 
 pub struct StandaloneInstaller {
-    releases: Vec<StandaloneRelease>,
+    releases: Vec<Release>,
     repo_slug: String,
     github_token: Option<String>,
+    release_source: ReleaseSourceConfig,
+    include_prereleases: bool,
 }
 
 impl StandaloneInstaller {
+    /// An installer for the GitHub releases of `repo_slug`
     pub fn new(repo_slug: &str, github_token: Option<String>) -> Self {
         Self {
             releases: Vec::new(),
             repo_slug: repo_slug.to_string(),
             github_token,
+            release_source: ReleaseSourceConfig::GitHub,
+            include_prereleases: false,
         }
     }
 
-    pub async fn get_releases(&mut self) -> Result<(), Error> {
-        let client = reqwest::Client::new();
-        let url = format!("https://api.github.com/repos/{}/releases", self.repo_slug);
-
-        if !self.releases.is_empty() {
-            return Ok(());
+    /// An installer for the releases of a binary, from its release source
+    pub fn for_binary(config: &BinaryConfig, github_token: Option<String>) -> Self {
+        Self {
+            release_source: config.release_source.clone(),
+            ..Self::new(&config.repository, github_token)
         }
+    }
 
-        let mut request = client.get(&url).header("User-Agent", "suiup");
-        if let Some(token) = &self.github_token {
-            request = request.header("Authorization", format!("token {}", token));
-        }
+    /// Resolves versions to pre-releases too, which are skipped by default
+    pub fn with_prereleases(mut self, include_prereleases: bool) -> Self {
+        self.include_prereleases = include_prereleases;
+        self
+    }
 
-        let response = match request.send().await {
-            Ok(response) => response,
-            Err(err) => {
-                if let Some(cached) = load_cached_standalone_releases(&self.repo_slug)? {
-                    self.releases = cached;
-                    return Ok(());
-                }
-                return Err(err).with_context(|| format!("Failed to send request to {url}"));
-            }
-        };
+    fn source(&self) -> Box<dyn ReleaseSource> {
+        self.release_source
+            .source(&self.repo_slug, self.github_token.clone())
+    }
 
-        let status = response.status();
-        if !status.is_success() {
-            if let Some(cached) = load_cached_standalone_releases(&self.repo_slug)? {
-                self.releases = cached;
-                return Ok(());
-            }
-            let body = response
-                .text()
-                .await
-                .unwrap_or_else(|e| format!("Unable to read response body: {e}"));
-            return Err(anyhow!(
-                "GitHub API request failed with status {} for {}: {}",
-                status,
-                url,
-                body
-            ));
+    pub async fn get_releases(&mut self) -> Result<(), Error> {
+        if !self.releases.is_empty() {
+            return Ok(());
         }
-
-        let releases: Vec<StandaloneRelease> =
-            parse_json_response(response, &url, "GitHub releases list").await?;
-        save_cached_standalone_releases(&self.repo_slug, &releases)?;
-        self.releases = releases;
+        self.releases = self
+            .source()
+            .releases()
+            .await?
+            .into_iter()
+            .filter(|release| !release.draft)
+            .collect();
         Ok(())
     }
 
-    pub fn get_latest_release(&self) -> Result<&StandaloneRelease, Error> {
+    /// The releases loaded by `get_releases`, newest first and without drafts
+    pub fn releases(&self) -> &[Release] {
+        &self.releases
+    }
+
+    pub fn get_latest_release(&self) -> Result<&Release, Error> {
         println!("Downloading release list");
-        let releases = &self.releases;
-        releases
-            .first()
+        self.latest_release()
+    }
+
+    fn latest_release(&self) -> Result<&Release, Error> {
+        self.releases
+            .iter()
+            .find(|release| release.is_installable(self.include_prereleases))
             .ok_or_else(|| anyhow!("No releases found for {}", self.repo_slug))
     }
 
     /// Returns the latest version string (e.g. "v0.6.5") without printing or downloading.
     /// Caller must call `get_releases()` first.
     pub fn latest_version(&self) -> Result<String, Error> {
-        let release = self
-            .releases
-            .first()
-            .ok_or_else(|| anyhow!("No releases found for {}", self.repo_slug))?;
+        let release = self.latest_release()?;
         standalone_tag_version(&release.tag_name)
             .ok_or_else(|| anyhow!("Cannot extract version from tag: {}", release.tag_name))
     }
 
-    /// Download the CLI binary, if it does not exist in the binary folder.
+    /// Download the CLI binary for the given OS and architecture, if it does not exist in the
+    /// binary folder. The binary is downloaded to a staging folder without taking the state
+    /// lock; `DownloadedVersion::install` then moves it into the binary folder.
     pub async fn download_version(
         &mut self,
         version: Option<String>,
         binary_name_str: &str,
-    ) -> Result<String, Error> {
-        let explicit_version = version.as_deref().map(normalize_standalone_version);
-        let version = if let Some(version) = explicit_version.clone() {
-            version
-        } else {
-            if self.releases.is_empty() {
-                self.get_releases().await?;
-            }
-            let latest_release = self.get_latest_release()?.tag_name.clone();
-            println!("No version specified. Downloading latest release: {latest_release}");
-            standalone_tag_version(&latest_release).unwrap_or(latest_release)
-        };
+        os: &str,
+        arch: &str,
+    ) -> Result<DownloadedVersion, Error> {
+        let (explicit_version, version) = self.resolve_version(version).await?;
 
         let cache_folder = binaries_dir().join("standalone");
         if !cache_folder.exists() {
@@ -142,69 +139,167 @@ impl StandaloneInstaller {
                 "Binary {}-{version} already installed. Use `suiup default set standalone {version}` to set the default version to the desired one",
                 binary_name_str
             );
-            return Ok(version);
+            return Ok(DownloadedVersion {
+                version,
+                staged: None,
+                destination: standalone_binary_path,
+            });
         }
 
+        let staging_dir = tempfile::tempdir_in(&cache_folder)
+            .context("Cannot create temporary directory for download")?;
+        let staged_path = staging_dir.path().join(binary_name_str);
+        self.download_asset(
+            explicit_version.as_deref(),
+            &version,
+            binary_name_str,
+            os,
+            arch,
+            &staged_path,
+        )
+        .await?;
+        fail_point("extract")?;
+        Ok(DownloadedVersion {
+            version,
+            staged: Some((staging_dir, staged_path)),
+            destination: standalone_binary_path,
+        })
+    }
+
+    /// Download the CLI binary for the given OS and architecture to `destination`, without
+    /// registering it anywhere.
+    pub async fn fetch_version(
+        &mut self,
+        version: Option<String>,
+        binary_name_str: &str,
+        os: &str,
+        arch: &str,
+        destination: &Path,
+    ) -> Result<String, Error> {
+        let (explicit_version, version) = self.resolve_version(version).await?;
+        self.download_asset(
+            explicit_version.as_deref(),
+            &version,
+            binary_name_str,
+            os,
+            arch,
+            destination,
+        )
+        .await?;
+        Ok(version)
+    }
+
+    /// Returns the normalized requested version, if any, and the version to download
+    async fn resolve_version(
+        &mut self,
+        version: Option<String>,
+    ) -> Result<(Option<String>, String), Error> {
+        let explicit_version = version.as_deref().map(normalize_standalone_version);
+        let version = if let Some(version) = explicit_version.clone() {
+            version
+        } else {
+            if self.releases.is_empty() {
+                self.get_releases().await?;
+            }
+            let latest_release = self.get_latest_release()?.tag_name.clone();
+            println!("No version specified. Downloading latest release: {latest_release}");
+            standalone_tag_version(&latest_release).unwrap_or(latest_release)
+        };
+        Ok((explicit_version, version))
+    }
+
+    async fn download_asset(
+        &mut self,
+        explicit_version: Option<&str>,
+        version: &str,
+        binary_name_str: &str,
+        os: &str,
+        arch: &str,
+        destination: &Path,
+    ) -> Result<(), Error> {
         if self.releases.is_empty() {
             self.get_releases().await?;
         }
 
-        let release = self
-            .releases
-            .iter()
-            .find(|release| match explicit_version.as_deref() {
-                Some(version) => standalone_tag_matches_version(&release.tag_name, version),
-                None => true,
-            })
-            .ok_or_else(|| anyhow!("Version {} not found", version))?;
-
-        let (os, arch) = detect_os_arch()?;
-        let asset_names = standalone_asset_name_candidates(binary_name_str, &os, &arch);
-        let asset = asset_names
-            .iter()
-            .find_map(|asset_name| {
-                release
-                    .assets
+        let release = match explicit_version {
+            Some(version) => {
+                let release = self
+                    .releases
                     .iter()
-                    .find(|asset| standalone_asset_name_matches(&asset.name, asset_name))
-            })
-            .ok_or_else(|| {
+                    .find(|release| standalone_tag_matches_version(&release.tag_name, version))
+                    .ok_or_else(|| anyhow!("Version {} not found", version))?;
+                ensure_installable(release, &release.tag_name, self.include_prereleases)?;
+                release
+            }
+            None => self.latest_release()?,
+        };
+
+        let asset = find_standalone_asset(release, binary_name_str, version, os, arch).ok_or_else(
+            || {
                 anyhow!(
                     "No compatible binary found for your system: {}-{}",
                     os,
                     arch
                 )
-            })?;
+            },
+        )?;
 
-        download_file(
-            &asset.browser_download_url,
-            &standalone_binary_path,
+        download_asset(
+            self.source().as_ref(),
+            asset,
+            destination,
             format!("{}-{version}", binary_name_str).as_str(),
-            self.github_token.clone(),
         )
         .await?;
 
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = std::fs::metadata(&standalone_binary_path)
+            let mut perms = std::fs::metadata(destination)
                 .with_context(|| {
-                    format!(
-                        "Cannot read metadata for binary {}",
-                        standalone_binary_path.display()
-                    )
+                    format!("Cannot read metadata for binary {}", destination.display())
                 })?
                 .permissions();
             perms.set_mode(0o755);
-            std::fs::set_permissions(&standalone_binary_path, perms).with_context(|| {
+            std::fs::set_permissions(destination, perms).with_context(|| {
                 format!(
                     "Cannot set executable permissions on {}",
-                    standalone_binary_path.display()
+                    destination.display()
                 )
             })?;
         }
 
-        Ok(version)
+        Ok(())
+    }
+}
+
+/// Finds the asset of a standalone binary release for the given OS and architecture. Binaries
+/// with an asset pattern use it, otherwise the asset name must start with
+/// `<binary>-<os>-<arch>`.
+pub(crate) fn find_standalone_asset<'a>(
+    release: &'a Release,
+    binary_name: &str,
+    version: &str,
+    os: &str,
+    arch: &str,
+) -> Option<&'a Asset> {
+    let config = BinaryRegistry::global().get(binary_name);
+    match config.and_then(|config| Some((config, config.asset_pattern()?))) {
+        Some((config, pattern)) => {
+            let values = config.asset_values(None, Some(version), os, arch);
+            release
+                .assets
+                .iter()
+                .find(|asset| pattern.matches(&asset.name, &values))
+        }
+        None => standalone_asset_name_candidates(binary_name, os, arch)
+            .iter()
+            .find_map(|asset_name| {
+                release
+                    .assets
+                    .iter()
+                    .find(|asset| standalone_asset_name_matches(&asset.name, asset_name))
+            }),
     }
 }
 
@@ -212,7 +307,7 @@ fn normalize_standalone_version(version: &str) -> String {
     ensure_version_prefix(version)
 }
 
-fn standalone_tag_version(tag: &str) -> Option<String> {
+pub(crate) fn standalone_tag_version(tag: &str) -> Option<String> {
     if tag.starts_with('v') || tag.chars().next().is_some_and(|c| c.is_ascii_digit()) {
         return Some(ensure_version_prefix(tag));
     }
@@ -266,75 +361,6 @@ fn standalone_asset_name_matches(asset_name: &str, candidate: &str) -> bool {
     asset_name.starts_with(candidate)
 }
 
-fn standalone_releases_cache_file(repo_slug: &str) -> std::path::PathBuf {
-    let sanitized = repo_slug.replace('/', "_");
-    get_suiup_cache_dir().join(format!("standalone_releases_{sanitized}.json"))
-}
-
-fn load_cached_standalone_releases(
-    repo_slug: &str,
-) -> Result<Option<Vec<StandaloneRelease>>, Error> {
-    let cache_file = standalone_releases_cache_file(repo_slug);
-    if !cache_file.exists() {
-        return Ok(None);
-    }
-
-    let raw = std::fs::read_to_string(&cache_file)
-        .with_context(|| format!("Cannot read standalone cache file {}", cache_file.display()))?;
-    let releases = serde_json::from_str(&raw).with_context(|| {
-        format!(
-            "Cannot deserialize standalone cache file {}",
-            cache_file.display()
-        )
-    })?;
-    Ok(Some(releases))
-}
-
-fn save_cached_standalone_releases(
-    repo_slug: &str,
-    releases: &[StandaloneRelease],
-) -> Result<(), Error> {
-    let cache_file = standalone_releases_cache_file(repo_slug);
-    if let Some(parent) = cache_file.parent() {
-        std::fs::create_dir_all(parent).with_context(|| {
-            format!(
-                "Cannot create standalone cache directory {}",
-                parent.display()
-            )
-        })?;
-    }
-
-    let payload = serde_json::to_string_pretty(releases)
-        .context("Cannot serialize standalone releases cache payload")?;
-    std::fs::write(&cache_file, payload).with_context(|| {
-        format!(
-            "Cannot write standalone cache file {}",
-            cache_file.display()
-        )
-    })?;
-    Ok(())
-}
-
-async fn parse_json_response<T>(
-    response: reqwest::Response,
-    request_url: &str,
-    response_name: &str,
-) -> Result<T, Error>
-where
-    T: DeserializeOwned,
-{
-    let response_body = response
-        .text()
-        .await
-        .with_context(|| format!("Cannot read {response_name} response body from {request_url}"))?;
-
-    serde_json::from_str(&response_body).map_err(|e| {
-        anyhow!(
-            "Failed to deserialize {response_name} response from {request_url}: {e}\nResponse body:\n{response_body}"
-        )
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
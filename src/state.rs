@@ -0,0 +1,236 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Versioning of the suiup state files.
+//!
+//! Every state file carries a `schema_version`. Files written by older suiup versions, which
+//! have no `schema_version`, are version 1. On startup, files with an older version are backed
+//! up next to the original as `<file>.v<version>.bak` and upgraded by running the migrations of
+//! each version in turn.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, anyhow, bail};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+
+use crate::fs_utils::{read_json_file, write_json_file};
+use crate::lock::lock_state;
+use crate::paths::{default_file_path, installed_binaries_file};
+
+/// The current version of the state files
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Upgrades the contents of a state file by one version
+type Migration = fn(Value) -> Result<Value, Error>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateFile {
+    /// `installed_binaries.json`
+    InstalledBinaries,
+    /// `default_version.json`
+    DefaultVersions,
+}
+
+impl StateFile {
+    pub const ALL: [StateFile; 2] = [StateFile::InstalledBinaries, StateFile::DefaultVersions];
+
+    pub fn path(&self) -> Result<PathBuf, Error> {
+        match self {
+            StateFile::InstalledBinaries => installed_binaries_file(),
+            StateFile::DefaultVersions => default_file_path(),
+        }
+    }
+
+    /// The migrations of this file, where the migration at index `i` upgrades version `i + 1`
+    /// to version `i + 2`.
+    fn migrations(&self) -> &'static [Migration] {
+        match self {
+            StateFile::InstalledBinaries => &[installed_binaries_v1_to_v2],
+            StateFile::DefaultVersions => &[default_versions_v1_to_v2],
+        }
+    }
+}
+
+/// Reads a state file, upgrading its contents in memory if it was written with an older schema.
+pub fn read_state_file<T: DeserializeOwned>(path: &Path, file: StateFile) -> Result<T, Error> {
+    let value: Value = read_json_file(path)?;
+    let (_, value) = migrate(file, value)
+        .with_context(|| format!("Cannot upgrade state file {}", path.display()))?;
+    serde_json::from_value(value)
+        .map_err(|e| anyhow!("Cannot deserialize from file {}: {e}", path.display()))
+}
+
+/// Upgrades all state files written with an older schema, keeping a backup of each of them.
+pub fn migrate_state_files() -> Result<(), Error> {
+    let mut outdated = vec![];
+    for file in StateFile::ALL {
+        let path = file.path()?;
+        if schema_version(&read_json_file(&path)?) < SCHEMA_VERSION {
+            outdated.push((file, path));
+        }
+    }
+    if outdated.is_empty() {
+        return Ok(());
+    }
+
+    let _lock = lock_state()?;
+    for (file, path) in outdated {
+        // Another process might have upgraded the file in the meantime
+        let value: Value = read_json_file(&path)?;
+        let (from, value) = migrate(file, value)
+            .with_context(|| format!("Cannot upgrade state file {}", path.display()))?;
+        if from == SCHEMA_VERSION {
+            continue;
+        }
+
+        let backup = backup_path(&path, from);
+        std::fs::copy(&path, &backup).with_context(|| {
+            format!("Cannot back up {} to {}", path.display(), backup.display())
+        })?;
+        write_json_file(&path, &value)?;
+        println!(
+            "Upgraded {} to schema version {SCHEMA_VERSION}, the previous file is kept at {}",
+            path.display(),
+            backup.display()
+        );
+    }
+    Ok(())
+}
+
+/// Runs all migrations needed to bring `value` to the current schema version. Returns the
+/// original version along with the upgraded value.
+fn migrate(file: StateFile, mut value: Value) -> Result<(u32, Value), Error> {
+    let from = schema_version(&value);
+    if from > SCHEMA_VERSION {
+        bail!(
+            "The file was written by a newer suiup (schema version {from}, this suiup supports up to {SCHEMA_VERSION}). Update suiup with `suiup self update`"
+        );
+    }
+    for migration in &file.migrations()[from as usize - 1..] {
+        value = migration(value)?;
+    }
+    Ok((from, value))
+}
+
+/// The schema version of a state file, files without one are version 1
+fn schema_version(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or(1, |version| version.max(1) as u32)
+}
+
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{version}.bak"));
+    path.with_file_name(name)
+}
+
+/// Version 1 is `{"binaries": [...]}`, version 2 adds the schema version.
+fn installed_binaries_v1_to_v2(value: Value) -> Result<Value, Error> {
+    let Value::Object(mut object) = value else {
+        bail!("Expected a JSON object with the installed binaries");
+    };
+    object.insert("schema_version".to_string(), json!(2));
+    object
+        .entry("binaries")
+        .or_insert_with(|| Value::Array(vec![]));
+    Ok(Value::Object(object))
+}
+
+/// Version 1 maps binary names to `[network, version, debug]` tuples, where the oldest files
+/// lack the debug flag. Version 2 maps them to objects with named fields.
+fn default_versions_v1_to_v2(value: Value) -> Result<Value, Error> {
+    let Value::Object(object) = value else {
+        bail!("Expected a JSON object with the default binaries");
+    };
+
+    let mut binaries = Map::new();
+    for (name, default) in object {
+        let (network, version, debug) = match default.as_array().map(Vec::as_slice) {
+            Some([network, version]) => (network, version, &Value::Bool(false)),
+            Some([network, version, debug]) => (network, version, debug),
+            _ => bail!("Invalid default entry for {name}: {default}"),
+        };
+        binaries.insert(
+            name,
+            json!({
+                "network_release": network,
+                "version": version,
+                "debug": debug,
+            }),
+        );
+    }
+    Ok(json!({
+        "schema_version": 2,
+        "binaries": binaries,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DefaultBinaries, InstalledBinaries};
+
+    #[test]
+    fn migrates_unversioned_installed_binaries() {
+        let v1 = json!({
+            "binaries": [{
+                "binary_name": "sui",
+                "network_release": "testnet",
+                "version": "v1.44.2",
+                "debug": false,
+                "path": "/tmp/sui-v1.44.2"
+            }]
+        });
+        let (from, value) = migrate(StateFile::InstalledBinaries, v1).unwrap();
+        assert_eq!(from, 1);
+        assert_eq!(value["schema_version"], 2);
+        let binaries: InstalledBinaries = serde_json::from_value(value).unwrap();
+        assert_eq!(binaries.binaries()[0].binary_name, "sui");
+    }
+
+    #[test]
+    fn migrates_default_version_tuples() {
+        let v1 = json!({
+            "sui": ["testnet", "v1.44.2", true],
+            "mvr": ["standalone", "v0.0.5"]
+        });
+        let (from, value) = migrate(StateFile::DefaultVersions, v1).unwrap();
+        assert_eq!(from, 1);
+        let defaults: DefaultBinaries = serde_json::from_value(value).unwrap();
+        let sui = &defaults.binaries()["sui"];
+        assert_eq!(sui.network_release, "testnet");
+        assert_eq!(sui.version, "v1.44.2");
+        assert!(sui.debug);
+        assert!(!defaults.binaries()["mvr"].debug);
+    }
+
+    #[test]
+    fn current_version_is_unchanged_and_newer_version_is_rejected() {
+        let current = json!({"schema_version": SCHEMA_VERSION, "binaries": {}});
+        let (from, value) = migrate(StateFile::DefaultVersions, current.clone()).unwrap();
+        assert_eq!(from, SCHEMA_VERSION);
+        assert_eq!(value, current);
+
+        let newer = json!({"schema_version": SCHEMA_VERSION + 1, "binaries": {}});
+        let err = migrate(StateFile::DefaultVersions, newer).unwrap_err();
+        assert!(err.to_string().contains("newer suiup"));
+    }
+
+    #[test]
+    fn every_version_has_a_migration() {
+        for file in StateFile::ALL {
+            assert_eq!(file.migrations().len(), SCHEMA_VERSION as usize - 1);
+        }
+    }
+
+    #[test]
+    fn backup_path_keeps_file_name() {
+        assert_eq!(
+            backup_path(Path::new("/tmp/suiup/default_version.json"), 1),
+            PathBuf::from("/tmp/suiup/default_version.json.v1.bak")
+        );
+    }
+}
@@ -0,0 +1,345 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Detection of the Rust toolchain and the system dependencies needed to build binaries from
+//! source: the toolchain a repository pins in its `rust-toolchain.toml`, the toolchains and
+//! components installed with rustup, and tools and libraries like `clang`, `cmake` and `libpq`.
+
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{Context, Error, bail};
+use serde::Deserialize;
+use tracing::debug;
+
+/// Time to wait for the repository's toolchain file
+const TOOLCHAIN_FILE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The Rust toolchain a build requires
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequiredToolchain {
+    /// Toolchain channel, e.g. `stable`, `nightly` or `1.85`
+    pub channel: Option<String>,
+    /// rustup components, e.g. `rustfmt` or `rust-src`
+    pub components: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ToolchainFile {
+    toolchain: ToolchainSection,
+}
+
+#[derive(Deserialize)]
+struct ToolchainSection {
+    channel: Option<String>,
+    #[serde(default)]
+    components: Vec<String>,
+}
+
+/// Parses a `rust-toolchain.toml` file, or a legacy `rust-toolchain` file that only contains
+/// the channel
+pub fn parse_toolchain_file(content: &str) -> Result<RequiredToolchain, Error> {
+    let content = content.trim();
+    if !content.contains('[') && !content.contains('=') && !content.is_empty() {
+        return Ok(RequiredToolchain {
+            channel: Some(content.to_string()),
+            components: vec![],
+        });
+    }
+    let file: ToolchainFile = toml::from_str(content).context("Invalid rust-toolchain file")?;
+    Ok(RequiredToolchain {
+        channel: file.toolchain.channel,
+        components: file.toolchain.components,
+    })
+}
+
+/// Fetches the toolchain file of a GitHub repository at the given branch or tag. Returns `None`
+/// when the repository has none or it cannot be fetched, in which case the build uses the
+/// default toolchain.
+pub async fn fetch_toolchain_file(repository: &str, git_ref: &str) -> Option<RequiredToolchain> {
+    let client = reqwest::Client::builder()
+        .timeout(TOOLCHAIN_FILE_TIMEOUT)
+        .build()
+        .ok()?;
+    for file in ["rust-toolchain.toml", "rust-toolchain"] {
+        let url = format!("https://raw.githubusercontent.com/{repository}/{git_ref}/{file}");
+        let response = match client.get(&url).header("User-Agent", "suiup").send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                debug!("No toolchain file at {url}: {}", response.status());
+                continue;
+            }
+            Err(e) => {
+                debug!("Cannot fetch {url}: {e}");
+                return None;
+            }
+        };
+        let content = response.text().await.ok()?;
+        match parse_toolchain_file(&content) {
+            Ok(toolchain) => return Some(toolchain),
+            Err(e) => {
+                debug!("Ignoring {url}: {e:#}");
+                return None;
+            }
+        }
+    }
+    None
+}
+
+/// The output of `<command> --version`, if the command runs successfully
+pub fn command_version(command: &str) -> Option<String> {
+    Command::new(command)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The toolchains installed with rustup, e.g. `stable-x86_64-unknown-linux-gnu`
+pub fn installed_toolchains() -> Result<Vec<String>, Error> {
+    rustup_list(&["toolchain", "list"])
+}
+
+/// The components installed for a toolchain, e.g. `rustfmt-x86_64-unknown-linux-gnu`
+pub fn installed_components(channel: &str) -> Result<Vec<String>, Error> {
+    rustup_list(&["component", "list", "--installed", "--toolchain", channel])
+}
+
+fn rustup_list(args: &[&str]) -> Result<Vec<String>, Error> {
+    let output = Command::new("rustup")
+        .args(args)
+        .output()
+        .context("Cannot run rustup")?;
+    if !output.status.success() {
+        bail!(
+            "`rustup {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Whether a toolchain for `channel` is in the list. Toolchains are listed with the host
+/// target, so `nightly` matches `nightly-x86_64-unknown-linux-gnu`, but not a dated
+/// `nightly-2024-05-01-x86_64-unknown-linux-gnu`.
+pub fn has_toolchain(toolchains: &[String], channel: &str) -> bool {
+    is_listed(toolchains, channel)
+}
+
+/// The components that are not in the installed list, which has names with or without the
+/// host target, e.g. `rustfmt-x86_64-unknown-linux-gnu` or `rust-src`
+pub fn missing_components(installed: &[String], required: &[String]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|component| !is_listed(installed, component))
+        .cloned()
+        .collect()
+}
+
+/// Whether `name` is in a rustup list, either as is or followed by a target triple
+fn is_listed(list: &[String], name: &str) -> bool {
+    list.iter().any(|listed| {
+        listed == name
+            || listed
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('-'))
+                .is_some_and(|target| target.starts_with(|c: char| c.is_ascii_alphabetic()))
+    })
+}
+
+/// Operating system families that differ in how build dependencies are installed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Debian,
+    Fedora,
+    Arch,
+    Alpine,
+    Suse,
+    OtherLinux,
+    MacOs,
+    Windows,
+}
+
+impl Platform {
+    /// The platform suiup runs on
+    pub fn detect() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else if cfg!(windows) {
+            Platform::Windows
+        } else {
+            std::fs::read_to_string("/etc/os-release")
+                .map(|content| Self::from_os_release(&content))
+                .unwrap_or(Platform::OtherLinux)
+        }
+    }
+
+    /// The Linux distribution family from the `ID` and `ID_LIKE` fields of `/etc/os-release`
+    pub fn from_os_release(content: &str) -> Self {
+        let ids: Vec<String> = content
+            .lines()
+            .filter_map(|line| {
+                line.strip_prefix("ID=")
+                    .or_else(|| line.strip_prefix("ID_LIKE="))
+            })
+            .flat_map(|value| {
+                value
+                    .trim_matches('"')
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let is = |names: &[&str]| ids.iter().any(|id| names.contains(&id.as_str()));
+        if is(&["debian", "ubuntu"]) {
+            Platform::Debian
+        } else if is(&["fedora", "rhel", "centos"]) {
+            Platform::Fedora
+        } else if is(&["arch"]) {
+            Platform::Arch
+        } else if is(&["alpine"]) {
+            Platform::Alpine
+        } else if is(&["suse", "opensuse"]) {
+            Platform::Suse
+        } else {
+            Platform::OtherLinux
+        }
+    }
+}
+
+/// Whether a system dependency of a build is available. `libpq` is found like the `pq-sys`
+/// crate finds it; other dependencies are commands on the PATH.
+pub fn has_system_dependency(dependency: &str) -> bool {
+    match dependency {
+        "libpq" => {
+            std::env::var_os("PQ_LIB_DIR").is_some()
+                || command_version("pg_config").is_some()
+                || Command::new("pkg-config")
+                    .args(["--exists", "libpq"])
+                    .status()
+                    .is_ok_and(|status| status.success())
+        }
+        command => command_version(command).is_some(),
+    }
+}
+
+/// How to install a system dependency on the platform
+pub fn install_hint(dependency: &str, platform: Platform) -> String {
+    let package = match (dependency, platform) {
+        ("libpq", Platform::Debian | Platform::Alpine) => "libpq-dev",
+        ("libpq", Platform::Fedora) => "libpq-devel",
+        ("libpq", Platform::Arch) => "postgresql-libs",
+        ("libpq", Platform::Suse) => "postgresql-devel",
+        ("pkg-config", Platform::Fedora | Platform::Arch | Platform::Alpine) => "pkgconf",
+        ("clang", Platform::MacOs) => return "xcode-select --install".to_string(),
+        ("clang", Platform::Windows) => return "winget install LLVM.LLVM".to_string(),
+        ("cmake", Platform::Windows) => return "winget install Kitware.CMake".to_string(),
+        ("libpq", Platform::Windows) => {
+            return "install PostgreSQL from https://www.postgresql.org/download/windows/ and set PQ_LIB_DIR to its lib folder".to_string();
+        }
+        (dependency, _) => dependency,
+    };
+    match platform {
+        Platform::Debian => format!("sudo apt-get install {package}"),
+        Platform::Fedora => format!("sudo dnf install {package}"),
+        Platform::Arch => format!("sudo pacman -S {package}"),
+        Platform::Alpine => format!("sudo apk add {package}"),
+        Platform::Suse => format!("sudo zypper install {package}"),
+        Platform::MacOs => format!("brew install {package}"),
+        Platform::OtherLinux | Platform::Windows => {
+            format!("install {package} with your package manager")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_toolchain_files() {
+        let toolchain = parse_toolchain_file(
+            "[toolchain]\nchannel = \"1.86\"\ncomponents = [\"rustfmt\", \"clippy\"]\n",
+        )
+        .unwrap();
+        assert_eq!(toolchain.channel.as_deref(), Some("1.86"));
+        assert_eq!(toolchain.components, names(&["rustfmt", "clippy"]));
+
+        let legacy = parse_toolchain_file("nightly-2024-05-01\n").unwrap();
+        assert_eq!(legacy.channel.as_deref(), Some("nightly-2024-05-01"));
+        assert!(legacy.components.is_empty());
+
+        assert!(parse_toolchain_file("[package]\nname = \"x\"").is_err());
+    }
+
+    #[test]
+    fn matches_installed_toolchains() {
+        let installed = names(&[
+            "stable-x86_64-unknown-linux-gnu",
+            "nightly-2024-05-01-x86_64-unknown-linux-gnu",
+            "1.86-aarch64-apple-darwin",
+        ]);
+        assert!(has_toolchain(&installed, "stable"));
+        assert!(has_toolchain(&installed, "nightly-2024-05-01"));
+        assert!(has_toolchain(&installed, "1.86"));
+        assert!(!has_toolchain(&installed, "nightly"));
+        assert!(!has_toolchain(&installed, "1.8"));
+    }
+
+    #[test]
+    fn finds_missing_components() {
+        let installed = names(&["cargo-x86_64-unknown-linux-gnu", "rust-src"]);
+        assert_eq!(
+            missing_components(&installed, &names(&["cargo", "rust-src", "rustfmt"])),
+            names(&["rustfmt"])
+        );
+    }
+
+    #[test]
+    fn detects_linux_distributions() {
+        assert_eq!(
+            Platform::from_os_release("NAME=\"Ubuntu\"\nID=ubuntu\nID_LIKE=debian\n"),
+            Platform::Debian
+        );
+        assert_eq!(
+            Platform::from_os_release("ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n"),
+            Platform::Fedora
+        );
+        assert_eq!(Platform::from_os_release("ID=alpine\n"), Platform::Alpine);
+        assert_eq!(
+            Platform::from_os_release("ID=nixos\n"),
+            Platform::OtherLinux
+        );
+    }
+
+    #[test]
+    fn install_hints_use_distribution_packages() {
+        assert_eq!(
+            install_hint("libpq", Platform::Debian),
+            "sudo apt-get install libpq-dev"
+        );
+        assert_eq!(
+            install_hint("libpq", Platform::Fedora),
+            "sudo dnf install libpq-devel"
+        );
+        assert_eq!(install_hint("cmake", Platform::MacOs), "brew install cmake");
+        assert_eq!(
+            install_hint("clang", Platform::MacOs),
+            "xcode-select --install"
+        );
+        assert_eq!(
+            install_hint("clang", Platform::Arch),
+            "sudo pacman -S clang"
+        );
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transactions group the filesystem changes of an install or a default switch, so that a
+//! failure at any step leaves the binaries folder, the default binaries and the state files as
+//! they were before.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, anyhow};
+use serde::Serialize;
+use tempfile::TempDir;
+
+use crate::fs_utils::write_json_file;
+use crate::history::{self, StateSnapshot};
+use crate::lock::{FileLock, lock_state};
+
+/// Environment variable naming a stage at which an install fails on purpose, see [`fail_point`]
+#[cfg(any(test, feature = "fault-injection"))]
+pub const FAIL_POINT_ENV: &str = "SUIUP_FAIL_POINT";
+
+/// A file changed by a transaction and how to undo the change.
+enum Undo {
+    /// The file did not exist before the transaction
+    Remove(PathBuf),
+    /// The file existed, its previous contents are kept in `backup`
+    Restore {
+        path: PathBuf,
+        backup: PathBuf,
+        // Keeps the backup alive until the transaction is finished
+        _dir: TempDir,
+    },
+}
+
+/// Records every file that is created or replaced, and undoes all changes in reverse order when
+/// dropped without being committed.
+///
+/// A transaction holds the state lock for its whole lifetime, so that other suiup processes
+/// cannot interleave their changes. Committed changes are recorded in the history journal.
+pub struct Transaction {
+    undo: Vec<Undo>,
+    committed: bool,
+    /// The state when the transaction started, if it could be read
+    before: Option<StateSnapshot>,
+    _lock: Option<FileLock>,
+}
+
+impl Transaction {
+    pub fn new() -> Result<Self, Error> {
+        let lock = lock_state()?;
+        Ok(Self {
+            undo: vec![],
+            committed: false,
+            before: StateSnapshot::read().ok(),
+            _lock: Some(lock),
+        })
+    }
+
+    /// Remembers the current state of `path`, so that it can be restored on rollback. Only the
+    /// first call for a path has an effect.
+    pub fn backup(&mut self, path: &Path) -> Result<(), Error> {
+        if self.undo.iter().any(|undo| undo.path() == path) {
+            return Ok(());
+        }
+        if !path.exists() {
+            self.undo.push(Undo::Remove(path.to_path_buf()));
+            return Ok(());
+        }
+
+        let parent = parent_dir(path)?;
+        let dir = tempfile::Builder::new()
+            .prefix(".suiup-backup-")
+            .tempdir_in(parent)
+            .with_context(|| format!("Cannot create backup directory in {}", parent.display()))?;
+        let backup = dir.path().join("backup");
+        // Files are replaced through renames, so a hard link keeps the previous contents
+        if std::fs::hard_link(path, &backup).is_err() {
+            std::fs::copy(path, &backup)
+                .with_context(|| format!("Cannot back up {}", path.display()))?;
+        }
+        self.undo.push(Undo::Restore {
+            path: path.to_path_buf(),
+            backup,
+            _dir: dir,
+        });
+        Ok(())
+    }
+
+    /// Moves a staged file to `target`, replacing any existing file.
+    pub fn install_file(&mut self, staged: &Path, target: &Path) -> Result<(), Error> {
+        self.backup(target)?;
+        std::fs::rename(staged, target)
+            .with_context(|| format!("Cannot move {} to {}", staged.display(), target.display()))
+    }
+
+    /// Copies `src` to `target` through a temporary file next to the target, so that the target
+    /// is replaced atomically.
+    pub fn copy_file(&mut self, src: &Path, target: &Path) -> Result<(), Error> {
+        let parent = parent_dir(target)?;
+        let staged = tempfile::Builder::new()
+            .prefix(".suiup-")
+            .tempfile_in(parent)
+            .with_context(|| format!("Cannot create temporary file in {}", parent.display()))?
+            .into_temp_path();
+        std::fs::copy(src, &staged).with_context(|| {
+            format!(
+                "Cannot copy binary from {} to {}",
+                src.display(),
+                target.display()
+            )
+        })?;
+        self.install_file(&staged, target)
+    }
+
+    /// Removes a file, restoring it on rollback.
+    pub fn remove_file(&mut self, path: &Path) -> Result<(), Error> {
+        self.backup(path)?;
+        std::fs::remove_file(path).with_context(|| format!("Cannot remove file {}", path.display()))
+    }
+
+    /// Writes a JSON state file, restoring the previous contents on rollback.
+    pub fn write_json<T: Serialize>(&mut self, path: &Path, data: &T) -> Result<(), Error> {
+        self.backup(path)?;
+        write_json_file(path, data)
+    }
+
+    /// Keeps all changes made in this transaction and records them in the history.
+    pub fn commit(mut self) {
+        self.committed = true;
+        if let Some(before) = self.before.take() {
+            let recorded = StateSnapshot::read().and_then(|after| history::record(before, after));
+            if let Err(e) = recorded {
+                eprintln!("Warning: cannot record the changes in the history: {e:#}");
+            }
+        }
+    }
+
+    fn rollback(&mut self) {
+        for undo in self.undo.drain(..).rev() {
+            let result = match &undo {
+                Undo::Remove(path) if path.exists() => std::fs::remove_file(path),
+                Undo::Remove(_) => Ok(()),
+                Undo::Restore { path, backup, .. } => std::fs::rename(backup, path)
+                    .or_else(|_| std::fs::copy(backup, path).map(|_| ())),
+            };
+            if let Err(e) = result {
+                eprintln!(
+                    "Warning: cannot roll back changes to {}: {e}",
+                    undo.path().display()
+                );
+            }
+        }
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if !self.committed && !self.undo.is_empty() {
+            println!("Rolling back changes");
+            self.rollback();
+        }
+    }
+}
+
+impl Undo {
+    fn path(&self) -> &Path {
+        match self {
+            Undo::Remove(path) => path,
+            Undo::Restore { path, .. } => path,
+        }
+    }
+}
+
+fn parent_dir(path: &Path) -> Result<&Path, Error> {
+    path.parent()
+        .ok_or_else(|| anyhow!("{} has no parent directory", path.display()))
+}
+
+/// Fails when the `SUIUP_FAIL_POINT` environment variable is set to `stage`. Tests use this to
+/// check that an install is rolled back when any of its stages fails.
+#[cfg(any(test, feature = "fault-injection"))]
+pub fn fail_point(stage: &str) -> Result<(), Error> {
+    if std::env::var(FAIL_POINT_ENV).is_ok_and(|value| value == stage) {
+        anyhow::bail!("Injected failure at stage `{stage}`");
+    }
+    Ok(())
+}
+
+/// Fault injection is only compiled into test builds
+#[cfg(not(any(test, feature = "fault-injection")))]
+#[inline(always)]
+pub fn fail_point(_stage: &str) -> Result<(), Error> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl Transaction {
+        /// A transaction that does not touch the state lock of the user's suiup data directory
+        fn without_lock() -> Self {
+            Self {
+                undo: vec![],
+                committed: false,
+                before: None,
+                _lock: None,
+            }
+        }
+    }
+
+    #[test]
+    fn rollback_restores_replaced_and_removes_created_files() {
+        let dir = TempDir::new().unwrap();
+        let existing = dir.path().join("existing");
+        let created = dir.path().join("created");
+        let source = dir.path().join("source");
+        std::fs::write(&existing, b"old").unwrap();
+        std::fs::write(&source, b"new").unwrap();
+
+        let mut tx = Transaction::without_lock();
+        tx.copy_file(&source, &existing).unwrap();
+        tx.write_json(&created, &vec!["sui"]).unwrap();
+        assert_eq!(std::fs::read(&existing).unwrap(), b"new");
+        assert!(created.exists());
+        drop(tx);
+
+        assert_eq!(std::fs::read(&existing).unwrap(), b"old");
+        assert!(!created.exists());
+        assert!(source.exists());
+        // No backups or temporary files are left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn commit_keeps_changes() {
+        let dir = TempDir::new().unwrap();
+        let staged = dir.path().join("staged");
+        let target = dir.path().join("target");
+        std::fs::write(&staged, b"new").unwrap();
+        std::fs::write(&target, b"old").unwrap();
+
+        let mut tx = Transaction::without_lock();
+        tx.install_file(&staged, &target).unwrap();
+        tx.commit();
+
+        assert_eq!(std::fs::read(&target).unwrap(), b"new");
+        assert!(!staged.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn only_first_backup_of_a_path_is_kept() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state.json");
+        std::fs::write(&path, b"[]").unwrap();
+
+        let mut tx = Transaction::without_lock();
+        tx.write_json(&path, &vec!["sui"]).unwrap();
+        tx.write_json(&path, &vec!["sui", "mvr"]).unwrap();
+        drop(tx);
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"[]");
+    }
+}
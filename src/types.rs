@@ -1,45 +1,139 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::fs_utils::{read_json_file, write_json_file};
-use anyhow::{Context, Error, anyhow};
+use crate::fs_utils::{sha256_file, write_json_file};
+use crate::handlers::version::extract_version_from_release;
+use anyhow::{Error, anyhow, bail};
 use std::{
     collections::BTreeMap,
     fmt::{self, Display, Formatter},
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
-use clap::ValueEnum;
+use clap::{Args, ValueEnum};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::paths::{default_file_path, installed_binaries_file};
+use crate::state::{SCHEMA_VERSION, StateFile, read_state_file};
 
 pub type Version = String;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Release {
+    /// The release tag, e.g. `testnet-v1.44.2`. Empty in release lists cached by older versions
+    #[serde(default)]
+    pub tag_name: String,
+    /// When the release was published, e.g. `2025-03-04T17:21:08Z`. Drafts have no date
+    #[serde(default)]
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    /// The release notes
+    #[serde(default)]
+    pub body: Option<String>,
     pub assets: Vec<Asset>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl Release {
+    /// The version of the release, e.g. `v1.44.2`. It is read from the tag, or from the name of
+    /// the first asset when the tag has none
+    pub fn version(&self) -> Option<String> {
+        extract_version_from_release(&self.tag_name)
+            .ok()
+            .or_else(|| {
+                let asset = self.assets.first()?;
+                extract_version_from_release(&asset.name).ok()
+            })
+    }
+
+    /// Whether versions are resolved to this release. Drafts never are, and pre-releases only
+    /// with `--pre`
+    pub fn is_installable(&self, include_prereleases: bool) -> bool {
+        !self.draft && (include_prereleases || !self.prerelease)
+    }
+
+    /// The publication date, e.g. `2025-03-04`
+    pub fn published_date(&self) -> Option<&str> {
+        self.published_at
+            .as_deref()
+            .map(|date| date.split_once('T').map_or(date, |(day, _)| day))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Asset {
     pub browser_download_url: String,
     pub name: String,
+    /// Size in bytes, when the release source reports it
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Digest of the asset, e.g. `sha256:<hex>`, when the release source reports it
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+impl Asset {
+    /// Checks a downloaded copy of the asset against the size and `sha256:` digest reported by
+    /// the release source. Digests with other algorithms are not checked.
+    pub fn verify(&self, path: &Path) -> Result<(), Error> {
+        if let Some(size) = self.size {
+            let actual = std::fs::metadata(path)
+                .map_err(|e| anyhow!("Cannot read metadata of {}: {e}", path.display()))?
+                .len();
+            if actual != size {
+                bail!(
+                    "Downloaded {} has {actual} bytes, but the release reports {size}",
+                    self.name
+                );
+            }
+        }
+        if let Some(expected) = self
+            .digest
+            .as_deref()
+            .and_then(|digest| digest.strip_prefix("sha256:"))
+        {
+            let actual = sha256_file(path)?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                bail!(
+                    "Checksum mismatch for {}: expected sha256 {expected}, got {actual}",
+                    self.name
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 pub struct Binaries {
     pub binaries: Vec<BinaryVersion>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Struct to store the default binaries, keyed by binary name
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct DefaultBinaries {
-    pub binaries: Vec<BinaryVersion>,
+    schema_version: u32,
+    binaries: BTreeMap<String, DefaultBinary>,
+}
+
+/// The installed binary that is used as default for a binary name
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DefaultBinary {
+    /// The network release or branch of the binary
+    pub network_release: String,
+    /// The version of the binary in the corresponding release
+    pub version: Version,
+    /// Debug build of the binary
+    pub debug: bool,
 }
 
 /// Struct to store the installed binaries
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct InstalledBinaries {
+    schema_version: u32,
     binaries: Vec<BinaryVersion>,
 }
 
@@ -55,6 +149,53 @@ pub struct BinaryVersion {
     pub debug: bool,
     /// Path to the binary
     pub path: Option<String>,
+    /// Cargo settings of a binary built from a branch
+    #[serde(default, skip_serializing_if = "BuildOptions::is_default")]
+    pub build: BuildOptions,
+}
+
+/// Cargo settings for building a binary from a branch with `install --nightly`
+#[derive(Args, Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildOptions {
+    /// Cargo features to enable when building from a branch, e.g. `--features tracing`
+    #[arg(long, value_delimiter = ',', requires = "nightly")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+
+    /// Do not enable the default features of the package when building from a branch
+    #[arg(long, requires = "nightly")]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_default_features: bool,
+
+    /// Cargo profile to build with when building from a branch
+    #[arg(
+        long,
+        value_name = "name",
+        requires = "nightly",
+        conflicts_with = "debug"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// Additional argument passed to `cargo install` when building from a branch. Can be
+    /// repeated, e.g. `--cargo-arg=--jobs --cargo-arg=4`
+    #[arg(
+        long = "cargo-arg",
+        value_name = "arg",
+        allow_hyphen_values = true,
+        requires = "nightly"
+    )]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cargo_args: Vec<String>,
+}
+
+/// A release archive that is installed from outside the GitHub releases of a binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveSource {
+    /// An archive on the local filesystem
+    File(PathBuf),
+    /// An archive that is downloaded from an arbitrary URL
+    Url(String),
 }
 
 #[derive(
@@ -72,7 +213,10 @@ pub enum Network {
 
 impl InstalledBinaries {
     pub fn create_file(path: &Path) -> Result<(), Error> {
-        let binaries = InstalledBinaries { binaries: vec![] };
+        let binaries = InstalledBinaries {
+            schema_version: SCHEMA_VERSION,
+            binaries: vec![],
+        };
         write_json_file(path, &binaries)
     }
 
@@ -87,7 +231,7 @@ impl InstalledBinaries {
 
     /// Read the installed binaries JSON file
     pub fn read_from_file() -> Result<Self, Error> {
-        read_json_file(&installed_binaries_file()?)
+        read_state_file(&installed_binaries_file()?, StateFile::InstalledBinaries)
     }
 
     /// Add a binary to the installed binaries JSON file
@@ -102,30 +246,110 @@ impl InstalledBinaries {
         self.binaries.retain(|b| b.binary_name != binary);
     }
 
+    /// Remove a single installed version of a binary from the installed binaries JSON file
+    pub fn remove_version(&mut self, binary: &BinaryVersion) {
+        self.binaries.retain(|b| {
+            b.binary_name != binary.binary_name
+                || b.network_release != binary.network_release
+                || b.version != binary.version
+                || b.debug != binary.debug
+        });
+    }
+
     /// List the binaries in the installed binaries JSON file
     pub fn binaries(&self) -> &[BinaryVersion] {
         &self.binaries
     }
 }
 
+impl BuildOptions {
+    /// Whether these are the settings of a plain `cargo install`
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The version recorded for a build from a branch with these settings: `nightly`, followed
+    /// by a short hash of the settings when they are not the default ones, so that builds of the
+    /// same branch with different settings are installed side by side
+    pub fn nightly_version(&self) -> String {
+        if self.is_default() {
+            return "nightly".to_string();
+        }
+        let hash = format!("{:x}", Sha256::digest(self.cargo_args().join(" ")));
+        format!("nightly-{}", &hash[..8])
+    }
+
+    /// The arguments to pass to `cargo install` for these settings
+    pub fn cargo_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        if let Some(profile) = &self.profile {
+            args.push("--profile".to_string());
+            args.push(profile.clone());
+        }
+        args.extend(self.cargo_args.iter().cloned());
+        args
+    }
+}
+
+impl ArchiveSource {
+    /// Returns the archive file name, which is also used to derive the network and version.
+    pub fn file_name(&self) -> Result<String, Error> {
+        let name = match self {
+            ArchiveSource::File(path) => path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(str::to_string),
+            ArchiveSource::Url(url) => url
+                .split(['?', '#'])
+                .next()
+                .and_then(|url| url.rsplit('/').next())
+                .filter(|name| !name.is_empty())
+                .map(str::to_string),
+        };
+
+        name.ok_or_else(|| anyhow!("Cannot determine the archive file name from {self}"))
+    }
+}
+
 impl DefaultBinaries {
-    pub fn _load() -> Result<DefaultBinaries, Error> {
-        let default_file_path = default_file_path()?;
-        let file_content = std::fs::read_to_string(&default_file_path).with_context(|| {
-            format!(
-                "Cannot read default binaries file {}",
-                default_file_path.display()
-            )
-        })?;
-        let default_binaries: DefaultBinaries =
-            serde_json::from_str(&file_content).map_err(|e| {
-                anyhow!(
-                    "Cannot deserialize default binaries file {}: {e}",
-                    default_file_path.display()
-                )
-            })?;
-
-        Ok(default_binaries)
+    pub fn create_file(path: &Path) -> Result<(), Error> {
+        let binaries = DefaultBinaries {
+            schema_version: SCHEMA_VERSION,
+            binaries: BTreeMap::new(),
+        };
+        write_json_file(path, &binaries)
+    }
+
+    /// Read the default version JSON file
+    pub fn read_from_file() -> Result<Self, Error> {
+        read_state_file(&default_file_path()?, StateFile::DefaultVersions)
+    }
+
+    /// Save the default binaries to the default version JSON file
+    pub fn save_to_file(&self) -> Result<(), Error> {
+        write_json_file(&default_file_path()?, self)
+    }
+
+    /// Set the default for a binary name, replacing any previous default
+    pub fn set(&mut self, binary_name: &str, default: DefaultBinary) {
+        self.binaries.insert(binary_name.to_string(), default);
+    }
+
+    /// Remove the default for a binary name
+    pub fn remove(&mut self, binary_name: &str) -> Option<DefaultBinary> {
+        self.binaries.remove(binary_name)
+    }
+
+    /// The default binaries, keyed by binary name
+    pub fn binaries(&self) -> &BTreeMap<String, DefaultBinary> {
+        &self.binaries
     }
 }
 
@@ -158,9 +382,41 @@ impl Display for Binaries {
 impl Display for BinaryVersion {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         if self.debug {
-            write!(f, "{}-{} (debug build)", self.binary_name, self.version)
+            write!(f, "{}-{} (debug build)", self.binary_name, self.version)?;
         } else {
-            write!(f, "{}-{}", self.binary_name, self.version)
+            write!(f, "{}-{}", self.binary_name, self.version)?;
+        }
+        if !self.build.is_default() {
+            write!(f, " ({})", self.build)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for BuildOptions {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut parts = vec![];
+        if !self.features.is_empty() {
+            parts.push(format!("features: {}", self.features.join(",")));
+        }
+        if self.no_default_features {
+            parts.push("no default features".to_string());
+        }
+        if let Some(profile) = &self.profile {
+            parts.push(format!("profile: {profile}"));
+        }
+        if !self.cargo_args.is_empty() {
+            parts.push(format!("cargo args: {}", self.cargo_args.join(" ")));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+impl Display for ArchiveSource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ArchiveSource::File(path) => write!(f, "{}", path.display()),
+            ArchiveSource::Url(url) => write!(f, "{url}"),
         }
     }
 }
@@ -175,16 +431,18 @@ impl Display for Network {
     }
 }
 
-impl From<BTreeMap<String, (String, Version, bool)>> for Binaries {
-    fn from(map: BTreeMap<String, (String, Version, bool)>) -> Self {
-        let binaries = map
+impl From<&DefaultBinaries> for Binaries {
+    fn from(defaults: &DefaultBinaries) -> Self {
+        let binaries = defaults
+            .binaries
             .iter()
-            .map(|(k, v)| BinaryVersion {
-                binary_name: k.to_string(),
-                network_release: v.0.clone(),
-                version: v.1.to_string(),
-                debug: v.2,
+            .map(|(name, default)| BinaryVersion {
+                binary_name: name.to_string(),
+                network_release: default.network_release.clone(),
+                version: default.version.clone(),
+                debug: default.debug,
                 path: None,
+                build: BuildOptions::default(),
             })
             .collect();
         Binaries { binaries }
@@ -5,7 +5,7 @@ mod test_utils;
 
 #[cfg(test)]
 mod tests {
-    use crate::test_utils::TestEnv;
+    use crate::test_utils::{TestEnv, create_release_archive, detect_os_arch_for_tests};
     use anyhow::Result;
     use assert_cmd::Command;
     use assert_cmd::cargo::cargo_bin_cmd;
@@ -180,6 +180,2091 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_install_from_file() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let archive = create_release_archive(
+            test_env.temp_dir.path(),
+            "sui-mainnet-v1.44.2-ubuntu-x86_64.tgz",
+            &["sui", "sui-node"],
+            "1.44.2",
+        )?;
+
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "sui",
+                "--from-file",
+                archive.to_str().unwrap(),
+                "-y",
+            ],
+            &test_env,
+        );
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("'sui' extracted successfully!"));
+
+        let binary_path = test_env.data_dir.join("suiup/binaries/mainnet/sui-v1.44.2");
+        assert!(binary_path.exists());
+
+        let mut cmd = suiup_command(vec!["default", "get"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("mainnet"))
+            .stdout(predicate::str::contains("v1.44.2"));
+
+        // Explicit network and version override what is derived from the archive name
+        let archive = create_release_archive(
+            test_env.temp_dir.path(),
+            "sui-node.tgz",
+            &["sui-node"],
+            "1.45.0",
+        )?;
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "sui-node@testnet-1.45.0",
+                "--from-file",
+                archive.to_str().unwrap(),
+                "-y",
+            ],
+            &test_env,
+        );
+        cmd.assert().success();
+        assert!(
+            test_env
+                .data_dir
+                .join("suiup/binaries/testnet/sui-node-v1.45.0")
+                .exists()
+        );
+
+        // Without a version in the name or the spec, the install is rejected
+        let mut cmd = suiup_command(
+            vec!["install", "sui", "--from-file", archive.to_str().unwrap()],
+            &test_env,
+        );
+        cmd.assert().failure().stderr(predicate::str::contains(
+            "Cannot determine the version from archive name sui-node.tgz",
+        ));
+
+        // The archive must contain the requested binary
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "sui@testnet-1.45.0",
+                "--from-file",
+                archive.to_str().unwrap(),
+            ],
+            &test_env,
+        );
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("Cannot find sui in archive"));
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_install_rolls_back_on_failure() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let archive = create_release_archive(
+            test_env.temp_dir.path(),
+            "sui-mainnet-v1.44.2-ubuntu-x86_64.tgz",
+            &["sui"],
+            "1.44.2",
+        )?;
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "sui",
+                "--from-file",
+                archive.to_str().unwrap(),
+                "-y",
+            ],
+            &test_env,
+        );
+        cmd.assert().success();
+
+        let binaries_dir = test_env.data_dir.join("suiup/binaries/mainnet");
+        let installed_file = test_env.config_dir.join("suiup/installed_binaries.json");
+        let default_file = test_env.config_dir.join("suiup/default_version.json");
+        let default_binary = test_env.bin_dir.join("sui");
+        let snapshot = || -> Result<_> {
+            let mut entries = fs::read_dir(&binaries_dir)?
+                .map(|e| e.map(|e| e.file_name()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.sort();
+            Ok((
+                entries,
+                fs::read(&installed_file)?,
+                fs::read(&default_file)?,
+                fs::read(&default_binary)?,
+            ))
+        };
+        let before = snapshot()?;
+
+        let archive = create_release_archive(
+            test_env.temp_dir.path(),
+            "sui-mainnet-v1.45.0-ubuntu-x86_64.tgz",
+            &["sui"],
+            "1.45.0",
+        )?;
+        for stage in ["extract", "install", "state", "default"] {
+            let mut cmd = suiup_command(
+                vec![
+                    "install",
+                    "sui",
+                    "--from-file",
+                    archive.to_str().unwrap(),
+                    "-y",
+                ],
+                &test_env,
+            );
+            cmd.env("SUIUP_FAIL_POINT", stage);
+            cmd.assert()
+                .failure()
+                .stderr(predicate::str::contains(format!(
+                    "Injected failure at stage `{stage}`"
+                )));
+            assert_eq!(
+                snapshot()?,
+                before,
+                "state changed after failing at {stage}"
+            );
+        }
+
+        // Switching the default version is rolled back as well
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "sui",
+                "--from-file",
+                archive.to_str().unwrap(),
+                "-y",
+            ],
+            &test_env,
+        );
+        cmd.assert().success();
+        let before = snapshot()?;
+        let mut cmd = suiup_command(vec!["default", "set", "sui@mainnet-1.44.2"], &test_env);
+        cmd.env("SUIUP_FAIL_POINT", "default");
+        cmd.assert().failure();
+        assert_eq!(snapshot()?, before);
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_repair_reconciles_state_with_disk() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        for version in ["1.44.2", "1.45.0"] {
+            let archive = create_release_archive(
+                test_env.temp_dir.path(),
+                &format!("sui-mainnet-v{version}-ubuntu-x86_64.tgz"),
+                &["sui"],
+                version,
+            )?;
+            let mut cmd = suiup_command(
+                vec![
+                    "install",
+                    "sui",
+                    "--from-file",
+                    archive.to_str().unwrap(),
+                    "-y",
+                ],
+                &test_env,
+            );
+            cmd.assert().success();
+        }
+
+        // Files deleted and copied by hand
+        let binaries_dir = test_env.data_dir.join("suiup/binaries");
+        fs::remove_file(binaries_dir.join("mainnet/sui-v1.44.2"))?;
+        fs::create_dir_all(binaries_dir.join("testnet"))?;
+        fs::copy(
+            binaries_dir.join("mainnet/sui-v1.45.0"),
+            binaries_dir.join("testnet/sui-v1.45.0"),
+        )?;
+        let default_binary = test_env.bin_dir.join("sui");
+        fs::remove_file(&default_binary)?;
+
+        let installed_file = test_env.config_dir.join("suiup/installed_binaries.json");
+        let installed_before = fs::read(&installed_file)?;
+        let mut cmd = suiup_command(vec!["repair", "--dry-run"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("+ sui-v1.45.0 [testnet]"))
+            .stdout(predicate::str::contains("- sui-v1.44.2 [mainnet]"))
+            .stdout(predicate::str::contains("~ default sui"))
+            .stdout(predicate::str::contains("Dry run"));
+        assert_eq!(fs::read(&installed_file)?, installed_before);
+        assert!(!default_binary.exists());
+
+        let mut cmd = suiup_command(vec!["repair", "-y"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Repair completed"));
+        assert_eq!(
+            fs::read(&default_binary)?,
+            fs::read(binaries_dir.join("mainnet/sui-v1.45.0"))?
+        );
+
+        let installed: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&installed_file)?)?;
+        let mut entries: Vec<String> = installed["binaries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| format!("{}@{}", b["network_release"], b["version"]).replace('"', ""))
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec!["mainnet@v1.45.0", "testnet@v1.45.0"]);
+
+        let mut cmd = suiup_command(vec!["repair"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("nothing to repair"));
+
+        // A default whose binary is gone is dropped along with its copy in the bin folder
+        fs::remove_file(binaries_dir.join("mainnet/sui-v1.45.0"))?;
+        let mut cmd = suiup_command(vec!["repair", "-y"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("- default sui"));
+        assert!(!default_binary.exists());
+
+        // Remove drops the entries of binaries that are already gone
+        fs::remove_file(binaries_dir.join("testnet/sui-v1.45.0"))?;
+        let mut cmd = suiup_command(vec!["remove", "sui"], &test_env);
+        cmd.assert().success().stdout(predicate::str::contains(
+            "does not exist, removing its entry",
+        ));
+        let installed: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&installed_file)?)?;
+        assert!(installed["binaries"].as_array().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_max_size_and_metadata() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let archives_dir = test_env.cache_dir.join("suiup/releases");
+        let now = SystemTime::now();
+        for (i, name) in ["oldest.tgz", "older.tgz", "newest.tgz"].iter().enumerate() {
+            let path = archives_dir.join(name);
+            fs::write(&path, vec![0u8; 1000])?;
+            let age = Duration::from_secs(60 * 60 * (3 - i as u64));
+            filetime::set_file_mtime(&path, filetime::FileTime::from_system_time(now - age))?;
+        }
+
+        let mut cmd = suiup_command(vec!["cleanup", "--max-size", "2KB", "--dry-run"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Would remove 1 files"));
+        assert!(archives_dir.join("oldest.tgz").exists());
+
+        let mut cmd = suiup_command(vec!["cleanup", "--max-size", "2KB"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("1 files removed"));
+        assert!(!archives_dir.join("oldest.tgz").exists());
+        assert!(archives_dir.join("older.tgz").exists());
+        assert!(archives_dir.join("newest.tgz").exists());
+
+        let mut cmd = suiup_command(vec!["cleanup", "--max-size", "2 parsecs"], &test_env);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid size unit"));
+
+        // Stale release lists go together with their ETag, ETags without a list always go
+        let cache_dir = test_env.cache_dir.join("suiup");
+        let old_time = now - Duration::from_secs(60 * 60 * 24 * 40);
+        for name in ["releases_MystenLabs_sui.txt", "etag_MystenLabs_sui.txt"] {
+            fs::write(cache_dir.join(name), "[]")?;
+            filetime::set_file_mtime(
+                cache_dir.join(name),
+                filetime::FileTime::from_system_time(old_time),
+            )?;
+        }
+        fs::write(cache_dir.join("releases_MystenLabs_walrus.txt"), "[]")?;
+        fs::write(cache_dir.join("etag_MystenLabs_walrus.txt"), "etag")?;
+        fs::write(cache_dir.join("etag_MystenLabs_mvr.txt"), "etag")?;
+
+        let mut cmd = suiup_command(vec!["cleanup", "--metadata"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("3 files removed"));
+        assert!(!cache_dir.join("releases_MystenLabs_sui.txt").exists());
+        assert!(!cache_dir.join("etag_MystenLabs_sui.txt").exists());
+        assert!(!cache_dir.join("etag_MystenLabs_mvr.txt").exists());
+        assert!(cache_dir.join("releases_MystenLabs_walrus.txt").exists());
+        assert!(cache_dir.join("etag_MystenLabs_walrus.txt").exists());
+        assert!(archives_dir.join("newest.tgz").exists());
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_cache_size_limit_evicts_least_recently_used() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let archives_dir = test_env.cache_dir.join("suiup/releases");
+        let archive = create_release_archive(
+            test_env.temp_dir.path(),
+            "sui-mainnet-v1.44.2-ubuntu-x86_64.tgz",
+            &["sui"],
+            "1.44.2",
+        )?;
+        let archive_size = fs::metadata(&archive)?.len();
+        for name in ["unused.tgz", "used.tgz"] {
+            fs::write(archives_dir.join(name), vec![0u8; 1000])?;
+        }
+        fs::write(archives_dir.join("used.tgz.lock"), "")?;
+        let old_time = SystemTime::now() - Duration::from_secs(60 * 60);
+        filetime::set_file_mtime(
+            archives_dir.join("used.tgz"),
+            filetime::FileTime::from_system_time(old_time),
+        )?;
+
+        // Leaves room for the installed archive and one of the others
+        let max_size = (archive_size + 1500).to_string();
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "sui",
+                "--from-file",
+                archive.to_str().unwrap(),
+                "-y",
+            ],
+            &test_env,
+        );
+        cmd.env("SUIUP_MAX_CACHE_SIZE", &max_size);
+        cmd.assert().success();
+
+        // Without an index entry the modification time counts, so `used.tgz` is the oldest
+        assert!(!archives_dir.join("used.tgz").exists());
+        assert!(!archives_dir.join("used.tgz.lock").exists());
+        assert!(archives_dir.join("unused.tgz").exists());
+        assert!(
+            archives_dir
+                .join("sui-mainnet-v1.44.2-ubuntu-x86_64.tgz")
+                .exists()
+        );
+        let index: serde_json::Value = serde_json::from_slice(&fs::read(
+            test_env.cache_dir.join("suiup/archive_index.json"),
+        )?)?;
+        assert!(index["archives"]["sui-mainnet-v1.44.2-ubuntu-x86_64.tgz"].is_u64());
+
+        // Archives used in the last minutes might be extracted by another process right now
+        let mut cmd = suiup_command(vec!["cleanup", "--max-size", "1"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("0 files removed"));
+        assert!(archives_dir.join("unused.tgz").exists());
+
+        Ok(())
+    }
+
+    /// The directory with fake `cargo`, `rustc` and `rustup` executables. The fake
+    /// `cargo install` logs its arguments and target directory and creates the binary in
+    /// `--root`, or fails when `FAKE_CARGO_FAIL` is set. The fake `rustup` logs its arguments in
+    /// `rustup.log` and only has the stable toolchain until another one is installed.
+    #[cfg(not(windows))]
+    fn fake_rust_dir(test_env: &TestEnv) -> Result<std::path::PathBuf> {
+        let dir = test_env.temp_dir.path().join("fake_cargo");
+        let cargo = r#"#!/bin/sh
+if [ "$1" = "--version" ]; then echo "cargo 1.0.0"; exit 0; fi
+echo "args: $*"
+echo "   Compiling with $CARGO_TARGET_DIR" >&2
+if [ -n "$FAKE_CARGO_FAIL" ]; then echo "error: linking failed" >&2; exit 101; fi
+while [ $# -gt 0 ]; do
+  if [ "$1" = "--root" ]; then root="$2"; shift; elif [ -z "$root" ]; then bin="$1"; fi
+  shift
+done
+mkdir -p "$root/bin"
+printf '#!/bin/sh\necho "built"\n' > "$root/bin/$bin"
+chmod +x "$root/bin/$bin"
+"#;
+        let rustup = r#"#!/bin/sh
+dir="${0%/*}"
+echo "$*" >> "$dir/rustup.log"
+case "$1 $2" in
+  "toolchain list")
+    echo "stable-x86_64-unknown-linux-gnu (default)"
+    if [ -f "$dir/toolchains" ]; then
+      while IFS= read -r line; do echo "$line"; done < "$dir/toolchains"
+    fi ;;
+  "toolchain install") echo "$3-x86_64-unknown-linux-gnu" >> "$dir/toolchains" ;;
+  "component list") echo "cargo-x86_64-unknown-linux-gnu" ;;
+  *) echo "rustup 1.28.0" ;;
+esac
+"#;
+        write_scripts(
+            &dir,
+            &[
+                ("cargo", cargo),
+                ("rustc", "#!/bin/sh\necho \"rustc 1.0.0\"\n"),
+                ("rustup", rustup),
+            ],
+        )?;
+        Ok(dir)
+    }
+
+    /// Puts the fake Rust executables first in PATH, along with fake `clang`, `cmake` and
+    /// `pg_config` so that the system dependencies of all binaries are found
+    #[cfg(not(windows))]
+    fn fake_cargo_path(test_env: &TestEnv) -> Result<String> {
+        let rust_dir = fake_rust_dir(test_env)?;
+        let deps_dir = test_env.temp_dir.path().join("fake_deps");
+        let version = "#!/bin/sh\necho \"1.0.0\"\n";
+        write_scripts(
+            &deps_dir,
+            &[
+                ("clang", version),
+                ("cmake", version),
+                ("pg_config", version),
+            ],
+        )?;
+        Ok(format!(
+            "{}:{}:/usr/bin:/bin",
+            rust_dir.display(),
+            deps_dir.display()
+        ))
+    }
+
+    #[cfg(not(windows))]
+    fn write_scripts(dir: &std::path::Path, scripts: &[(&str, &str)]) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::create_dir_all(dir)?;
+        for (name, script) in scripts {
+            fs::write(dir.join(name), script)?;
+            fs::set_permissions(dir.join(name), fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_nightly_build_provisions_toolchain() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let path = fake_cargo_path(&test_env)?;
+        let rust_dir = test_env.temp_dir.path().join("fake_cargo");
+
+        // ledger-signer is built with the nightly toolchain, which is only installed when agreed
+        let mut cmd = suiup_command(vec!["install", "ledger-signer", "--nightly"], &test_env);
+        cmd.env("PATH", &path);
+        cmd.assert().failure().stderr(predicate::str::contains(
+            "The build requires the Rust nightly toolchain",
+        ));
+
+        let mut cmd = suiup_command(
+            vec!["install", "ledger-signer", "--nightly", "-y"],
+            &test_env,
+        );
+        cmd.env("PATH", &path);
+        cmd.assert().success();
+        let rustup_log = fs::read_to_string(rust_dir.join("rustup.log"))?;
+        assert!(rustup_log.contains("toolchain install nightly --profile minimal"));
+        let logs: Vec<_> =
+            fs::read_dir(test_env.cache_dir.join("suiup/build_logs"))?.collect::<Result<_, _>>()?;
+        let log = fs::read_to_string(logs[0].path())?;
+        assert!(log.contains("args: +nightly install --locked"));
+
+        // Missing system dependencies are reported before building, with how to install them
+        let mut cmd = suiup_command(vec!["install", "sui", "--nightly", "-y"], &test_env);
+        cmd.env("PATH", rust_dir.as_os_str());
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Building sui requires these missing dependencies",
+            ))
+            .stderr(predicate::str::contains("clang:"))
+            .stderr(predicate::str::contains("libpq:"));
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_nightly_build_cache_and_logs() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let path = fake_cargo_path(&test_env)?;
+        let target_dir = test_env.cache_dir.join("suiup/builds/MystenLabs_mvr");
+        let logs_dir = test_env.cache_dir.join("suiup/build_logs");
+
+        let mut cmd = suiup_command(vec!["install", "mvr", "--nightly", "-y"], &test_env);
+        cmd.env("PATH", &path);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Build log:"));
+        assert!(target_dir.is_dir());
+        let logs: Vec<_> = fs::read_dir(&logs_dir)?.collect::<Result<_, _>>()?;
+        assert_eq!(logs.len(), 1);
+        let log = fs::read_to_string(logs[0].path())?;
+        assert!(log.contains(&format!("Compiling with {}", target_dir.display())));
+        assert!(log.contains("install --locked --force --git https://github.com/MystenLabs/mvr"));
+
+        // A build with other cargo settings is installed next to the previous build of the branch
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "mvr",
+                "--nightly",
+                "--features",
+                "tracing",
+                "--profile",
+                "dev-assert",
+                "--cargo-arg=--jobs",
+                "--cargo-arg=2",
+                "-y",
+            ],
+            &test_env,
+        );
+        cmd.env("PATH", &path);
+        cmd.assert().success();
+        let mut logs: Vec<_> = fs::read_dir(&logs_dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        logs.sort();
+        let log = fs::read_to_string(logs.last().unwrap())?;
+        assert!(log.contains("--features tracing --profile dev-assert --jobs 2"));
+
+        let mut cmd = suiup_command(vec!["show"], &test_env);
+        cmd.assert().success().stdout(predicate::str::contains(
+            "features: tracing; profile: dev-assert; cargo args: --jobs 2",
+        ));
+        let installed =
+            fs::read_to_string(test_env.config_dir.join("suiup/installed_binaries.json"))?;
+        assert_eq!(installed.matches("\"nightly\"").count(), 1);
+        assert_eq!(installed.matches("\"nightly-").count(), 1);
+        let bin_dir = test_env.data_dir.join("suiup/binaries/main/bin");
+        let builds: Vec<_> = fs::read_dir(&bin_dir)?
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<_, _>>()?;
+        assert!(builds.iter().any(|name| name == "mvr-nightly"));
+        assert!(builds.iter().any(|name| name.starts_with("mvr-nightly-")));
+
+        let mut cmd = suiup_command(vec!["install", "mvr", "--nightly", "-y"], &test_env);
+        cmd.env("PATH", &path).env("FAKE_CARGO_FAIL", "1");
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("error: linking failed"))
+            .stderr(predicate::str::contains("The full build log is at"));
+
+        fs::write(target_dir.join("artifact"), vec![0u8; 1000])?;
+        let mut cmd = suiup_command(vec!["cleanup", "--build-cache", "--dry-run"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Would remove"));
+        assert!(target_dir.exists());
+
+        // The target directory and the logs of a running build are kept
+        let lock = suiup::lock::FileLock::try_acquire(&suiup::lock::build_lock_file(&target_dir))?
+            .expect("no build is running");
+        fs::write(logs_dir.join("walrus-main-2024-02-29T123456.log"), "log")?;
+        let mut cmd = suiup_command(vec!["cleanup", "--build-cache"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("its build is running"));
+        assert!(target_dir.exists());
+        let logs: Vec<_> = fs::read_dir(&logs_dir)?.collect::<Result<_, _>>()?;
+        assert!(!logs.is_empty());
+        assert!(
+            logs.iter()
+                .all(|log| log.file_name().to_string_lossy().starts_with("mvr-"))
+        );
+        drop(lock);
+
+        let mut cmd = suiup_command(vec!["cleanup", "--build-cache"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Cleanup complete"));
+        assert!(!target_dir.exists());
+        assert_eq!(fs::read_dir(&logs_dir)?.count(), 0);
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_build_from_source_fallback() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let path = fake_cargo_path(&test_env)?;
+
+        // The release only has an archive for another platform
+        let cache_dir = test_env.cache_dir.join("suiup");
+        let name = "sui-testnet-v1.44.2-plan9-riscv.tgz";
+        fs::write(
+            cache_dir.join("releases_MystenLabs_sui.txt"),
+            format!(
+                r#"[{{"assets":[{{"name":"{name}","browser_download_url":"https://example.invalid/{name}"}}]}}]"#
+            ),
+        )?;
+        fs::write(cache_dir.join("etag_MystenLabs_sui.txt"), "etag")?;
+
+        let mut cmd = suiup_command(vec!["install", "sui@testnet-1.44.2", "-y"], &test_env);
+        cmd.env("PATH", &path);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("Asset not found for"));
+
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "sui@testnet-1.44.2",
+                "--build-from-source-fallback",
+                "-y",
+            ],
+            &test_env,
+        );
+        cmd.env("PATH", &path);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("building sui v1.44.2 from source"));
+
+        let logs: Vec<_> = fs::read_dir(cache_dir.join("build_logs"))?.collect::<Result<_, _>>()?;
+        let log = fs::read_to_string(logs[0].path())?;
+        assert!(log.contains("--git https://github.com/MystenLabs/sui --tag testnet-v1.44.2 sui"));
+        assert!(
+            test_env
+                .data_dir
+                .join("suiup/binaries/testnet/sui-v1.44.2")
+                .exists()
+        );
+
+        let mut cmd = suiup_command(vec!["show"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("v1.44.2"))
+            .stdout(predicate::str::contains("nightly").not());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_registry_user_definitions() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let user_dir = test_env.config_dir.join("suiup/binaries");
+        fs::create_dir_all(&user_dir)?;
+        let definition = |name: &str| {
+            format!(
+                r#"name = "{name}"
+description = "Internal Move tool"
+repository = "example/{name}"
+installation_type = "standalone"
+asset_pattern = "{{name}}-{{os}}-{{arch}}*"
+"#
+            )
+        };
+        fs::write(user_dir.join("move-lint.toml"), definition("move-lint"))?;
+        fs::write(user_dir.join("sui.toml"), definition("sui"))?;
+
+        let mut cmd = suiup_command(vec!["registry", "list"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Internal Move tool"))
+            .stdout(predicate::str::contains(
+                user_dir
+                    .join("move-lint.toml")
+                    .to_string_lossy()
+                    .to_string(),
+            ))
+            .stdout(predicate::str::contains("built-in"))
+            .stderr(predicate::str::contains("`sui` is a built-in binary"));
+
+        let mut cmd = suiup_command(vec!["list"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("move-lint"));
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_install_from_filesystem_release_source() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let (os, arch) = detect_os_arch_for_tests();
+
+        // A mirror with a folder of assets per release tag
+        let mirror = test_env.temp_dir.path().join("mirror");
+        for version in ["1.1.0", "1.2.0"] {
+            let tag_dir = mirror.join(format!("testnet-v{version}"));
+            fs::create_dir_all(&tag_dir)?;
+            create_release_archive(
+                &tag_dir,
+                &format!("mirror-tool-testnet-v{version}-{os}-{arch}.tgz"),
+                &["mirror-tool"],
+                version,
+            )?;
+        }
+        let cli_mirror = test_env.temp_dir.path().join("cli-mirror");
+        fs::create_dir_all(cli_mirror.join("v0.3.0"))?;
+        fs::write(
+            cli_mirror.join(format!("v0.3.0/mirror-cli-{os}-{arch}")),
+            "#!/bin/sh\necho mirror-cli 0.3.0\n",
+        )?;
+
+        let user_dir = test_env.config_dir.join("suiup/binaries");
+        fs::create_dir_all(&user_dir)?;
+        fs::write(
+            user_dir.join("mirror-tool.toml"),
+            format!(
+                r#"name = "mirror-tool"
+description = "Tool from a mirror"
+repository = "example/mirror-tool"
+installation_type = "archive"
+network_based = true
+supported_networks = ["testnet"]
+asset_pattern = "{{name}}-{{network}}-{{version}}-{{os}}-{{arch}}.tgz"
+release_source = {{ type = "filesystem", path = '{}' }}
+"#,
+                mirror.display()
+            ),
+        )?;
+        fs::write(
+            user_dir.join("mirror-cli.toml"),
+            format!(
+                r#"name = "mirror-cli"
+description = "CLI from a mirror"
+repository = "example/mirror-cli"
+installation_type = "standalone"
+asset_pattern = "{{name}}-{{os}}-{{arch}}*"
+release_source = {{ type = "filesystem", path = '{}' }}
+"#,
+                cli_mirror.display()
+            ),
+        )?;
+
+        let mut cmd = suiup_command(vec!["install", "mirror-tool", "-y"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Last testnet release: v1.2.0"));
+        assert!(
+            test_env
+                .data_dir
+                .join("suiup/binaries/testnet/mirror-tool-v1.2.0")
+                .exists()
+        );
+
+        let mut cmd = suiup_command(
+            vec!["install", "mirror-tool@testnet-1.1.0", "-y"],
+            &test_env,
+        );
+        cmd.assert().success();
+        assert!(
+            test_env
+                .data_dir
+                .join("suiup/binaries/testnet/mirror-tool-v1.1.0")
+                .exists()
+        );
+
+        let mut cmd = suiup_command(vec!["install", "mirror-cli", "-y"], &test_env);
+        cmd.assert().success();
+        assert!(
+            test_env
+                .data_dir
+                .join("suiup/binaries/standalone/mirror-cli-v0.3.0")
+                .exists()
+        );
+
+        let mut cmd = suiup_command(
+            vec!["install", "mirror-tool@testnet-1.3.0", "-y"],
+            &test_env,
+        );
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("Release testnet-v1.3.0 not found"));
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_install_skips_prereleases() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let (os, arch) = detect_os_arch_for_tests();
+
+        let releases = test_env.temp_dir.path().join("releases");
+        for version in ["1.2.0", "1.3.0"] {
+            let tag_dir = releases.join(format!("testnet-v{version}"));
+            fs::create_dir_all(&tag_dir)?;
+            create_release_archive(
+                &tag_dir,
+                &format!("pre-tool-testnet-v{version}-{os}-{arch}.tgz"),
+                &["pre-tool"],
+                version,
+            )?;
+        }
+        fs::write(
+            releases.join("releases.json"),
+            format!(
+                r#"{{"releases": [
+                    {{"tag_name": "testnet-v1.3.0", "prerelease": true,
+                      "assets": [{{"name": "pre-tool-testnet-v1.3.0-{os}-{arch}.tgz"}}]}},
+                    {{"tag_name": "testnet-v1.2.0", "published_at": "2025-03-04T17:21:08Z",
+                      "assets": [{{"name": "pre-tool-testnet-v1.2.0-{os}-{arch}.tgz"}}]}}
+                ]}}"#
+            ),
+        )?;
+        let user_dir = test_env.config_dir.join("suiup/binaries");
+        fs::create_dir_all(&user_dir)?;
+        fs::write(
+            user_dir.join("pre-tool.toml"),
+            format!(
+                r#"name = "pre-tool"
+description = "Tool with pre-releases"
+repository = "example/pre-tool"
+installation_type = "archive"
+network_based = true
+supported_networks = ["testnet"]
+asset_pattern = "{{name}}-{{network}}-{{version}}-{{os}}-{{arch}}.tgz"
+release_source = {{ type = "filesystem", path = '{}' }}
+"#,
+                releases.display()
+            ),
+        )?;
+
+        let mut cmd = suiup_command(vec!["install", "pre-tool", "-y"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Last testnet release: v1.2.0"));
+
+        let mut cmd = suiup_command(vec!["install", "pre-tool@testnet-1.3.0", "-y"], &test_env);
+        cmd.assert().failure().stderr(predicate::str::contains(
+            "Release testnet-v1.3.0 is a pre-release. Use --pre to install pre-releases",
+        ));
+
+        let mut cmd = suiup_command(vec!["install", "pre-tool", "--pre", "-y"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Last testnet release: v1.3.0"));
+        let pre_release = test_env
+            .data_dir
+            .join("suiup/binaries/testnet/pre-tool-v1.3.0");
+        assert!(pre_release.exists());
+
+        // Undoing the removal of a pre-release installs it again
+        let mut cmd = suiup_command(vec!["remove", "pre-tool"], &test_env);
+        cmd.assert().success();
+        assert!(!pre_release.exists());
+        let mut cmd = suiup_command(vec!["undo", "-y"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Undo completed"));
+        assert!(pre_release.exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_remote_versions() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let (os, arch) = detect_os_arch_for_tests();
+
+        let releases = test_env.temp_dir.path().join("releases");
+        for (tag, asset) in [
+            (
+                "testnet-v1.2.0",
+                format!("list-tool-testnet-v1.2.0-{os}-{arch}.tgz"),
+            ),
+            (
+                "testnet-v1.3.0",
+                format!("list-tool-testnet-v1.3.0-{os}-{arch}.tgz"),
+            ),
+            (
+                "devnet-v1.4.0",
+                "list-tool-devnet-v1.4.0-plan9-mips.tgz".to_string(),
+            ),
+        ] {
+            let tag_dir = releases.join(tag);
+            fs::create_dir_all(&tag_dir)?;
+            create_release_archive(&tag_dir, &asset, &["list-tool"], "1.2.0")?;
+        }
+        // Downloads are checked against the size in the manifest
+        let size = fs::metadata(releases.join(format!(
+            "testnet-v1.2.0/list-tool-testnet-v1.2.0-{os}-{arch}.tgz"
+        )))?
+        .len();
+        fs::write(
+            releases.join("releases.json"),
+            format!(
+                r#"{{"releases": [
+                    {{"tag_name": "devnet-v1.4.0",
+                      "assets": [{{"name": "list-tool-devnet-v1.4.0-plan9-mips.tgz"}}]}},
+                    {{"tag_name": "testnet-v1.3.0", "prerelease": true,
+                      "assets": [{{"name": "list-tool-testnet-v1.3.0-{os}-{arch}.tgz"}}]}},
+                    {{"tag_name": "testnet-v1.2.0", "published_at": "2025-03-04T17:21:08Z",
+                      "assets": [{{"name": "list-tool-testnet-v1.2.0-{os}-{arch}.tgz",
+                                   "size": {size}}}]}}
+                ]}}"#
+            ),
+        )?;
+        let user_dir = test_env.config_dir.join("suiup/binaries");
+        fs::create_dir_all(&user_dir)?;
+        fs::write(
+            user_dir.join("list-tool.toml"),
+            format!(
+                r#"name = "list-tool"
+description = "Tool with releases on several networks"
+repository = "example/list-tool"
+installation_type = "archive"
+network_based = true
+supported_networks = ["testnet", "devnet"]
+asset_pattern = "{{name}}-{{network}}-{{version}}-{{os}}-{{arch}}.tgz"
+release_source = {{ type = "filesystem", path = '{}' }}
+"#,
+                releases.display()
+            ),
+        )?;
+
+        let mut cmd = suiup_command(vec!["install", "list-tool", "-y"], &test_env);
+        cmd.assert().success();
+
+        let mut cmd = suiup_command(vec!["list", "--remote", "list-tool"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("v1.4.0"))
+            .stdout(predicate::str::contains("pre-release"))
+            .stdout(predicate::str::contains("2025-03-04"))
+            .stdout(predicate::str::contains(
+                indicatif::HumanBytes(size).to_string(),
+            ))
+            .stdout(predicate::str::contains("default"));
+
+        let mut cmd = suiup_command(
+            vec![
+                "list",
+                "--remote",
+                "list-tool",
+                "--network",
+                "testnet",
+                "--limit",
+                "1",
+            ],
+            &test_env,
+        );
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("v1.3.0"))
+            .stdout(predicate::str::contains("v1.4.0").not())
+            .stdout(predicate::str::contains("Showing 1 of 2 versions"));
+
+        let mut cmd = suiup_command(
+            vec!["list", "--remote", "list-tool", "--network", "mainnet"],
+            &test_env,
+        );
+        cmd.assert().failure().stderr(predicate::str::contains(
+            "list-tool is not released for mainnet. Available networks: testnet, devnet",
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_changelog_between_versions() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let (os, arch) = detect_os_arch_for_tests();
+
+        let releases = test_env.temp_dir.path().join("releases");
+        for version in ["1.2.0", "1.3.0", "1.4.0"] {
+            let tag_dir = releases.join(format!("testnet-v{version}"));
+            fs::create_dir_all(&tag_dir)?;
+            create_release_archive(
+                &tag_dir,
+                &format!("notes-tool-testnet-v{version}-{os}-{arch}.tgz"),
+                &["notes-tool"],
+                version,
+            )?;
+        }
+        fs::write(
+            releases.join("releases.json"),
+            format!(
+                r###"{{"releases": [
+                    {{"tag_name": "testnet-v1.4.0", "body": "## Breaking changes\n- Renamed the config file",
+                      "assets": [{{"name": "notes-tool-testnet-v1.4.0-{os}-{arch}.tgz"}}]}},
+                    {{"tag_name": "testnet-v1.3.0", "body": "- Faster startup",
+                      "assets": [{{"name": "notes-tool-testnet-v1.3.0-{os}-{arch}.tgz"}}]}},
+                    {{"tag_name": "testnet-v1.2.0", "body": "- First release",
+                      "assets": [{{"name": "notes-tool-testnet-v1.2.0-{os}-{arch}.tgz"}}]}}
+                ]}}"###
+            ),
+        )?;
+        let user_dir = test_env.config_dir.join("suiup/binaries");
+        fs::create_dir_all(&user_dir)?;
+        fs::write(
+            user_dir.join("notes-tool.toml"),
+            format!(
+                r#"name = "notes-tool"
+description = "Tool with release notes"
+repository = "example/notes-tool"
+installation_type = "archive"
+network_based = true
+supported_networks = ["testnet"]
+asset_pattern = "{{name}}-{{network}}-{{version}}-{{os}}-{{arch}}.tgz"
+release_source = {{ type = "filesystem", path = '{}' }}
+"#,
+                releases.display()
+            ),
+        )?;
+
+        let mut cmd = suiup_command(vec!["changelog", "notes-tool@testnet"], &test_env);
+        cmd.assert().failure().stderr(predicate::str::contains(
+            "notes-tool is not installed for testnet. Use --from",
+        ));
+
+        let mut cmd = suiup_command(vec!["install", "notes-tool@testnet-1.2.0", "-y"], &test_env);
+        cmd.assert().success();
+
+        let mut cmd = suiup_command(vec!["changelog", "notes-tool@testnet"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("from v1.2.0 to v1.4.0"))
+            .stdout(predicate::str::contains("Renamed the config file"))
+            .stdout(predicate::str::contains("Faster startup"))
+            .stdout(predicate::str::contains("First release").not());
+
+        let mut cmd = suiup_command(
+            vec![
+                "changelog",
+                "notes-tool@testnet",
+                "--from",
+                "1.3.0",
+                "--to",
+                "latest",
+            ],
+            &test_env,
+        );
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Renamed the config file"))
+            .stdout(predicate::str::contains("Faster startup").not());
+
+        let mut cmd = suiup_command(vec!["status", "--changelog"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Breaking changes"))
+            .stdout(predicate::str::contains("Faster startup"));
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_mirror_sync_and_serve() -> Result<()> {
+        use std::io::{BufRead, BufReader};
+        use std::process::Stdio;
+
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let (os, arch) = detect_os_arch_for_tests();
+
+        let releases = test_env.temp_dir.path().join("releases");
+        for version in ["1.1.0", "1.2.0"] {
+            let tag_dir = releases.join(format!("testnet-v{version}"));
+            fs::create_dir_all(&tag_dir)?;
+            create_release_archive(
+                &tag_dir,
+                &format!("mirror-tool-testnet-v{version}-{os}-{arch}.tgz"),
+                &["mirror-tool"],
+                version,
+            )?;
+        }
+        let user_dir = test_env.config_dir.join("suiup/binaries");
+        fs::create_dir_all(&user_dir)?;
+        let definition = r#"name = "mirror-tool"
+description = "Tool from a mirror"
+repository = "example/mirror-tool"
+installation_type = "archive"
+network_based = true
+supported_networks = ["testnet"]
+asset_pattern = "{name}-{network}-{version}-{os}-{arch}.tgz"
+"#;
+        fs::write(
+            user_dir.join("mirror-tool.toml"),
+            format!(
+                "{definition}release_source = {{ type = \"filesystem\", path = '{}' }}\n",
+                releases.display()
+            ),
+        )?;
+
+        let mirror = test_env.temp_dir.path().join("mirror");
+        let platforms = format!("{os}-{arch},windows-x86_64");
+        let sync = vec![
+            "mirror",
+            "sync",
+            "--dir",
+            mirror.to_str().unwrap(),
+            "--binaries",
+            "mirror-tool",
+            "--networks",
+            "testnet,mainnet",
+            "--platforms",
+            &platforms,
+            "--releases",
+            "2",
+        ];
+        let mut cmd = suiup_command(sync.clone(), &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "No mirror-tool asset for windows-x86_64 in testnet-v1.2.0",
+            ))
+            .stdout(predicate::str::contains("2 downloaded, 0 already mirrored"));
+        let repository = mirror.join("example/mirror-tool");
+        assert!(
+            repository
+                .join(format!(
+                    "testnet-v1.1.0/mirror-tool-testnet-v1.1.0-{os}-{arch}.tgz"
+                ))
+                .exists()
+        );
+        let manifest = fs::read_to_string(repository.join("releases.json"))?;
+        assert!(manifest.find("testnet-v1.2.0") < manifest.find("testnet-v1.1.0"));
+
+        let mut cmd = suiup_command(sync, &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("0 downloaded, 2 already mirrored"));
+
+        // Clients download the GitHub releases of the binary from the mirror
+        fs::write(user_dir.join("mirror-tool.toml"), definition)?;
+        let mut server = std::process::Command::new(env!("CARGO_BIN_EXE_suiup"))
+            .args(["mirror", "serve", "--address", "127.0.0.1", "--port", "0"])
+            .arg("--dir")
+            .arg(&mirror)
+            .env(DATA_HOME, &test_env.data_dir)
+            .env(CONFIG_HOME, &test_env.config_dir)
+            .env(CACHE_HOME, &test_env.cache_dir)
+            .stdout(Stdio::piped())
+            .spawn()?;
+        // The server logs every request, so its output is kept open until it is stopped
+        let mut output = BufReader::new(server.stdout.take().unwrap());
+        let mut line = String::new();
+        output.read_line(&mut line)?;
+        let url = line
+            .split_whitespace()
+            .last()
+            .unwrap_or_default()
+            .to_string();
+
+        let mut cmd = suiup_command(vec!["install", "mirror-tool", "-y"], &test_env);
+        cmd.env("SUIUP_GITHUB_API_URL", &url);
+        let result = cmd.assert();
+        server.kill()?;
+        result
+            .success()
+            .stdout(predicate::str::contains("Last testnet release: v1.2.0"));
+        assert!(
+            test_env
+                .data_dir
+                .join("suiup/binaries/testnet/mirror-tool-v1.2.0")
+                .exists()
+        );
+
+        Ok(())
+    }
+
+    /// Serves the JSON `body` with an ETag at every path of a local port, answering 304 when the
+    /// request carries that ETag. Returns the base URL and the number of 304 answers.
+    fn serve_json(
+        body: String,
+    ) -> Result<(String, std::sync::Arc<std::sync::atomic::AtomicUsize>)> {
+        use std::io::{BufRead, BufReader, Write};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        let counter = not_modified.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut cached = false;
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 0) && line != "\r\n" {
+                    let lower = line.to_lowercase();
+                    cached |= lower.starts_with("if-none-match:") && lower.contains("\"v1\"");
+                    line.clear();
+                }
+                let response = if cached {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                };
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        Ok((url, not_modified))
+    }
+
+    #[tokio::test]
+    async fn test_release_cache_without_metadata_is_refreshed() -> Result<()> {
+        use std::sync::atomic::Ordering;
+
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let (os, arch) = detect_os_arch_for_tests();
+        let body = format!(
+            r#"[
+                {{"tag_name": "testnet-v1.3.0", "prerelease": true,
+                  "assets": [{{"name": "cache-tool-testnet-v1.3.0-{os}-{arch}.tgz",
+                               "browser_download_url": "http://localhost/a.tgz"}}]}},
+                {{"tag_name": "testnet-v1.2.0", "published_at": "2025-03-04T17:21:08Z",
+                  "assets": [{{"name": "cache-tool-testnet-v1.2.0-{os}-{arch}.tgz",
+                               "browser_download_url": "http://localhost/b.tgz"}}]}}
+            ]"#
+        );
+        let (api_url, not_modified) = serve_json(body)?;
+        let user_dir = test_env.config_dir.join("suiup/binaries");
+        fs::create_dir_all(&user_dir)?;
+        fs::write(
+            user_dir.join("cache-tool.toml"),
+            r#"name = "cache-tool"
+description = "Tool with a release list cached by an older suiup"
+repository = "example/cache-tool"
+installation_type = "archive"
+network_based = true
+supported_networks = ["testnet"]
+asset_pattern = "{name}-{network}-{version}-{os}-{arch}.tgz"
+"#,
+        )?;
+
+        // A release list cached before release metadata was stored, with a matching ETag
+        let cache_dir = test_env.cache_dir.join("suiup");
+        fs::create_dir_all(&cache_dir)?;
+        fs::write(
+            cache_dir.join("releases_example_cache-tool.txt"),
+            format!(
+                r#"[{{"assets": [{{"name": "cache-tool-testnet-v1.3.0-{os}-{arch}.tgz",
+                                   "browser_download_url": "http://localhost/a.tgz"}}]}}]"#
+            ),
+        )?;
+        fs::write(cache_dir.join("etag_example_cache-tool.txt"), "\"v1\"")?;
+
+        let list = || {
+            let mut cmd = suiup_command(vec!["list", "--remote", "cache-tool"], &test_env);
+            cmd.env("SUIUP_GITHUB_API_URL", &api_url);
+            cmd
+        };
+        list()
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("v1.2.0"))
+            .stdout(predicate::str::contains("2025-03-04"))
+            .stdout(predicate::str::contains("pre-release"));
+        assert_eq!(not_modified.load(Ordering::SeqCst), 0);
+        let cached = fs::read_to_string(cache_dir.join("releases_example_cache-tool.txt"))?;
+        assert!(cached.contains("testnet-v1.2.0"));
+
+        // The refreshed cache has release metadata and is revalidated with its ETag
+        list()
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("2025-03-04"));
+        assert_eq!(not_modified.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_registry_remote_index() -> Result<()> {
+        use base64::Engine;
+        use base64::engine::general_purpose::STANDARD as BASE64;
+        use ring::rand::SystemRandom;
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+        use std::sync::atomic::Ordering;
+
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let generate = || {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+            Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+        };
+        let registry_key = generate();
+        let other_key = generate();
+        let unknown_key = generate();
+        let definition = |name: &str| {
+            format!(
+                r#"name = "{name}"
+description = "Community Move tool"
+repository = "example/{name}"
+installation_type = "standalone"
+asset_pattern = "{{name}}-{{os}}-{{arch}}*"
+"#
+            )
+        };
+        let entry = |key: &Ed25519KeyPair, definition: String| {
+            serde_json::json!({
+                "definition": definition,
+                "signature": BASE64.encode(key.sign(definition.as_bytes())),
+            })
+        };
+        let index = serde_json::json!({
+            "binaries": [
+                entry(&registry_key, definition("move-fmt")),
+                entry(&other_key, definition("move-evil")),
+                entry(&registry_key, definition("sui")),
+            ]
+        });
+        let (url, not_modified) = serve_json(index.to_string())?;
+        let url = format!("{url}/index.json");
+        let public_key = BASE64.encode(registry_key.public_key());
+
+        // A key that signs none of the entries is rejected
+        let wrong_key = BASE64.encode(unknown_key.public_key());
+        let mut cmd = suiup_command(
+            vec!["registry", "add", &url, "--public-key", &wrong_key],
+            &test_env,
+        );
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("signed with the given public key"));
+
+        let mut cmd = suiup_command(
+            vec!["registry", "add", &url, "--public-key", &public_key],
+            &test_env,
+        );
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("with 2 binary definitions"))
+            .stdout(predicate::str::contains(
+                "sui (ignored, already defined by built-in)",
+            ))
+            .stderr(predicate::str::contains("Skipping `move-evil`"));
+
+        let mut cmd = suiup_command(
+            vec!["registry", "add", &url, "--public-key", &public_key],
+            &test_env,
+        );
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("Already subscribed"));
+
+        let mut cmd = suiup_command(vec!["registry", "list"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("move-fmt"))
+            .stdout(predicate::str::contains(url.as_str()))
+            .stdout(predicate::str::contains("move-evil").not());
+
+        // The cached index is refreshed with its ETag
+        let mut cmd = suiup_command(vec!["registry", "update"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("is up to date"));
+        assert_eq!(not_modified.load(Ordering::SeqCst), 1);
+
+        // A tampered cache is not trusted
+        let cache_dir = test_env.cache_dir.join("suiup/registries");
+        for file in fs::read_dir(&cache_dir)? {
+            let path = file?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let content = fs::read_to_string(&path)?.replace("move-fmt", "move-fmx");
+                fs::write(&path, content)?;
+            }
+        }
+        let mut cmd = suiup_command(vec!["list"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("move-fm").not())
+            .stderr(predicate::str::contains(
+                "signature does not match the registry public key",
+            ));
+
+        let mut cmd = suiup_command(vec!["registry", "remove", &url], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Removed registry"));
+        assert_eq!(fs::read_dir(&cache_dir)?.count(), 0);
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_doctor_fix() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let archive = create_release_archive(
+            test_env.temp_dir.path(),
+            "sui-mainnet-v1.44.2-ubuntu-x86_64.tgz",
+            &["sui"],
+            "1.44.2",
+        )?;
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "sui",
+                "--from-file",
+                archive.to_str().unwrap(),
+                "-y",
+            ],
+            &test_env,
+        );
+        cmd.assert().success();
+
+        // A default binary that lost its permissions and one whose binary is gone
+        let default_binary = test_env.bin_dir.join("sui");
+        fs::set_permissions(&default_binary, fs::Permissions::from_mode(0o644))?;
+        let defaults_file = test_env.config_dir.join("suiup/default_version.json");
+        let mut defaults: serde_json::Value = serde_json::from_slice(&fs::read(&defaults_file)?)?;
+        defaults["binaries"]["mvr"] = serde_json::json!({"network_release": "standalone", "version": "v0.0.5", "debug": false});
+        fs::write(&defaults_file, serde_json::to_vec(&defaults)?)?;
+
+        let mut cmd = suiup_command(vec!["doctor"], &test_env);
+        cmd.env("PATH", "/usr/bin:/bin").env("SHELL", "/bin/bash");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("is not executable"))
+            .stdout(predicate::str::contains("Default mvr refers to"))
+            .stdout(predicate::str::contains(
+                "Run `suiup doctor --fix` to fix 3",
+            ));
+        assert_eq!(
+            fs::metadata(&default_binary)?.permissions().mode() & 0o777,
+            0o644
+        );
+
+        let mut cmd = suiup_command(vec!["doctor", "--fix", "-y"], &test_env);
+        cmd.env("PATH", "/usr/bin:/bin").env("SHELL", "/bin/bash");
+        cmd.assert().success().stdout(predicate::str::contains(
+            "Remove the dangling default binary mvr",
+        ));
+
+        assert_eq!(
+            fs::metadata(&default_binary)?.permissions().mode() & 0o777,
+            0o755
+        );
+        let defaults: serde_json::Value = serde_json::from_slice(&fs::read(&defaults_file)?)?;
+        assert!(defaults["binaries"].get("mvr").is_none());
+        assert!(defaults["binaries"].get("sui").is_some());
+        // The default binary starts again and reports the installed version
+        let mut cmd = suiup_command(vec!["doctor"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Default sui is v1.44.2@mainnet"))
+            .stdout(predicate::str::contains(
+                "Installed sui-v1.44.2 [mainnet] is a script",
+            ));
+        fs::write(&default_binary, "#!/bin/sh\necho \"sui 1.45.0\"\n")?;
+        let mut cmd = suiup_command(vec!["doctor"], &test_env);
+        cmd.assert().success().stdout(predicate::str::contains(
+            "reports `sui 1.45.0`, but v1.44.2 is installed",
+        ));
+
+        let bashrc = fs::read_to_string(test_env.temp_dir.path().join(".bashrc"))?;
+        assert!(bashrc.contains(&format!(
+            "export PATH=\"{}:$PATH\"",
+            test_env.temp_dir.path().join(".local/bin").display()
+        )));
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_history_and_undo() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let mut cmd = suiup_command(vec!["history"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("No operations recorded yet"));
+
+        for version in ["1.44.2", "1.45.0"] {
+            let archive = create_release_archive(
+                test_env.temp_dir.path(),
+                &format!("sui-mainnet-v{version}-ubuntu-x86_64.tgz"),
+                &["sui"],
+                version,
+            )?;
+            let mut cmd = suiup_command(
+                vec![
+                    "install",
+                    "sui",
+                    "--from-file",
+                    archive.to_str().unwrap(),
+                    "-y",
+                ],
+                &test_env,
+            );
+            cmd.assert().success();
+        }
+
+        let mut cmd = suiup_command(vec!["history"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("#2"))
+            .stdout(predicate::str::contains("+ sui-v1.45.0 [mainnet]"))
+            .stdout(predicate::str::contains(
+                "~ default sui: v1.44.2@mainnet -> v1.45.0@mainnet",
+            ))
+            .stdout(predicate::str::contains("#1"))
+            .stdout(predicate::str::contains(
+                "~ default sui: none -> v1.44.2@mainnet",
+            ));
+
+        let mut cmd = suiup_command(vec!["undo", "-y"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Undo completed"));
+
+        let binaries_dir = test_env.data_dir.join("suiup/binaries/mainnet");
+        assert!(!binaries_dir.join("sui-v1.45.0").exists());
+        assert_eq!(
+            fs::read(test_env.bin_dir.join("sui"))?,
+            fs::read(binaries_dir.join("sui-v1.44.2"))?
+        );
+        let mut cmd = suiup_command(vec!["default", "get"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("v1.44.2"))
+            .stdout(predicate::str::contains("v1.45.0").not());
+
+        // The undo is an operation as well
+        let mut cmd = suiup_command(vec!["history", "--limit", "1"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("#3"))
+            .stdout(predicate::str::contains("suiup undo -y"))
+            .stdout(predicate::str::contains("#2").not());
+
+        let mut cmd = suiup_command(vec!["undo", "5", "-y"], &test_env);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("the history has 3"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_state_files_are_migrated() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        let state_dir = test_env.config_dir.join("suiup");
+        fs::create_dir_all(&state_dir)?;
+
+        // State files as written by suiup versions without a schema version
+        let installed_file = state_dir.join("installed_binaries.json");
+        let default_file = state_dir.join("default_version.json");
+        let installed_v1 = r#"{"binaries":[{"binary_name":"sui","network_release":"testnet","version":"v1.44.2","debug":false,"path":null}]}"#;
+        let default_v1 = r#"{"sui":["testnet","v1.44.2",false]}"#;
+        fs::write(&installed_file, installed_v1)?;
+        fs::write(&default_file, default_v1)?;
+
+        let mut cmd = suiup_command(vec!["default", "get"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Upgraded"))
+            .stdout(predicate::str::contains("v1.44.2"));
+
+        let installed: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&installed_file)?)?;
+        assert_eq!(installed["schema_version"], 2);
+        assert_eq!(installed["binaries"][0]["binary_name"], "sui");
+        let default: serde_json::Value = serde_json::from_str(&fs::read_to_string(&default_file)?)?;
+        assert_eq!(default["schema_version"], 2);
+        assert_eq!(default["binaries"]["sui"]["network_release"], "testnet");
+
+        assert_eq!(
+            fs::read_to_string(state_dir.join("installed_binaries.json.v1.bak"))?,
+            installed_v1
+        );
+        assert_eq!(
+            fs::read_to_string(state_dir.join("default_version.json.v1.bak"))?,
+            default_v1
+        );
+
+        // Upgraded files are left alone on the next run
+        let mut cmd = suiup_command(vec!["default", "get"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Upgraded").not());
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_install_waits_for_state_lock() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let archive = create_release_archive(
+            test_env.temp_dir.path(),
+            "sui-mainnet-v1.44.2-ubuntu-x86_64.tgz",
+            &["sui"],
+            "1.44.2",
+        )?;
+        let install_args = vec![
+            "install",
+            "sui",
+            "--from-file",
+            archive.to_str().unwrap(),
+            "-y",
+        ];
+
+        // Simulate another suiup process holding the state lock
+        let lock_file = fs::File::create(test_env.data_dir.join("suiup/suiup.lock"))?;
+        lock_file.lock()?;
+
+        let mut cmd = suiup_command(install_args.clone(), &test_env);
+        cmd.env("SUIUP_LOCK_TIMEOUT", "1");
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Waiting for another suiup process",
+            ))
+            .stderr(predicate::str::contains("Timed out after 1s"));
+        assert!(
+            !test_env
+                .data_dir
+                .join("suiup/binaries/mainnet/sui-v1.44.2")
+                .exists()
+        );
+
+        lock_file.unlock()?;
+        let mut cmd = suiup_command(install_args, &test_env);
+        cmd.env("SUIUP_LOCK_TIMEOUT", "1");
+        cmd.assert().success();
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_install_prompts_without_the_state_lock() -> Result<()> {
+        use std::io::{Read, Write};
+        use std::process::Stdio;
+
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let archive = create_release_archive(
+            test_env.temp_dir.path(),
+            "sui-mainnet-v1.44.2-ubuntu-x86_64.tgz",
+            &["sui"],
+            "1.44.2",
+        )?;
+
+        let mut install = std::process::Command::new(env!("CARGO_BIN_EXE_suiup"))
+            .args(["install", "sui", "--from-file"])
+            .arg(&archive)
+            .env(DATA_HOME, &test_env.data_dir)
+            .env(CONFIG_HOME, &test_env.config_dir)
+            .env(CACHE_HOME, &test_env.cache_dir)
+            .env(HOME, test_env.temp_dir.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut stdout = install.stdout.take().unwrap();
+        let mut output = vec![];
+        let mut byte = [0u8; 1];
+        while !String::from_utf8_lossy(&output).contains("[y/N]") {
+            if stdout.read(&mut byte)? == 0 {
+                break;
+            }
+            output.push(byte[0]);
+        }
+        assert!(String::from_utf8_lossy(&output).contains("set this new installed version"));
+
+        // Other suiup processes can change the state while the question is pending
+        let lock = suiup::lock::FileLock::try_acquire(&test_env.data_dir.join("suiup/suiup.lock"))?;
+        assert!(lock.is_some());
+        drop(lock);
+
+        install.stdin.take().unwrap().write_all(b"y\n")?;
+        assert!(install.wait()?.success());
+        assert!(test_env.bin_dir.join("sui").exists());
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_bundle_create_and_install() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let (os, arch) = detect_os_arch_for_tests();
+        let archive_name = format!("sui-mainnet-v1.44.2-{os}-{arch}.tgz");
+        let releases_dir = test_env.cache_dir.join("suiup/releases");
+        fs::create_dir_all(&releases_dir)?;
+        create_release_archive(&releases_dir, &archive_name, &["sui", "sui-node"], "1.44.2")?;
+
+        // sui and sui-node share the same archive, which is bundled once
+        let bundle = test_env.temp_dir.path().join("bundle.tar");
+        let mut cmd = suiup_command(
+            vec![
+                "bundle",
+                "create",
+                "--out",
+                bundle.to_str().unwrap(),
+                "sui@mainnet-1.44.2",
+                "sui-node@mainnet-1.44.2",
+            ],
+            &test_env,
+        );
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Created bundle"));
+        assert!(bundle.exists());
+
+        // Installing must not depend on the archive already being in the cache
+        fs::remove_dir_all(&releases_dir)?;
+        let mut cmd = suiup_command(
+            vec!["bundle", "install", bundle.to_str().unwrap(), "-y"],
+            &test_env,
+        );
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Installed 2 binaries from bundle"));
+
+        let binaries_dir = test_env.data_dir.join("suiup/binaries/mainnet");
+        assert!(binaries_dir.join("sui-v1.44.2").exists());
+        assert!(binaries_dir.join("sui-node-v1.44.2").exists());
+        assert!(releases_dir.join(&archive_name).exists());
+
+        // A bundle for another platform is rejected
+        let (other_os, other_target) = if os == "windows" {
+            ("linux", "ubuntu")
+        } else {
+            ("windows", "windows")
+        };
+        create_release_archive(
+            &releases_dir,
+            &format!("sui-mainnet-v1.44.2-{other_target}-x86_64.tgz"),
+            &["sui"],
+            "1.44.2",
+        )?;
+        let mut cmd = suiup_command(
+            vec![
+                "bundle",
+                "create",
+                "--out",
+                bundle.to_str().unwrap(),
+                "--os",
+                other_os,
+                "--arch",
+                "x86_64",
+                "sui@mainnet-1.44.2",
+            ],
+            &test_env,
+        );
+        cmd.assert().success();
+        let mut cmd = suiup_command(
+            vec!["bundle", "install", bundle.to_str().unwrap(), "-y"],
+            &test_env,
+        );
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "Bundle was created for {other_target}-x86_64"
+            )));
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_fetch_for_other_target() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let releases_dir = test_env.cache_dir.join("suiup/releases");
+        fs::create_dir_all(&releases_dir)?;
+        create_release_archive(
+            &releases_dir,
+            "sui-mainnet-v1.44.2-windows-x86_64.tgz",
+            &["sui.exe"],
+            "1.44.2",
+        )?;
+        create_release_archive(
+            &releases_dir,
+            "sui-mainnet-v1.44.2-ubuntu-aarch64.tgz",
+            &["sui"],
+            "1.44.2",
+        )?;
+
+        let out_dir = test_env.temp_dir.path().join("out");
+        let mut cmd = suiup_command(
+            vec![
+                "fetch",
+                "sui@mainnet-1.44.2",
+                "--dir",
+                out_dir.to_str().unwrap(),
+                "--os",
+                "windows",
+                "--arch",
+                "amd64",
+            ],
+            &test_env,
+        );
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Target: windows-x86_64"));
+        assert!(out_dir.join("sui.exe").exists());
+
+        let mut cmd = suiup_command(
+            vec![
+                "fetch",
+                "sui@mainnet-1.44.2",
+                "--dir",
+                out_dir.to_str().unwrap(),
+            ],
+            &test_env,
+        );
+        cmd.env("SUIUP_TARGET", "linux-arm64");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Target: ubuntu-aarch64"));
+        assert!(out_dir.join("sui").exists());
+
+        // Fetching neither installs the binaries nor sets defaults
+        assert!(!test_env.data_dir.join("suiup/binaries/mainnet").exists());
+        assert!(!test_env.bin_dir.join("sui").exists());
+
+        // Install is always for the host, whatever the target of fetch is
+        let mut cmd = suiup_command(
+            vec!["install", "sui@mainnet-1.44.2", "--os", "windows", "-y"],
+            &test_env,
+        );
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("unexpected argument '--os'"));
+        let (os, arch) = detect_os_arch_for_tests();
+        create_release_archive(
+            &releases_dir,
+            &format!("sui-mainnet-v1.44.2-{os}-{arch}.tgz"),
+            &["sui"],
+            "1.44.2",
+        )?;
+        let mut cmd = suiup_command(vec!["install", "sui@mainnet-1.44.2", "-y"], &test_env);
+        cmd.env("SUIUP_TARGET", "windows-x86_64");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Target:").not());
+        assert!(
+            test_env
+                .data_dir
+                .join("suiup/binaries/mainnet/sui-v1.44.2")
+                .exists()
+        );
+        assert!(test_env.bin_dir.join("sui").exists());
+
+        let mut cmd = suiup_command(
+            vec!["fetch", "sui@mainnet-1.44.2", "--os", "freebsd"],
+            &test_env,
+        );
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("Unsupported OS: freebsd"));
+
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_install_with_archive_members() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+
+        let (os, arch) = detect_os_arch_for_tests();
+        let releases_dir = test_env.cache_dir.join("suiup/releases");
+        fs::create_dir_all(&releases_dir)?;
+        create_release_archive(
+            &releases_dir,
+            &format!("sui-mainnet-v1.44.2-{os}-{arch}.tgz"),
+            &["sui", "sui-tool", "sui-faucet"],
+            "1.44.2",
+        )?;
+
+        let mut cmd = suiup_command(
+            vec!["install", "sui@mainnet-1.44.2", "--with", "sui-foo"],
+            &test_env,
+        );
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("sui-foo is not shipped with sui"));
+
+        let mut cmd = suiup_command(
+            vec![
+                "install",
+                "sui@mainnet-1.44.2",
+                "--with",
+                "sui-tool,sui-faucet",
+                "-y",
+            ],
+            &test_env,
+        );
+        cmd.assert().success();
+
+        for binary in ["sui", "sui-tool", "sui-faucet"] {
+            assert!(
+                test_env
+                    .data_dir
+                    .join(format!("suiup/binaries/mainnet/{binary}-v1.44.2"))
+                    .exists()
+            );
+            assert!(test_env.bin_dir.join(binary).exists());
+        }
+
+        let mut cmd = suiup_command(vec!["show"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("sui-tool"))
+            .stdout(predicate::str::contains("sui-faucet"));
+
+        // Binaries installed with --with are managed like any other binary
+        create_release_archive(
+            &releases_dir,
+            &format!("sui-mainnet-v1.44.3-{os}-{arch}.tgz"),
+            &["sui", "sui-tool"],
+            "1.44.3",
+        )?;
+        let mut cmd = suiup_command(
+            vec!["install", "sui@mainnet-1.44.3", "--with", "sui-tool", "-y"],
+            &test_env,
+        );
+        cmd.assert().success();
+
+        let mut cmd = suiup_command(vec!["switch", "sui-tool@mainnet-1.44.2"], &test_env);
+        cmd.assert().success().stdout(predicate::str::contains(
+            "Default binary updated to sui-tool@mainnet-v1.44.2",
+        ));
+        let mut cmd = suiup_command(vec!["default", "set", "sui-tool@mainnet-1.44.3"], &test_env);
+        cmd.assert().success().stdout(predicate::str::contains(
+            "Default binary updated to sui-tool@mainnet-v1.44.3",
+        ));
+
+        let mut cmd = suiup_command(vec!["remove", "sui-tool"], &test_env);
+        cmd.assert().success();
+        for version in ["v1.44.2", "v1.44.3"] {
+            assert!(
+                !test_env
+                    .data_dir
+                    .join(format!("suiup/binaries/mainnet/sui-tool-{version}"))
+                    .exists()
+            );
+        }
+        assert!(!test_env.bin_dir.join("sui-tool").exists());
+        assert!(test_env.bin_dir.join("sui").exists());
+
+        // Undo reinstalls the member from the archive of its binary
+        let mut cmd = suiup_command(vec!["undo", "-y"], &test_env);
+        cmd.assert().success();
+        for version in ["v1.44.2", "v1.44.3"] {
+            assert!(
+                test_env
+                    .data_dir
+                    .join(format!("suiup/binaries/mainnet/sui-tool-{version}"))
+                    .exists()
+            );
+        }
+        assert!(test_env.bin_dir.join("sui-tool").exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_install_with_members_status_and_update() -> Result<()> {
+        let test_env = TestEnv::new()?;
+        test_env.initialize_paths()?;
+        let (os, arch) = detect_os_arch_for_tests();
+
+        let releases = test_env.temp_dir.path().join("releases");
+        let add_release = |version: &str| -> Result<()> {
+            let tag_dir = releases.join(format!("testnet-v{version}"));
+            fs::create_dir_all(&tag_dir)?;
+            create_release_archive(
+                &tag_dir,
+                &format!("pack-tool-testnet-v{version}-{os}-{arch}.tgz"),
+                &["pack-tool", "pack-helper"],
+                version,
+            )?;
+            Ok(())
+        };
+        add_release("1.1.0")?;
+        let user_dir = test_env.config_dir.join("suiup/binaries");
+        fs::create_dir_all(&user_dir)?;
+        fs::write(
+            user_dir.join("pack-tool.toml"),
+            format!(
+                r#"name = "pack-tool"
+description = "Tool shipped with a helper"
+repository = "example/pack-tool"
+installation_type = "archive"
+network_based = true
+supported_networks = ["testnet"]
+asset_pattern = "{{name}}-{{network}}-{{version}}-{{os}}-{{arch}}.tgz"
+archive_members = ["pack-helper"]
+release_source = {{ type = "filesystem", path = '{}' }}
+"#,
+                releases.display()
+            ),
+        )?;
+
+        let mut cmd = suiup_command(
+            vec!["install", "pack-tool", "--with", "pack-helper", "-y"],
+            &test_env,
+        );
+        cmd.assert().success();
+
+        // Members follow the releases of the binary they ship with
+        add_release("1.2.0")?;
+        let mut cmd = suiup_command(vec!["status"], &test_env);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("pack-helper"))
+            .stdout(predicate::str::contains(
+                "suiup install pack-tool@testnet-v1.2.0 --with pack-helper",
+            ));
+
+        let mut cmd = suiup_command(vec!["update", "pack-helper", "-y"], &test_env);
+        cmd.assert().success().stdout(predicate::str::contains(
+            "pack-helper is outdated. Local: v1.1.0, Latest: v1.2.0",
+        ));
+        assert!(
+            test_env
+                .data_dir
+                .join("suiup/binaries/testnet/pack-helper-v1.2.0")
+                .exists()
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_install_nightly() -> Result<()> {
         Ok(())
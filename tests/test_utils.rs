@@ -162,7 +162,37 @@ impl Drop for TestEnv {
     }
 }
 
-fn detect_os_arch_for_tests() -> (&'static str, &'static str) {
+/// Creates a gzip-compressed release archive with one shell script per binary, printing the
+/// given version, and returns its path.
+pub fn create_release_archive(
+    dir: &Path,
+    archive_name: &str,
+    binaries: &[&str],
+    version: &str,
+) -> Result<PathBuf> {
+    let archive_path = dir.join(archive_name);
+    let file = std::fs::File::create(&archive_path)?;
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+
+    for binary in binaries {
+        let script = format!("#!/bin/sh\necho \"{binary} {version}\"\n");
+        let mut header = tar::Header::new_gnu();
+        header.set_size(script.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        #[cfg(windows)]
+        let entry_name = format!("{binary}.exe");
+        #[cfg(not(windows))]
+        let entry_name = binary.to_string();
+        builder.append_data(&mut header, entry_name, script.as_bytes())?;
+    }
+
+    builder.into_inner()?.finish()?;
+    Ok(archive_path)
+}
+
+pub fn detect_os_arch_for_tests() -> (&'static str, &'static str) {
     let os = if cfg!(target_os = "macos") {
         "macos"
     } else if cfg!(target_os = "linux") {